@@ -27,6 +27,8 @@ cfg_if::cfg_if! {
 impl crate::service::Client<RpcServiceClient<Channel>> for AssetsClient {
     type ReadyRequest = ReadyRequest;
     type ReadyResponse = ReadyResponse;
+    type WatchAssetsRequest = WatchAssetsRequest;
+    type AssetEvent = AssetEvent;
 
     async fn is_ready(
         &self,
@@ -36,6 +38,15 @@ impl crate::service::Client<RpcServiceClient<Channel>> for AssetsClient {
         grpc_debug!("(is_ready) request: {:?}", request);
         self.get_client().await?.is_ready(request).await
     }
+
+    async fn watch_assets(
+        &self,
+        request: Self::WatchAssetsRequest,
+    ) -> Result<tonic::Response<tonic::codec::Streaming<Self::AssetEvent>>, tonic::Status> {
+        grpc_info!("(watch_assets) {} client.", self.get_name());
+        grpc_debug!("(watch_assets) request: {:?}", request);
+        self.get_client().await?.watch_assets(request).await
+    }
 }
 
 #[cfg(feature = "stub_client")]
@@ -43,6 +54,8 @@ impl crate::service::Client<RpcServiceClient<Channel>> for AssetsClient {
 impl crate::service::Client<RpcServiceClient<Channel>> for AssetsClient {
     type ReadyRequest = ReadyRequest;
     type ReadyResponse = ReadyResponse;
+    type WatchAssetsRequest = WatchAssetsRequest;
+    type AssetEvent = AssetEvent;
 
     async fn is_ready(
         &self,
@@ -52,6 +65,18 @@ impl crate::service::Client<RpcServiceClient<Channel>> for AssetsClient {
         grpc_debug!("(is_ready MOCK) request: {:?}", request);
         Ok(tonic::Response::new(ReadyResponse { ready: true }))
     }
+
+    async fn watch_assets(
+        &self,
+        request: Self::WatchAssetsRequest,
+    ) -> Result<tonic::Response<tonic::codec::Streaming<Self::AssetEvent>>, tonic::Status> {
+        grpc_warn!("(watch_assets MOCK) {} client.", self.get_name());
+        grpc_debug!("(watch_assets MOCK) request: {:?}", request);
+        // A streaming response can only be created from a server connection
+        Err(tonic::Status::unimplemented(
+            "watch_assets is not available for the stub client",
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -89,4 +114,28 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap().into_inner().ready, true);
     }
+
+    #[tokio::test]
+    #[cfg(all(feature = "stub_backends", not(feature = "stub_client")))]
+    async fn test_client_watch_assets_request() {
+        let name = "assets";
+        let (server_host, server_port) =
+            lib_common::grpc::get_endpoint_from_env("GRPC_HOST", "GRPC_PORT");
+
+        let client: AssetsClient = GrpcClient::new_client(&server_host, server_port, name);
+        assert_eq!(client.get_name(), name);
+
+        let result = client.watch_assets(WatchAssetsRequest::default()).await;
+        println!("{:?}", result);
+        assert!(result.is_ok());
+
+        // The stub server returns a finite stream of mock events
+        let mut stream = result.unwrap().into_inner();
+        let mut events = vec![];
+        while let Some(event) = stream.message().await.unwrap() {
+            events.push(event);
+        }
+        assert_eq!(events.len(), 2);
+        assert!(events[0].sequence < events[1].sequence);
+    }
 }
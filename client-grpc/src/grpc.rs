@@ -14,6 +14,124 @@ pub struct ReadyResponse {
     #[prost(bool, tag = "1")]
     pub ready: bool,
 }
+/// Watch Assets Request object
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchAssetsRequest {
+    /// Only stream events of assets with these ids, all assets if empty
+    #[prost(string, repeated, tag = "1")]
+    pub asset_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Only stream events of these kinds of assets, all kinds if empty
+    #[prost(enumeration = "AssetKind", repeated, tag = "2")]
+    pub kinds: ::prost::alloc::vec::Vec<i32>,
+    /// Resume the stream after this sequence number, replaying the events
+    /// that have been missed. Only new events are streamed if not provided.
+    #[prost(uint64, optional, tag = "3")]
+    pub after_sequence: ::core::option::Option<u64>,
+    /// Epoch of the events the stream is resumed from. Resuming is rejected
+    /// if the sequence numbers have been reset since, by a restart.
+    #[prost(string, optional, tag = "4")]
+    pub epoch: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// Asset change event
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AssetEvent {
+    /// Sequence number of the event
+    #[prost(uint64, tag = "1")]
+    pub sequence: u64,
+    /// The kind of asset that changed
+    #[prost(enumeration = "AssetKind", tag = "2")]
+    pub kind: i32,
+    /// The id of the asset that changed
+    #[prost(string, tag = "3")]
+    pub asset_id: ::prost::alloc::string::String,
+    /// The id of the operator owning the asset, if known
+    #[prost(string, optional, tag = "4")]
+    pub operator_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// The type of change
+    #[prost(enumeration = "AssetAction", tag = "5")]
+    pub action: i32,
+    /// Time at which the change was recorded
+    #[prost(message, optional, tag = "6")]
+    pub timestamp: ::core::option::Option<::prost_types::Timestamp>,
+    /// JSON encoded data describing the change, if any
+    #[prost(string, optional, tag = "7")]
+    pub data: ::core::option::Option<::prost::alloc::string::String>,
+    /// Epoch of the sequence numbers, changing when they are reset
+    #[prost(string, tag = "8")]
+    pub epoch: ::prost::alloc::string::String,
+}
+/// Kind of asset
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum AssetKind {
+    /// Aircraft
+    Aircraft = 0,
+    /// Vertiport
+    Vertiport = 1,
+    /// Vertipad
+    Vertipad = 2,
+    /// Asset group
+    AssetGroup = 3,
+}
+impl AssetKind {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            AssetKind::Aircraft => "ASSET_KIND_AIRCRAFT",
+            AssetKind::Vertiport => "ASSET_KIND_VERTIPORT",
+            AssetKind::Vertipad => "ASSET_KIND_VERTIPAD",
+            AssetKind::AssetGroup => "ASSET_KIND_ASSET_GROUP",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "ASSET_KIND_AIRCRAFT" => Some(Self::Aircraft),
+            "ASSET_KIND_VERTIPORT" => Some(Self::Vertiport),
+            "ASSET_KIND_VERTIPAD" => Some(Self::Vertipad),
+            "ASSET_KIND_ASSET_GROUP" => Some(Self::AssetGroup),
+            _ => None,
+        }
+    }
+}
+/// Type of change of an asset
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum AssetAction {
+    /// The asset has been registered
+    Created = 0,
+    /// The asset has been updated
+    Updated = 1,
+    /// The asset has been removed
+    Removed = 2,
+}
+impl AssetAction {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            AssetAction::Created => "ASSET_ACTION_CREATED",
+            AssetAction::Updated => "ASSET_ACTION_UPDATED",
+            AssetAction::Removed => "ASSET_ACTION_REMOVED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "ASSET_ACTION_CREATED" => Some(Self::Created),
+            "ASSET_ACTION_UPDATED" => Some(Self::Updated),
+            "ASSET_ACTION_REMOVED" => Some(Self::Removed),
+            _ => None,
+        }
+    }
+}
 /// Generated client implementations.
 #[cfg(not(tarpaulin_include))]
 pub mod rpc_service_client {
@@ -121,5 +239,31 @@ pub mod rpc_service_client {
             req.extensions_mut().insert(GrpcMethod::new("grpc.RpcService", "isReady"));
             self.inner.unary(req, path, codec).await
         }
+        /// Stream change events of assets
+        pub async fn watch_assets(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WatchAssetsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::AssetEvent>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/watchAssets",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "watchAssets"));
+            self.inner.server_streaming(req, path, codec).await
+        }
     }
 }
@@ -11,6 +11,10 @@ where
     type ReadyRequest;
     /// The type expected for ReadyResponse structs.
     type ReadyResponse;
+    /// The type expected for WatchAssetsRequest structs.
+    type WatchAssetsRequest;
+    /// The type expected for AssetEvent structs.
+    type AssetEvent;
 
     /// Returns a [`tonic::Response`] containing a [`ReadyResponse`](Self::ReadyResponse)
     /// Takes an [`ReadyRequest`](Self::ReadyRequest).
@@ -38,4 +42,45 @@ where
         &self,
         request: Self::ReadyRequest,
     ) -> Result<tonic::Response<Self::ReadyResponse>, tonic::Status>;
+
+    /// Returns a [`tonic::Response`] containing a stream of [`AssetEvent`](Self::AssetEvent)s
+    /// Takes a [`WatchAssetsRequest`](Self::WatchAssetsRequest).
+    ///
+    /// The stream stays open until it is dropped by the client or the
+    /// server shuts down, or the client can not keep up with the events.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`tonic::Status`] with [`tonic::Code::OutOfRange`] if the events
+    /// after the requested `after_sequence` are no longer available.
+    /// The stream ends with [`tonic::Code::Aborted`] if the client missed
+    /// events, it should then resume after the sequence in the message.
+    ///
+    /// # Examples
+    /// ```
+    /// use lib_common::grpc::get_endpoint_from_env;
+    /// use svc_assets_client_grpc::prelude::*;
+    ///
+    /// async fn example () -> Result<(), Box<dyn std::error::Error>> {
+    ///     let (host, port) = get_endpoint_from_env("SERVER_HOSTNAME", "SERVER_PORT_GRPC");
+    ///     let client = AssetsClient::new_client(&host, port, "assets");
+    ///     let mut stream = client
+    ///         .watch_assets(assets::WatchAssetsRequest {
+    ///             asset_ids: vec![],
+    ///             kinds: vec![assets::AssetKind::Vertipad as i32],
+    ///             after_sequence: None,
+    ///             epoch: None,
+    ///         })
+    ///         .await?
+    ///         .into_inner();
+    ///     while let Some(event) = stream.message().await? {
+    ///         println!("EVENT={:?}", event);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn watch_assets(
+        &self,
+        request: Self::WatchAssetsRequest,
+    ) -> Result<tonic::Response<tonic::codec::Streaming<Self::AssetEvent>>, tonic::Status>;
 }
@@ -64,7 +64,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     {
         let data = vehicle::Data {
             vehicle_model_id: "Jet".to_string(),
-            registration_number: "N2133423".to_string(),
+            registration_number: "N21334".to_string(),
             serial_number: "1234".to_string(),
             description: None,
             last_maintenance: Some(chrono::Utc::now().into()),
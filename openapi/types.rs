@@ -96,6 +96,8 @@ pub struct UpdateVertipadPayload {
     /// Indicates if the Vertipad is in business.
     pub enabled: Option<bool>,
     /// Indicates if the Vertipad is currently occupied.
+    ///
+    /// Can not be updated, use the occupy and release requests instead.
     pub occupied: Option<bool>,
     /// Optional RRULE data string to indicate the Vertipad's available days and hours.
     pub schedule: Option<Option<String>>,
@@ -104,3 +106,116 @@ pub struct UpdateVertipadPayload {
     /// If any other fields are provided, they will be ignored.
     pub mask: Vec<String>,
 }
+
+/// Request to occupy a Vertipad.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct OccupyVertipadPayload {
+    /// The UUID v4 of the Aircraft occupying the Vertipad.
+    pub aircraft_id: String,
+    /// The UUID v4 of the flight the Vertipad is occupied for, if any.
+    pub flight_id: Option<String>,
+}
+
+/// Request to release a Vertipad.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct ReleaseVertipadPayload {
+    /// The UUID v4 of the Aircraft occupying the Vertipad.
+    pub aircraft_id: String,
+}
+
+/// Request to reserve a Vertipad.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct CreateReservationPayload {
+    /// The UUID v4 of the Aircraft the Vertipad is reserved for.
+    pub aircraft_id: String,
+    /// The UUID v4 of the flight the Vertipad is reserved for, if any.
+    pub flight_id: Option<String>,
+    /// Start of the reserved window as an RFC 3339 timestamp.
+    pub start: String,
+    /// End of the reserved window as an RFC 3339 timestamp.
+    pub end: String,
+}
+
+/// Request to extend a Vertipad reservation.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct ExtendReservationPayload {
+    /// New end of the reserved window as an RFC 3339 timestamp.
+    ///
+    /// Must be later than the current end.
+    pub end: String,
+}
+
+/// Options to get an Asset.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct GetAssetParams {
+    /// Return the Asset as it was at this time (RFC 3339), reconstructed
+    /// from its change history.
+    pub as_of: Option<String>,
+}
+
+/// Options to remove an Asset.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct RemoveAssetParams {
+    /// The actor removing the Asset, recorded with the removal.
+    pub deleted_by: Option<String>,
+    /// The reason for removing the Asset, recorded with the removal.
+    pub reason: Option<String>,
+}
+
+/// Options to remove a Vertiport.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct RemoveVertiportParams {
+    /// Also remove the Vertipads of the Vertiport and clear the hangar
+    /// references of the Aircraft hangared at the Vertiport.
+    ///
+    /// Without this, the removal is refused if any of those exist.
+    #[serde(default)]
+    pub cascade: bool,
+    /// Only list the affected Vertipads and Aircraft, without removing
+    /// or updating anything.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// The actor removing the Vertiport, recorded with the removal.
+    pub deleted_by: Option<String>,
+    /// The reason for removing the Vertiport, recorded with the removal.
+    pub reason: Option<String>,
+}
+
+/// Request to add or replace a Vehicle Model in the catalog.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct VehicleModelPayload {
+    /// The manufacturer of the Vehicle Model.
+    pub manufacturer: String,
+    /// The name of the Vehicle Model.
+    pub model_name: String,
+    /// Maximum payload in kilograms.
+    pub max_payload_kg: f64,
+    /// Maximum range in kilometers.
+    pub max_range_km: f64,
+    /// Cruise speed in kilometers per hour.
+    pub cruise_speed_kph: f64,
+    /// Time in minutes to fully charge or refuel the Vehicle.
+    pub charge_time_minutes: f64,
+    /// Length in meters.
+    pub length_m: f64,
+    /// Width in meters, including rotors and wings.
+    pub width_m: f64,
+    /// Height in meters.
+    pub height_m: f64,
+}
+
+/// Mission to find capable Aircraft for.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct CapableAircraftParams {
+    /// Mass of the payload in kilograms.
+    pub payload_kg: f64,
+    /// Distance of the flight in kilometers.
+    pub distance_km: f64,
+    /// The UUID of the departure Vertiport.
+    pub vertiport_id: String,
+    /// Also include Aircraft hangared at Vertiports within this distance
+    /// in kilometers of the departure Vertiport.
+    pub radius_km: Option<f64>,
+    /// Departure time (RFC 3339), now if not provided.
+    pub departure_time: Option<String>,
+}
@@ -0,0 +1,966 @@
+//! Audit Log
+//! append-only record of all changes made to assets
+//!
+//! Every registration, update, removal, restore and purge of an asset is
+//! recorded with the actor, the request id and the values of the changed
+//! fields before and after the change. Entries are appended to a local JSON
+//! lines file and are never modified or removed.
+
+#[macro_use]
+pub mod macros;
+
+use crate::events::AssetKind;
+use crate::metrics::Metrics;
+use chrono::{DateTime, Utc};
+use hyper::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::OnceCell;
+use utoipa::{IntoParams, ToSchema};
+
+/// Request header identifying the actor making a change.
+pub const ACTOR_HEADER: &str = "x-actor";
+
+pub use crate::request_id::REQUEST_ID_HEADER;
+
+pub(crate) static AUDIT_LOG: OnceCell<AuditLog> = OnceCell::const_new();
+
+/// Returns AUDIT_LOG, an [`AuditLog`] configured using a Config object
+/// generated from environment variables.
+/// Initializes AUDIT_LOG if it hasn't been initialized yet, in memory if
+/// the audit file can not be used.
+pub async fn get_audit_log() -> &'static AuditLog {
+    AUDIT_LOG
+        .get_or_init(|| async move {
+            let config = crate::Config::try_from_env().unwrap_or_default();
+            let metrics = crate::metrics::get_metrics().await.clone();
+            match AuditLog::open(&config.audit_file, metrics.clone()) {
+                Ok(log) => log,
+                Err(e) => {
+                    audit_error!("(get_audit_log) {}, changes will be kept in memory.", e);
+                    AuditLog::in_memory(metrics)
+                }
+            }
+        })
+        .await
+}
+
+/// Initializes AUDIT_LOG with the audit file configured in `config`,
+/// failing if the file can not be used.
+pub async fn init_audit_log(config: &crate::Config) -> Result<&'static AuditLog, std::io::Error> {
+    AUDIT_LOG
+        .get_or_try_init(|| async move {
+            let metrics = crate::metrics::get_metrics().await.clone();
+            AuditLog::open(&config.audit_file, metrics)
+        })
+        .await
+}
+
+/// Type of change recorded in the audit log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum AuditAction {
+    /// The asset has been registered.
+    Created,
+    /// One or more fields of the asset have been updated.
+    Updated,
+    /// The asset has been removed.
+    Removed,
+    /// The asset has been restored after its removal.
+    Restored,
+    /// The asset has been permanently deleted once its retention period
+    /// passed.
+    Purged,
+}
+
+/// The value of a single field before and after a change.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FieldChange {
+    /// The name of the field.
+    pub field: String,
+    /// The value before the change, `null` for new assets.
+    #[schema(value_type = Option<Object>)]
+    pub before: serde_json::Value,
+    /// The value after the change.
+    #[schema(value_type = Option<Object>)]
+    pub after: serde_json::Value,
+}
+
+/// A single change recorded in the audit log.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuditEntry {
+    /// Monotonically increasing sequence number of the entry.
+    pub sequence: u64,
+    /// Time at which the change was recorded.
+    pub timestamp: DateTime<Utc>,
+    /// The kind of asset that changed.
+    pub kind: AssetKind,
+    /// The UUID of the asset that changed.
+    pub asset_id: String,
+    /// The type of change.
+    pub action: AuditAction,
+    /// The actor who made the change, if known.
+    pub actor: Option<String>,
+    /// The id of the request which made the change, if known.
+    pub request_id: Option<String>,
+    /// The reason given for the change, if any.
+    pub reason: Option<String>,
+    /// The changed fields.
+    pub changes: Vec<FieldChange>,
+}
+
+/// Filter for the audit log query.
+///
+/// Fields which are not provided match all entries.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct AuditFilter {
+    /// Only return changes of this kind of asset.
+    pub kind: Option<AssetKind>,
+    /// Only return changes of the asset with this UUID.
+    pub asset_id: Option<String>,
+    /// Only return changes made by this actor.
+    pub actor: Option<String>,
+    /// Only return changes of this type.
+    pub action: Option<AuditAction>,
+    /// Only return changes to this field.
+    pub field: Option<String>,
+    /// Only return changes recorded at or after this time (RFC 3339).
+    pub since: Option<DateTime<Utc>>,
+    /// Only return changes recorded before this time (RFC 3339).
+    pub until: Option<DateTime<Utc>>,
+    /// Only return the most recent `limit` matching changes, 100 by
+    /// default and at most 1000 when queried through the REST API.
+    pub limit: Option<usize>,
+}
+
+impl AuditFilter {
+    /// Check if the given entry passes this filter, ignoring the limit.
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        if let Some(kind) = &self.kind {
+            if &entry.kind != kind {
+                return false;
+            }
+        }
+        if let Some(asset_id) = &self.asset_id {
+            if &entry.asset_id != asset_id {
+                return false;
+            }
+        }
+        if let Some(actor) = &self.actor {
+            if entry.actor.as_ref() != Some(actor) {
+                return false;
+            }
+        }
+        if let Some(action) = &self.action {
+            if &entry.action != action {
+                return false;
+            }
+        }
+        if let Some(field) = &self.field {
+            if !entry.changes.iter().any(|change| &change.field == field) {
+                return false;
+            }
+        }
+        if let Some(since) = &self.since {
+            if &entry.timestamp < since {
+                return false;
+            }
+        }
+        if let Some(until) = &self.until {
+            if &entry.timestamp >= until {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// The state of an asset at a point in time, reconstructed from the audit
+/// log.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PastState {
+    /// The asset existed with these fields.
+    Existing(serde_json::Value),
+    /// The asset was not registered yet, or was removed.
+    Missing,
+    /// The asset is older than its history in the audit log, which starts
+    /// at the time of its first entry, if any.
+    Unknown(Option<DateTime<Utc>>),
+}
+
+/// Who made a change and why.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AuditContext {
+    /// The actor making the change, if known.
+    pub actor: Option<String>,
+    /// The id of the request making the change, if known.
+    pub request_id: Option<String>,
+    /// The reason given for the change, if any.
+    pub reason: Option<String>,
+}
+
+impl AuditContext {
+    /// Create a new [`AuditContext`] from the [`ACTOR_HEADER`] and
+    /// [`REQUEST_ID_HEADER`] request headers.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+
+        Self {
+            actor: header(ACTOR_HEADER),
+            request_id: header(REQUEST_ID_HEADER),
+            reason: None,
+        }
+    }
+
+    /// Use the actor and reason provided with the removal of an asset,
+    /// keeping the actor of the request if no actor is provided.
+    pub fn with_removal(self, deleted_by: Option<String>, reason: Option<String>) -> Self {
+        Self {
+            actor: deleted_by.or(self.actor),
+            reason,
+            ..self
+        }
+    }
+}
+
+/// Returns the fields of a new asset as changes from `null`.
+///
+/// Fields without a value are left out.
+pub fn created_changes<T: Serialize>(data: &T) -> Vec<FieldChange> {
+    let serde_json::Value::Object(fields) = serde_json::to_value(data).unwrap_or_default() else {
+        return vec![];
+    };
+
+    fields
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(field, value)| FieldChange {
+            field,
+            before: serde_json::Value::Null,
+            after: value,
+        })
+        .collect()
+}
+
+/// Returns the values of all fields which differ before and after an
+/// update.
+pub fn changed_fields<T: Serialize>(before: &T, after: &T) -> Vec<FieldChange> {
+    let serde_json::Value::Object(before) = serde_json::to_value(before).unwrap_or_default() else {
+        return vec![];
+    };
+    let serde_json::Value::Object(after) = serde_json::to_value(after).unwrap_or_default() else {
+        return vec![];
+    };
+
+    after
+        .into_iter()
+        .filter(|(field, value)| before.get(field) != Some(value))
+        .map(|(field, value)| FieldChange {
+            before: before.get(&field).cloned().unwrap_or_default(),
+            field,
+            after: value,
+        })
+        .collect()
+}
+
+/// Returns the values of the `mask` fields before and after an update.
+pub fn masked_changes<T: Serialize>(before: &T, after: &T, mask: &[String]) -> Vec<FieldChange> {
+    let before = serde_json::to_value(before).unwrap_or_default();
+    let after = serde_json::to_value(after).unwrap_or_default();
+
+    mask.iter()
+        .map(|field| FieldChange {
+            field: field.clone(),
+            before: before.get(field).cloned().unwrap_or_default(),
+            after: after.get(field).cloned().unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Where the entries of an [`AuditLog`] are kept.
+#[derive(Debug)]
+enum Entries {
+    /// All entries, kept in memory only.
+    Memory(Vec<AuditEntry>),
+    /// Entries appended to the audit file at `path`.
+    File {
+        path: PathBuf,
+        file: File,
+        /// Offsets in the file of the entries of each asset, oldest first.
+        offsets: HashMap<(AssetKind, String), Vec<u64>>,
+        /// Length of the file up to the end of the last entry.
+        len: u64,
+    },
+}
+
+#[derive(Debug)]
+struct AuditState {
+    entries: Entries,
+    last_sequence: u64,
+}
+
+/// Append-only log of all changes made to assets.
+///
+/// Entries are appended to an audit file, of which only the offsets of the
+/// entries of each asset are kept in memory. If no file path is provided,
+/// the log is kept in memory only.
+#[derive(Clone, Debug)]
+pub struct AuditLog {
+    state: Arc<Mutex<AuditState>>,
+    metrics: Metrics,
+}
+
+impl AuditLog {
+    /// Open the audit log stored at `path`, indexing all recorded entries.
+    ///
+    /// The log is kept in memory only if `path` is empty. Fails if the file
+    /// can not be read or appended to.
+    pub fn open(path: &str, metrics: Metrics) -> Result<Self, std::io::Error> {
+        if path.is_empty() {
+            audit_warn!(
+                "(AuditLog::open) no audit file configured, changes will be kept in memory."
+            );
+            return Ok(Self::in_memory(metrics));
+        }
+
+        Self::try_open(PathBuf::from(path), metrics)
+            .map_err(|e| std::io::Error::new(e.kind(), format!("audit file [{}]: {}", path, e)))
+    }
+
+    /// Create a new, empty, in-memory audit log.
+    pub fn in_memory(metrics: Metrics) -> Self {
+        Self::new(
+            AuditState {
+                entries: Entries::Memory(vec![]),
+                last_sequence: 0,
+            },
+            metrics,
+        )
+    }
+
+    fn new(state: AuditState, metrics: Metrics) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            metrics,
+        }
+    }
+
+    fn try_open(path: PathBuf, metrics: Metrics) -> Result<Self, std::io::Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)?;
+        let mut offsets: HashMap<(AssetKind, String), Vec<u64>> = HashMap::new();
+        let mut last_sequence = 0;
+        let mut len = 0;
+        let mut count = 0;
+
+        let mut reader = BufReader::new(&file);
+        let mut line = vec![];
+        for line_number in 1.. {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)? as u64;
+            if read == 0 {
+                break;
+            }
+            if line.last() != Some(&b'\n') {
+                // A partially written last line is expected after a crash
+                audit_warn!(
+                    "(AuditLog::try_open) dropping incomplete entry on line {}.",
+                    line_number
+                );
+                break;
+            }
+            let offset = len;
+            len += read;
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            match serde_json::from_slice::<AuditEntry>(&line) {
+                Ok(entry) => {
+                    last_sequence = last_sequence.max(entry.sequence);
+                    offsets
+                        .entry((entry.kind, entry.asset_id))
+                        .or_default()
+                        .push(offset);
+                    count += 1;
+                }
+                Err(e) => {
+                    audit_warn!(
+                        "(AuditLog::try_open) skipping invalid entry on line {}: {}",
+                        line_number,
+                        e
+                    );
+                }
+            }
+        }
+        // New entries are appended right after the last complete line
+        file.set_len(len)?;
+
+        audit_info!(
+            "(AuditLog::try_open) opened audit file [{}] with {} entries.",
+            path.display(),
+            count
+        );
+        Ok(Self::new(
+            AuditState {
+                entries: Entries::File {
+                    path,
+                    file,
+                    offsets,
+                    len,
+                },
+                last_sequence,
+            },
+            metrics,
+        ))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, AuditState> {
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Record a change of an asset.
+    ///
+    /// Errors writing to the audit file are logged and counted in the
+    /// metrics, leaving a gap in the sequence numbers. They are not
+    /// returned, since the change itself has already been stored by the
+    /// time this is called.
+    pub fn record(
+        &self,
+        kind: AssetKind,
+        asset_id: &str,
+        action: AuditAction,
+        context: &AuditContext,
+        changes: Vec<FieldChange>,
+    ) -> AuditEntry {
+        let mut state = self.lock();
+
+        let entry = AuditEntry {
+            sequence: state.last_sequence + 1,
+            timestamp: Utc::now(),
+            kind,
+            asset_id: asset_id.to_string(),
+            action,
+            actor: context.actor.clone(),
+            request_id: context.request_id.clone(),
+            reason: context.reason.clone(),
+            changes,
+        };
+        state.last_sequence = entry.sequence;
+
+        audit_debug!("(record) {:?}", entry);
+        match &mut state.entries {
+            Entries::Memory(entries) => entries.push(entry.clone()),
+            Entries::File {
+                file, offsets, len, ..
+            } => match write_entry(file, &entry) {
+                Ok(written) => {
+                    offsets
+                        .entry((kind, entry.asset_id.clone()))
+                        .or_default()
+                        .push(*len);
+                    *len += written;
+                }
+                Err(e) => {
+                    audit_error!(
+                        "(record) could not write entry [{}] to audit file: {}",
+                        entry.sequence,
+                        e
+                    );
+                    self.metrics.record_audit_write_failure();
+                    // Drop a partially written line, so the next entry
+                    // starts on a line of its own
+                    if let Err(e) = file.set_len(*len) {
+                        audit_error!("(record) could not truncate audit file: {}", e);
+                    }
+                }
+            },
+        }
+
+        entry
+    }
+
+    /// Returns all changes of an asset, oldest first.
+    pub fn history(
+        &self,
+        kind: AssetKind,
+        asset_id: &str,
+    ) -> Result<Vec<AuditEntry>, std::io::Error> {
+        self.query(&AuditFilter {
+            kind: Some(kind),
+            asset_id: Some(asset_id.to_string()),
+            ..Default::default()
+        })
+    }
+
+    /// Reconstruct the state of an asset at `as_of` by rolling back all
+    /// changes recorded after `as_of`, starting from its `current` state.
+    ///
+    /// `removed` indicates if the asset is currently removed. The state is
+    /// unknown before the first entry of an asset registered before the
+    /// audit log, or at any time for an asset without entries.
+    pub fn state_at(
+        &self,
+        kind: AssetKind,
+        asset_id: &str,
+        current: serde_json::Value,
+        removed: bool,
+        as_of: DateTime<Utc>,
+    ) -> Result<PastState, std::io::Error> {
+        let history = self.history(kind, asset_id)?;
+        let Some(first) = history.first() else {
+            return Ok(PastState::Unknown(None));
+        };
+        if as_of < first.timestamp && first.action != AuditAction::Created {
+            return Ok(PastState::Unknown(Some(first.timestamp)));
+        }
+
+        let mut state = current;
+        let mut removed = removed;
+        for entry in history
+            .iter()
+            .rev()
+            .take_while(|entry| entry.timestamp > as_of)
+        {
+            match entry.action {
+                AuditAction::Created => return Ok(PastState::Missing),
+                AuditAction::Updated => {
+                    if let serde_json::Value::Object(fields) = &mut state {
+                        for change in &entry.changes {
+                            fields.insert(change.field.clone(), change.before.clone());
+                        }
+                    }
+                }
+                AuditAction::Removed => removed = false,
+                AuditAction::Restored | AuditAction::Purged => removed = true,
+            }
+        }
+
+        match removed {
+            true => Ok(PastState::Missing),
+            false => Ok(PastState::Existing(state)),
+        }
+    }
+
+    /// Returns the changes passing the filter, oldest first.
+    ///
+    /// The changes of a single asset are read from their offsets in the
+    /// audit file, other queries read the whole file.
+    pub fn query(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>, std::io::Error> {
+        let state = self.lock();
+        let (path, asset_offsets, len) = match &state.entries {
+            Entries::Memory(entries) => {
+                let mut kept = VecDeque::new();
+                for entry in entries.iter().filter(|entry| filter.matches(entry)) {
+                    keep_last(&mut kept, entry.clone(), filter.limit);
+                }
+                return Ok(kept.into());
+            }
+            Entries::File {
+                path, offsets, len, ..
+            } => {
+                let asset_offsets = filter.asset_id.as_ref().map(|asset_id| {
+                    let mut found: Vec<u64> = offsets
+                        .iter()
+                        .filter(|((kind, id), _)| {
+                            id == asset_id && (filter.kind.is_none() || filter.kind == Some(*kind))
+                        })
+                        .flat_map(|(_, offsets)| offsets.iter().copied())
+                        .collect();
+                    found.sort_unstable();
+                    found
+                });
+                (path.clone(), asset_offsets, *len)
+            }
+        };
+        // Entries are never modified, so the file can be read while new
+        // ones are appended
+        drop(state);
+
+        let mut kept = VecDeque::new();
+        match asset_offsets {
+            Some(offsets) => {
+                for entry in read_entries(&path, &offsets)? {
+                    if filter.matches(&entry) {
+                        keep_last(&mut kept, entry, filter.limit);
+                    }
+                }
+            }
+            None => {
+                let reader = BufReader::new(File::open(&path)?.take(len));
+                for line in reader.split(b'\n') {
+                    match serde_json::from_slice::<AuditEntry>(&line?) {
+                        Ok(entry) if filter.matches(&entry) => {
+                            keep_last(&mut kept, entry, filter.limit)
+                        }
+                        // Invalid entries have been reported when opening the file
+                        _ => continue,
+                    }
+                }
+            }
+        }
+        Ok(kept.into())
+    }
+}
+
+/// Add an entry to the `kept` entries, only keeping the most recent `limit`
+/// entries if provided.
+fn keep_last(kept: &mut VecDeque<AuditEntry>, entry: AuditEntry, limit: Option<usize>) {
+    kept.push_back(entry);
+    if matches!(limit, Some(limit) if kept.len() > limit) {
+        kept.pop_front();
+    }
+}
+
+/// Read the entries at `offsets` in the audit file at `path`.
+fn read_entries(path: &Path, offsets: &[u64]) -> Result<Vec<AuditEntry>, std::io::Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut line = vec![];
+    offsets
+        .iter()
+        .map(|offset| {
+            reader.seek(SeekFrom::Start(*offset))?;
+            line.clear();
+            reader.read_until(b'\n', &mut line)?;
+            Ok(serde_json::from_slice(&line)?)
+        })
+        .collect()
+}
+
+/// Append an entry to the audit file, returning the number of bytes
+/// written.
+fn write_entry(file: &mut File, entry: &AuditEntry) -> Result<u64, std::io::Error> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    file.sync_data()?;
+    Ok(line.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Serialize)]
+    struct Data {
+        name: String,
+        registration_number: String,
+        description: Option<String>,
+    }
+
+    fn context(actor: &str) -> AuditContext {
+        AuditContext {
+            actor: Some(actor.to_string()),
+            request_id: Some("request".to_string()),
+            reason: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_changes() {
+        crate::get_log_handle().await;
+        ut_info!("(test_changes) Start.");
+
+        let before = Data {
+            name: "Aircraft".to_string(),
+            registration_number: "N12345".to_string(),
+            description: None,
+        };
+        let after = Data {
+            registration_number: "N54321".to_string(),
+            ..before
+        };
+
+        let changes = created_changes(&after);
+        assert_eq!(changes.len(), 2);
+        assert!(changes
+            .iter()
+            .all(|change| change.before == serde_json::Value::Null));
+
+        let before = Data {
+            name: "Aircraft".to_string(),
+            registration_number: "N12345".to_string(),
+            description: None,
+        };
+        let changes = masked_changes(&before, &after, &["registration_number".to_string()]);
+        assert_eq!(
+            changes,
+            vec![FieldChange {
+                field: "registration_number".to_string(),
+                before: json!("N12345"),
+                after: json!("N54321"),
+            }]
+        );
+        assert_eq!(changed_fields(&before, &after), changes);
+
+        ut_info!("(test_changes) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_record_query() {
+        crate::get_log_handle().await;
+        ut_info!("(test_record_query) Start.");
+
+        let log = AuditLog::in_memory(Metrics::new());
+        let change = FieldChange {
+            field: "registration_number".to_string(),
+            before: json!("N12345"),
+            after: json!("N54321"),
+        };
+        log.record(
+            AssetKind::Aircraft,
+            "aircraft",
+            AuditAction::Created,
+            &context("operator"),
+            vec![],
+        );
+        log.record(
+            AssetKind::Aircraft,
+            "aircraft",
+            AuditAction::Updated,
+            &context("regulator"),
+            vec![change.clone()],
+        );
+        let entry = log.record(
+            AssetKind::Vertipad,
+            "pad",
+            AuditAction::Removed,
+            &context("operator").with_removal(None, Some("closed".to_string())),
+            vec![],
+        );
+        assert_eq!(entry.sequence, 3);
+        assert_eq!(entry.actor, Some("operator".to_string()));
+        assert_eq!(entry.reason, Some("closed".to_string()));
+
+        let history = log.history(AssetKind::Aircraft, "aircraft").unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].action, AuditAction::Created);
+        assert_eq!(history[1].changes, vec![change]);
+        assert!(log
+            .history(AssetKind::Vertipad, "aircraft")
+            .unwrap()
+            .is_empty());
+
+        let entries = log
+            .query(&AuditFilter {
+                actor: Some("operator".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        let entries = log
+            .query(&AuditFilter {
+                field: Some("registration_number".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].actor, Some("regulator".to_string()));
+
+        // The limit keeps the most recent entries
+        let entries = log
+            .query(&AuditFilter {
+                limit: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].sequence, 3);
+        let entries = log
+            .query(&AuditFilter {
+                until: Some(entries[0].timestamp),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+
+        ut_info!("(test_record_query) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_state_at() {
+        crate::get_log_handle().await;
+        ut_info!("(test_state_at) Start.");
+
+        let log = AuditLog::in_memory(Metrics::new());
+        let created = log.record(
+            AssetKind::Aircraft,
+            "aircraft",
+            AuditAction::Created,
+            &context("operator"),
+            vec![],
+        );
+        let updated = log.record(
+            AssetKind::Aircraft,
+            "aircraft",
+            AuditAction::Updated,
+            &context("operator"),
+            vec![FieldChange {
+                field: "registration_number".to_string(),
+                before: json!("N12345"),
+                after: json!("N54321"),
+            }],
+        );
+        let removed = log.record(
+            AssetKind::Aircraft,
+            "aircraft",
+            AuditAction::Removed,
+            &context("operator"),
+            vec![],
+        );
+        let current = json!({ "name": "Aircraft", "registration_number": "N54321" });
+        let state_at = |as_of| {
+            log.state_at(
+                AssetKind::Aircraft,
+                "aircraft",
+                current.clone(),
+                true,
+                as_of,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(state_at(removed.timestamp), PastState::Missing);
+        assert_eq!(
+            state_at(updated.timestamp),
+            PastState::Existing(current.clone())
+        );
+        assert_eq!(
+            state_at(created.timestamp),
+            PastState::Existing(json!({ "name": "Aircraft", "registration_number": "N12345" }))
+        );
+        assert_eq!(
+            state_at(created.timestamp - chrono::Duration::seconds(1)),
+            PastState::Missing
+        );
+
+        // The state of assets registered before the audit log is unknown
+        // before their first entry
+        let pad_state_at = |as_of| {
+            log.state_at(AssetKind::Vertipad, "pad", current.clone(), false, as_of)
+                .unwrap()
+        };
+        assert_eq!(pad_state_at(created.timestamp), PastState::Unknown(None));
+        let pad_updated = log.record(
+            AssetKind::Vertipad,
+            "pad",
+            AuditAction::Updated,
+            &context("operator"),
+            vec![],
+        );
+        assert_eq!(
+            pad_state_at(created.timestamp),
+            PastState::Unknown(Some(pad_updated.timestamp))
+        );
+        assert_eq!(
+            pad_state_at(pad_updated.timestamp),
+            PastState::Existing(current.clone())
+        );
+
+        ut_info!("(test_state_at) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_audit_file() {
+        crate::get_log_handle().await;
+        ut_info!("(test_audit_file) Start.");
+
+        let path =
+            std::env::temp_dir().join(format!("svc-assets-audit-{}.jsonl", uuid::Uuid::new_v4()));
+        let path_str = path.to_string_lossy().to_string();
+
+        let log = AuditLog::open(&path_str, Metrics::new()).unwrap();
+        log.record(
+            AssetKind::Vertiport,
+            "port",
+            AuditAction::Created,
+            &context("operator"),
+            vec![],
+        );
+        log.record(
+            AssetKind::Vertiport,
+            "port",
+            AuditAction::Removed,
+            &context("operator"),
+            vec![],
+        );
+
+        // Entries are restored from the file, and new entries are appended
+        let log = AuditLog::open(&path_str, Metrics::new()).unwrap();
+        assert_eq!(log.history(AssetKind::Vertiport, "port").unwrap().len(), 2);
+        let entry = log.record(
+            AssetKind::Vertiport,
+            "port",
+            AuditAction::Restored,
+            &context("operator"),
+            vec![],
+        );
+        assert_eq!(entry.sequence, 3);
+        log.record(
+            AssetKind::Vertipad,
+            "pad",
+            AuditAction::Created,
+            &context("regulator"),
+            vec![],
+        );
+
+        // A partially written entry is dropped, and new entries start on
+        // a line of their own
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"sequence\":5,").unwrap();
+        let log = AuditLog::open(&path_str, Metrics::new()).unwrap();
+        let entry = log.record(
+            AssetKind::Vertiport,
+            "port",
+            AuditAction::Updated,
+            &context("operator"),
+            vec![],
+        );
+        assert_eq!(entry.sequence, 5);
+        let log = AuditLog::open(&path_str, Metrics::new()).unwrap();
+        assert_eq!(log.history(AssetKind::Vertiport, "port").unwrap().len(), 4);
+        let entries = log
+            .query(&AuditFilter {
+                actor: Some("operator".to_string()),
+                limit: Some(2),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.sequence)
+                .collect::<Vec<_>>(),
+            vec![3, 5]
+        );
+        let entries = log
+            .query(&AuditFilter {
+                asset_id: Some("pad".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].kind, AssetKind::Vertipad);
+
+        std::fs::remove_file(path).ok();
+
+        // Audit files which can not be used are reported
+        let dir = std::env::temp_dir().to_string_lossy().to_string();
+        assert!(AuditLog::open(&dir, Metrics::new()).is_err());
+        ut_info!("(test_audit_file) Success.");
+    }
+}
@@ -0,0 +1,390 @@
+//! Asset Cache
+//! keeps the assets looked up by id, so repeated lookups don't reach svc-storage
+//!
+//! Each kind of asset has its own [`ObjectCache`], bounded in size, in
+//! which svc-storage objects are kept for `asset_cache_ttl_seconds`. The
+//! service invalidates the cached asset whenever it changes it in
+//! svc-storage itself. Changes made to svc-storage by other services are
+//! only seen once the cached asset has expired.
+//!
+//! Expired assets are kept until evicted, so reads can still be served
+//! while svc-storage is down, see [`degraded`](crate::degraded).
+
+use crate::events::AssetKind;
+use crate::grpc::policy::is_transient;
+use crate::metrics::Metrics;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use svc_storage_client_grpc::prelude::*;
+use tokio::sync::OnceCell;
+use tonic::Status;
+
+pub(crate) static ASSET_CACHE: OnceCell<AssetCache> = OnceCell::const_new();
+
+/// Returns ASSET_CACHE, an [`AssetCache`] configured using a Config object
+/// generated from environment variables.
+/// Initializes ASSET_CACHE if it hasn't been initialized yet.
+pub async fn get_asset_cache() -> &'static AssetCache {
+    ASSET_CACHE
+        .get_or_init(|| async move {
+            let config = crate::Config::try_from_env().unwrap_or_default();
+            AssetCache::new(&config, crate::metrics::get_metrics().await.clone())
+        })
+        .await
+}
+
+/// A cached object.
+#[derive(Debug)]
+struct CacheEntry<T> {
+    value: T,
+    stored_at: Instant,
+}
+
+#[derive(Debug)]
+struct CacheState<T> {
+    entries: HashMap<String, CacheEntry<T>>,
+    /// Incremented on each invalidation, so lookups started before it
+    /// don't cache the object they loaded.
+    generation: u64,
+}
+
+/// Objects of a single svc-storage resource, by id.
+///
+/// Objects expire after the time to live, but are kept to serve stale
+/// reads. Once full, expired objects are evicted first, then the oldest
+/// object.
+#[derive(Clone, Debug)]
+pub struct ObjectCache<T> {
+    resource: &'static str,
+    state: Arc<Mutex<CacheState<T>>>,
+    ttl: Duration,
+    max_entries: usize,
+    metrics: Metrics,
+}
+
+impl<T: Clone> ObjectCache<T> {
+    /// Create an empty cache for the svc-storage `resource`, such as
+    /// `vehicle`, keeping at most `max_entries` objects for `ttl`.
+    ///
+    /// Nothing is cached if `max_entries` or `ttl` is 0.
+    pub fn new(
+        resource: &'static str,
+        ttl: Duration,
+        max_entries: usize,
+        metrics: Metrics,
+    ) -> Self {
+        Self {
+            resource,
+            state: Arc::new(Mutex::new(CacheState {
+                entries: HashMap::new(),
+                generation: 0,
+            })),
+            ttl,
+            max_entries,
+            metrics,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CacheState<T>> {
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Indicates if objects are cached.
+    pub fn is_enabled(&self) -> bool {
+        self.max_entries > 0 && !self.ttl.is_zero()
+    }
+
+    /// Returns the cached object with the given id, if it has not expired.
+    pub fn get(&self, id: &str) -> Option<T> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let value = match self.lock().entries.get(id) {
+            Some(entry) if entry.stored_at.elapsed() < self.ttl => Some(entry.value.clone()),
+            _ => None,
+        };
+
+        self.metrics
+            .record_cache_lookup(self.resource, value.is_some());
+        value
+    }
+
+    /// Returns the object with the given id, using `load` to get it from
+    /// svc-storage if it isn't cached.
+    ///
+    /// The loaded object is only cached if nothing has been invalidated
+    /// while it was loaded, as it may already be outdated. If the load
+    /// fails with a transient error, the expired object is returned
+    /// instead when the current request may be served stale objects.
+    pub async fn get_or_load(
+        &self,
+        id: &str,
+        load: impl Future<Output = Result<T, Status>>,
+    ) -> Result<T, Status> {
+        if let Some(value) = self.get(id) {
+            return Ok(value);
+        }
+        if !self.is_enabled() {
+            return load.await;
+        }
+
+        let generation = self.lock().generation;
+        match load.await {
+            Ok(value) => {
+                self.insert(id, value.clone(), generation);
+                Ok(value)
+            }
+            Err(status) if is_transient(&status) => self.get_stale(id).ok_or(status),
+            Err(status) => Err(status),
+        }
+    }
+
+    /// Returns the object with the given id even if it has expired, if the
+    /// current request may be served stale objects.
+    fn get_stale(&self, id: &str) -> Option<T> {
+        let (value, age) = {
+            let state = self.lock();
+            let entry = state.entries.get(id)?;
+            (entry.value.clone(), entry.stored_at.elapsed())
+        };
+
+        match crate::degraded::record_stale_read(age) {
+            true => Some(value),
+            false => None,
+        }
+    }
+
+    /// Keep an object loaded at `generation`.
+    fn insert(&self, id: &str, value: T, generation: u64) {
+        let mut state = self.lock();
+        if state.generation != generation {
+            return;
+        }
+
+        if !state.entries.contains_key(id) && state.entries.len() >= self.max_entries {
+            let ttl = self.ttl;
+            state
+                .entries
+                .retain(|_, entry| entry.stored_at.elapsed() < ttl);
+        }
+        if !state.entries.contains_key(id) && state.entries.len() >= self.max_entries {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.stored_at)
+                .map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                state.entries.remove(&oldest);
+            }
+        }
+        state.entries.insert(
+            id.to_string(),
+            CacheEntry {
+                value,
+                stored_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Remove the object with the given id, which has been changed.
+    pub fn invalidate(&self, id: &str) {
+        let mut state = self.lock();
+        state.entries.remove(id);
+        state.generation += 1;
+    }
+
+    /// Returns the number of cached objects, including expired ones.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Indicates if no objects are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The svc-storage objects of the assets looked up by id.
+#[derive(Clone, Debug)]
+pub struct AssetCache {
+    vehicles: ObjectCache<vehicle::Object>,
+    vertiports: ObjectCache<vertiport::Object>,
+    vertipads: ObjectCache<vertipad::Object>,
+}
+
+impl AssetCache {
+    /// Create an empty cache using the provided configuration, recording
+    /// its hits and misses in `metrics`.
+    pub fn new(config: &crate::Config, metrics: Metrics) -> Self {
+        let ttl = Duration::from_secs(config.asset_cache_ttl_seconds);
+        let max_entries = match config.asset_cache_enabled {
+            true => config.asset_cache_max_entries as usize,
+            false => 0,
+        };
+
+        Self {
+            vehicles: ObjectCache::new("vehicle", ttl, max_entries, metrics.clone()),
+            vertiports: ObjectCache::new("vertiport", ttl, max_entries, metrics.clone()),
+            vertipads: ObjectCache::new("vertipad", ttl, max_entries, metrics),
+        }
+    }
+
+    /// Returns the cached vehicles of the aircraft.
+    pub fn vehicles(&self) -> &ObjectCache<vehicle::Object> {
+        &self.vehicles
+    }
+
+    /// Returns the cached vertiports.
+    pub fn vertiports(&self) -> &ObjectCache<vertiport::Object> {
+        &self.vertiports
+    }
+
+    /// Returns the cached vertipads.
+    pub fn vertipads(&self) -> &ObjectCache<vertipad::Object> {
+        &self.vertipads
+    }
+
+    /// Remove an asset changed by the service from the cache.
+    pub fn invalidate(&self, kind: AssetKind, id: &str) {
+        match kind {
+            AssetKind::Aircraft => self.vehicles.invalidate(id),
+            AssetKind::Vertiport => self.vertiports.invalidate(id),
+            AssetKind::Vertipad => self.vertipads.invalidate(id),
+            // Asset groups are not cached
+            AssetKind::AssetGroup => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::degraded::StaleReads;
+
+    fn cache(ttl: Duration, max_entries: usize) -> ObjectCache<String> {
+        ObjectCache::new("vehicle", ttl, max_entries, Metrics::new())
+    }
+
+    async fn load(value: &str) -> Result<String, Status> {
+        Ok(value.to_string())
+    }
+
+    async fn unavailable() -> Result<String, Status> {
+        Err(Status::unavailable("connection refused"))
+    }
+
+    #[tokio::test]
+    async fn test_get_or_load() {
+        crate::get_log_handle().await;
+        ut_info!("(test_get_or_load) Start.");
+
+        let cache = cache(Duration::from_secs(60), 10);
+        assert_eq!(cache.get_or_load("a", load("a1")).await.unwrap(), "a1");
+        // Cached objects are not loaded again
+        assert_eq!(cache.get_or_load("a", load("a2")).await.unwrap(), "a1");
+        // Failed loads are not cached
+        assert!(cache.get_or_load("b", unavailable()).await.is_err());
+        assert_eq!(cache.len(), 1);
+
+        let text = cache.metrics.render(None);
+        assert!(text
+            .contains("svc_assets_asset_cache_lookups_total{kind=\"vehicle\",result=\"hit\"} 1\n"));
+        assert!(text.contains(
+            "svc_assets_asset_cache_lookups_total{kind=\"vehicle\",result=\"miss\"} 2\n"
+        ));
+
+        ut_info!("(test_get_or_load) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_invalidate() {
+        crate::get_log_handle().await;
+        ut_info!("(test_invalidate) Start.");
+
+        let cache = cache(Duration::from_secs(60), 10);
+        cache.get_or_load("a", load("a1")).await.unwrap();
+        cache.invalidate("a");
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.get_or_load("a", load("a2")).await.unwrap(), "a2");
+
+        // Objects loaded during an invalidation may be outdated already
+        let invalidated = cache.clone();
+        let result = cache
+            .get_or_load("b", async move {
+                invalidated.invalidate("b");
+                Ok("b1".to_string())
+            })
+            .await;
+        assert_eq!(result.unwrap(), "b1");
+        assert_eq!(cache.get("b"), None);
+
+        ut_info!("(test_invalidate) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_bounds() {
+        crate::get_log_handle().await;
+        ut_info!("(test_bounds) Start.");
+
+        // The oldest object is evicted once full
+        let full = cache(Duration::from_secs(60), 2);
+        full.get_or_load("a", load("a")).await.unwrap();
+        full.get_or_load("b", load("b")).await.unwrap();
+        full.get_or_load("c", load("c")).await.unwrap();
+        assert_eq!(full.len(), 2);
+        assert_eq!(full.get("a"), None);
+        assert!(full.get("c").is_some());
+
+        // Expired objects are loaded again, but kept for stale reads
+        let expiring = cache(Duration::from_millis(10), 2);
+        expiring.get_or_load("a", load("a1")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(expiring.get("a"), None);
+        assert_eq!(expiring.len(), 1);
+        assert_eq!(expiring.get_or_load("a", load("a2")).await.unwrap(), "a2");
+
+        let disabled = cache(Duration::from_secs(60), 0);
+        assert!(!disabled.is_enabled());
+        disabled.get_or_load("a", load("a")).await.unwrap();
+        assert!(disabled.is_empty());
+
+        ut_info!("(test_bounds) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_stale_reads() {
+        crate::get_log_handle().await;
+        ut_info!("(test_stale_reads) Start.");
+
+        let cache = cache(Duration::from_millis(10), 10);
+        cache.get_or_load("a", load("a1")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // Expired objects are only served to requests allowing stale reads
+        assert!(cache.get_or_load("a", unavailable()).await.is_err());
+        let reads = StaleReads::default();
+        let result = reads
+            .clone()
+            .scope(cache.get_or_load("a", unavailable()))
+            .await;
+        assert_eq!(result.unwrap(), "a1");
+        assert!(reads.age().unwrap() >= Duration::from_millis(20));
+
+        // Objects which are not found anymore are not served
+        let reads = StaleReads::default();
+        let result = reads
+            .clone()
+            .scope(cache.get_or_load("a", async { Err(Status::not_found("a")) }))
+            .await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::NotFound);
+        assert_eq!(reads.age(), None);
+
+        ut_info!("(test_stale_reads) Success.");
+    }
+}
@@ -0,0 +1,215 @@
+//! Vehicle Model Catalog
+//! keeps the manufacturer and performance data of vehicle models
+//!
+//! Vehicles in `svc-storage` only refer to their model by its
+//! `vehicle_model_id`. The catalog resolves this id to the model's
+//! manufacturer, name and performance data when building an
+//! [`Aircraft`](crate::rest::structs::Aircraft). Models are kept in a JSON
+//! file, so the catalog survives a restart.
+
+#[macro_use]
+pub mod macros;
+
+use crate::json_file::{JsonFileStore, JsonRecord};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::OnceCell;
+use utoipa::ToSchema;
+
+pub(crate) static CATALOG: OnceCell<VehicleCatalog> = OnceCell::const_new();
+
+/// Returns CATALOG, a [`VehicleCatalog`] configured using a Config object
+/// generated from environment variables.
+/// Initializes CATALOG if it hasn't been initialized yet, in memory if the
+/// vehicle models file can not be used.
+pub async fn get_catalog() -> &'static VehicleCatalog {
+    CATALOG
+        .get_or_init(|| async move {
+            let config = crate::Config::try_from_env().unwrap_or_default();
+            match VehicleCatalog::open(&config.vehicle_models_file) {
+                Ok(catalog) => catalog,
+                Err(e) => {
+                    catalog_error!("(get_catalog) {}, models will be kept in memory.", e);
+                    VehicleCatalog::default()
+                }
+            }
+        })
+        .await
+}
+
+/// Initializes CATALOG with the vehicle models file configured in
+/// `config`, failing if the file can not be used.
+pub async fn init_catalog(
+    config: &crate::Config,
+) -> Result<&'static VehicleCatalog, std::io::Error> {
+    CATALOG
+        .get_or_try_init(|| async move { VehicleCatalog::open(&config.vehicle_models_file) })
+        .await
+}
+
+/// Outer dimensions of a vehicle model.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VehicleDimensions {
+    /// Length in meters.
+    pub length_m: f64,
+    /// Width in meters, including rotors and wings.
+    pub width_m: f64,
+    /// Height in meters.
+    pub height_m: f64,
+}
+
+/// Manufacturer and performance data of a vehicle model.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VehicleModel {
+    /// The UUID of the vehicle model, referred to by the
+    /// `vehicle_model_id` of vehicles.
+    pub id: String,
+    /// The manufacturer of the vehicle model.
+    pub manufacturer: String,
+    /// The name of the vehicle model.
+    pub model_name: String,
+    /// Maximum payload in kilograms.
+    pub max_payload_kg: f64,
+    /// Maximum range in kilometers.
+    pub max_range_km: f64,
+    /// Cruise speed in kilometers per hour.
+    pub cruise_speed_kph: f64,
+    /// Time in minutes to fully charge or refuel the vehicle.
+    pub charge_time_minutes: f64,
+    /// Outer dimensions of the vehicle model.
+    pub dimensions: VehicleDimensions,
+    /// Time at which the model has been added to the catalog.
+    pub created_at: DateTime<Utc>,
+    /// Time at which the model has last been updated.
+    pub updated_at: DateTime<Utc>,
+}
+
+impl JsonRecord for VehicleModel {
+    fn key(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Catalog of all known vehicle models.
+///
+/// If no file path is provided, the models are kept in memory only.
+#[derive(Clone, Debug, Default)]
+pub struct VehicleCatalog {
+    models: JsonFileStore<VehicleModel>,
+}
+
+impl VehicleCatalog {
+    /// Open the catalog stored at `path`.
+    ///
+    /// Fails if the file can not be used.
+    pub fn open(path: &str) -> Result<Self, std::io::Error> {
+        if path.is_empty() {
+            catalog_warn!(
+                "(VehicleCatalog::open) no vehicle models file configured, models will be kept in memory."
+            );
+        }
+
+        let models = JsonFileStore::open(path)
+            .map_err(|e| std::io::Error::new(e.kind(), format!("vehicle models {}", e)))?;
+        catalog_info!(
+            "(VehicleCatalog::open) opened vehicle models file [{}] with {} model(s).",
+            path,
+            models.len()
+        );
+        Ok(Self { models })
+    }
+
+    /// Add a model to the catalog, or replace the model with the same id.
+    ///
+    /// The creation time of a replaced model is kept.
+    pub fn upsert(&self, model: VehicleModel) -> Result<VehicleModel, std::io::Error> {
+        let model = self.models.modify(|models| {
+            let mut model = model;
+            if let Some(previous) = models.get(&model.id) {
+                model.created_at = previous.created_at;
+            }
+            models.insert(model.id.clone(), model.clone());
+            model
+        })?;
+        catalog_debug!("(upsert) {:?}", model);
+
+        Ok(model)
+    }
+
+    /// Remove a model from the catalog, returning the removed model.
+    pub fn remove(&self, model_id: &str) -> Result<Option<VehicleModel>, std::io::Error> {
+        let model = self.models.remove(model_id)?;
+        if let Some(model) = &model {
+            catalog_debug!("(remove) {:?}", model);
+        }
+
+        Ok(model)
+    }
+
+    /// Returns the model with the given id, if known.
+    pub fn get(&self, model_id: &str) -> Option<VehicleModel> {
+        self.models.get(model_id)
+    }
+
+    /// Returns all models, sorted by manufacturer and model name.
+    pub fn list(&self) -> Vec<VehicleModel> {
+        let mut list = self.models.values();
+        list.sort_by(|a, b| {
+            (&a.manufacturer, &a.model_name).cmp(&(&b.manufacturer, &b.model_name))
+        });
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(id: &str, manufacturer: &str, model_name: &str) -> VehicleModel {
+        VehicleModel {
+            id: id.to_string(),
+            manufacturer: manufacturer.to_string(),
+            model_name: model_name.to_string(),
+            max_payload_kg: 250.0,
+            max_range_km: 120.0,
+            cruise_speed_kph: 180.0,
+            charge_time_minutes: 45.0,
+            dimensions: VehicleDimensions {
+                length_m: 8.0,
+                width_m: 12.0,
+                height_m: 3.0,
+            },
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upsert_remove() {
+        crate::get_log_handle().await;
+        ut_info!("(test_upsert_remove) Start.");
+
+        let catalog = VehicleCatalog::default();
+        let first = catalog.upsert(model("a", "Arrow", "Cargo")).unwrap();
+        catalog.upsert(model("b", "Acme", "Lift")).unwrap();
+        assert_eq!(catalog.get("a"), Some(first.clone()));
+
+        // Models are sorted by manufacturer
+        let list = catalog.list();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].id, "b");
+
+        // Replacing a model keeps its creation time
+        let mut updated = model("a", "Arrow", "Cargo XL");
+        updated.created_at = Utc::now() + chrono::Duration::days(1);
+        let updated = catalog.upsert(updated).unwrap();
+        assert_eq!(updated.created_at, first.created_at);
+        assert_eq!(catalog.get("a").unwrap().model_name, "Cargo XL");
+
+        assert_eq!(catalog.remove("a").unwrap(), Some(updated));
+        assert_eq!(catalog.remove("a").unwrap(), None);
+        assert_eq!(catalog.get("a"), None);
+
+        ut_info!("(test_upsert_remove) Success.");
+    }
+}
@@ -1,14 +1,22 @@
 //! # Config
 //!
 //! Define and implement config options for module
+//!
+//! Options are read from environment variables, layered over an optional
+//! TOML or YAML configuration file, layered over the defaults.
 
+use crate::store::{MEMORY_BACKEND, SVC_STORAGE_BACKEND};
 use anyhow::Result;
-use config::{ConfigError, Environment};
+use axum::http::HeaderValue;
+use config::{builder::DefaultState, ConfigBuilder, ConfigError, Environment, File};
 use dotenv::dotenv;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+/// Environment variable holding the path of the configuration file, if any.
+pub const CONFIG_FILE_ENV: &str = "CONFIG_FILE";
 
 /// struct holding configuration options
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Config {
     /// port to be used for gRPC server
     pub docker_port_grpc: u16,
@@ -24,9 +32,80 @@ pub struct Config {
     pub rest_request_limit_per_second: u8,
     /// Enforces a limit on the concurrent number of requests the underlying service can handle
     pub rest_concurrency_limit_per_service: u8,
-    /// Full url (including port number) to be allowed as request origin for
-    /// REST requests
+    /// Full urls (including port number) to be allowed as request origins for
+    /// REST requests, separated by commas
     pub rest_cors_allowed_origin: String,
+    /// Enables publishing asset events to AMQP
+    pub amqp_enabled: bool,
+    /// host of AMQP broker
+    pub amqp_host: String,
+    /// port of AMQP broker
+    pub amqp_port: u16,
+    /// AMQP exchange asset events are published to
+    pub amqp_exchange: String,
+    /// path to the file used to queue asset events until they are delivered,
+    /// events are only kept in memory if empty
+    pub outbox_file: String,
+    /// Maximum delay in seconds between retries of failed event deliveries
+    pub outbox_max_backoff_seconds: u64,
+    /// Number of recent asset events kept to allow subscribers to resume
+    pub event_history_size: u32,
+    /// Enables receiving aircraft telemetry over MAVLink
+    pub telemetry_enabled: bool,
+    /// UDP port to listen on for MAVLink messages
+    pub telemetry_port_udp: u16,
+    /// Comma separated list of `system_id=aircraft_id` pairs, correlating
+    /// MAVLink system ids to aircraft
+    pub telemetry_system_ids: String,
+    /// Seconds without a heartbeat after which an aircraft is marked unavailable
+    pub telemetry_heartbeat_timeout_seconds: u64,
+    /// Remaining battery percentage below which an aircraft is marked unavailable
+    pub telemetry_min_battery_percent: u8,
+    /// path to the file recording the details of asset removals, which are
+    /// only kept in memory if empty
+    pub deletions_file: String,
+    /// Days after which removed assets are permanently deleted, never if 0.
+    /// Must be 0 with the svc-storage backend, which can not permanently
+    /// delete assets
+    pub deletion_retention_days: u32,
+    /// path to the file the audit log of all asset changes is appended to,
+    /// changes are only kept in memory if empty
+    pub audit_file: String,
+    /// path to the file used to keep the vehicle model catalog, models are
+    /// only kept in memory if empty
+    pub vehicle_models_file: String,
+    /// path to the file used to keep the vertipad reservations,
+    /// reservations are only kept in memory if empty
+    pub reservations_file: String,
+    /// base URL of the OpenTelemetry collector spans are exported to over
+    /// OTLP/HTTP, spans are not exported if empty
+    pub otlp_endpoint: String,
+    /// interval in seconds at which the dependencies of the service are
+    /// checked
+    pub health_check_interval_seconds: u64,
+    /// true to cache the assets looked up by id
+    pub asset_cache_enabled: bool,
+    /// time in seconds after which a cached asset is looked up again
+    pub asset_cache_ttl_seconds: u64,
+    /// maximum number of cached assets of each kind
+    pub asset_cache_max_entries: u32,
+    /// time in milliseconds after which a svc-storage call is given up,
+    /// calls are never given up if 0
+    pub storage_timeout_ms: u64,
+    /// number of times a svc-storage read failing with a transient error
+    /// is retried
+    pub storage_read_retries: u32,
+    /// time in milliseconds before the first retry of a svc-storage read,
+    /// doubled with each retry
+    pub storage_retry_backoff_ms: u64,
+    /// number of consecutive failed calls after which calls to a
+    /// svc-storage client are failed right away, never if 0
+    pub storage_breaker_failures: u32,
+    /// time in seconds during which calls to a svc-storage client are
+    /// failed right away before a call is tried again
+    pub storage_breaker_open_seconds: u64,
+    /// where assets are stored, `svc-storage` or `memory`
+    pub storage_backend: String,
 }
 
 impl Default for Config {
@@ -48,34 +127,213 @@ impl Config {
             rest_request_limit_per_second: 2,
             rest_concurrency_limit_per_service: 5,
             rest_cors_allowed_origin: String::from("http://localhost:3000"),
+            amqp_enabled: false,
+            amqp_host: String::from("rabbitmq"),
+            amqp_port: 5672,
+            amqp_exchange: String::from("assets"),
+            outbox_file: String::from("outbox.jsonl"),
+            outbox_max_backoff_seconds: 60,
+            event_history_size: 1000,
+            telemetry_enabled: false,
+            telemetry_port_udp: 14550,
+            telemetry_system_ids: String::from(""),
+            telemetry_heartbeat_timeout_seconds: 10,
+            telemetry_min_battery_percent: 20,
+            deletions_file: String::from("deletions.json"),
+            deletion_retention_days: 0,
+            audit_file: String::from("audit.jsonl"),
+            vehicle_models_file: String::from("vehicle_models.json"),
+            reservations_file: String::from("reservations.json"),
+            otlp_endpoint: String::from(""),
+            health_check_interval_seconds: 10,
+            asset_cache_enabled: true,
+            asset_cache_ttl_seconds: 30,
+            asset_cache_max_entries: 1000,
+            storage_timeout_ms: 2000,
+            storage_read_retries: 2,
+            storage_retry_backoff_ms: 100,
+            storage_breaker_failures: 5,
+            storage_breaker_open_seconds: 30,
+            storage_backend: String::from("svc-storage"),
         }
     }
 
-    /// Create a new `Config` object using environment variables
+    /// Create a new `Config` object using environment variables, layered
+    /// over the configuration file at `CONFIG_FILE` if set
     pub fn try_from_env() -> Result<Self, ConfigError> {
         // read .env file if present
         dotenv().ok();
+
+        let mut builder = Self::defaults()?;
+        if let Some(path) = std::env::var(CONFIG_FILE_ENV)
+            .ok()
+            .filter(|path| !path.is_empty())
+        {
+            builder = builder.add_source(File::with_name(&path));
+        }
+        builder
+            .add_source(Environment::default().separator("__"))
+            .build()?
+            .try_deserialize()
+    }
+
+    /// Returns a builder holding the default values of all options
+    fn defaults() -> Result<ConfigBuilder<DefaultState>, ConfigError> {
         let default_config = Config::default();
 
         config::Config::builder()
             .set_default("docker_port_grpc", default_config.docker_port_grpc)?
             .set_default("docker_port_rest", default_config.docker_port_rest)?
+            .set_default("storage_host_grpc", default_config.storage_host_grpc)?
+            .set_default("storage_port_grpc", default_config.storage_port_grpc)?
             .set_default("log_config", default_config.log_config)?
             .set_default(
                 "rest_concurrency_limit_per_service",
                 default_config.rest_concurrency_limit_per_service,
             )?
             .set_default(
-                "rest_request_limit_per_seconds",
+                "rest_request_limit_per_second",
                 default_config.rest_request_limit_per_second,
             )?
             .set_default(
                 "rest_cors_allowed_origin",
                 default_config.rest_cors_allowed_origin,
             )?
-            .add_source(Environment::default().separator("__"))
-            .build()?
-            .try_deserialize()
+            .set_default("amqp_enabled", default_config.amqp_enabled)?
+            .set_default("amqp_host", default_config.amqp_host)?
+            .set_default("amqp_port", default_config.amqp_port)?
+            .set_default("amqp_exchange", default_config.amqp_exchange)?
+            .set_default("outbox_file", default_config.outbox_file)?
+            .set_default(
+                "outbox_max_backoff_seconds",
+                default_config.outbox_max_backoff_seconds,
+            )?
+            .set_default("event_history_size", default_config.event_history_size)?
+            .set_default("telemetry_enabled", default_config.telemetry_enabled)?
+            .set_default("telemetry_port_udp", default_config.telemetry_port_udp)?
+            .set_default("telemetry_system_ids", default_config.telemetry_system_ids)?
+            .set_default(
+                "telemetry_heartbeat_timeout_seconds",
+                default_config.telemetry_heartbeat_timeout_seconds,
+            )?
+            .set_default(
+                "telemetry_min_battery_percent",
+                default_config.telemetry_min_battery_percent,
+            )?
+            .set_default("deletions_file", default_config.deletions_file)?
+            .set_default(
+                "deletion_retention_days",
+                default_config.deletion_retention_days,
+            )?
+            .set_default("audit_file", default_config.audit_file)?
+            .set_default("vehicle_models_file", default_config.vehicle_models_file)?
+            .set_default("reservations_file", default_config.reservations_file)?
+            .set_default("otlp_endpoint", default_config.otlp_endpoint)?
+            .set_default(
+                "health_check_interval_seconds",
+                default_config.health_check_interval_seconds,
+            )?
+            .set_default("asset_cache_enabled", default_config.asset_cache_enabled)?
+            .set_default(
+                "asset_cache_ttl_seconds",
+                default_config.asset_cache_ttl_seconds,
+            )?
+            .set_default(
+                "asset_cache_max_entries",
+                default_config.asset_cache_max_entries,
+            )?
+            .set_default("storage_timeout_ms", default_config.storage_timeout_ms)?
+            .set_default("storage_read_retries", default_config.storage_read_retries)?
+            .set_default(
+                "storage_retry_backoff_ms",
+                default_config.storage_retry_backoff_ms,
+            )?
+            .set_default(
+                "storage_breaker_failures",
+                default_config.storage_breaker_failures,
+            )?
+            .set_default(
+                "storage_breaker_open_seconds",
+                default_config.storage_breaker_open_seconds,
+            )?
+            .set_default("storage_backend", default_config.storage_backend)
+    }
+
+    /// Check for values the service can not run with, reporting all
+    /// problems at once.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = vec![];
+        for origin in self.rest_cors_allowed_origin.split(',') {
+            if let Err(e) = check_origin(origin.trim()) {
+                problems.push(format!(
+                    "rest_cors_allowed_origin [{}] {}",
+                    origin.trim(),
+                    e
+                ));
+            }
+        }
+        if self.docker_port_rest == self.docker_port_grpc {
+            problems.push(format!(
+                "docker_port_rest and docker_port_grpc are both {}",
+                self.docker_port_rest
+            ));
+        }
+        if self.rest_request_limit_per_second == 0 {
+            problems.push("rest_request_limit_per_second must be at least 1".to_string());
+        }
+        if self.rest_concurrency_limit_per_service == 0 {
+            problems.push("rest_concurrency_limit_per_service must be at least 1".to_string());
+        }
+        if self.amqp_enabled && self.amqp_exchange.is_empty() {
+            problems.push("amqp_exchange must be set when amqp_enabled".to_string());
+        }
+        if self.telemetry_min_battery_percent > 100 {
+            problems.push(format!(
+                "telemetry_min_battery_percent [{}] must be at most 100",
+                self.telemetry_min_battery_percent
+            ));
+        }
+        if !self.otlp_endpoint.is_empty()
+            && !self.otlp_endpoint.starts_with("http://")
+            && !self.otlp_endpoint.starts_with("https://")
+        {
+            problems.push(format!(
+                "otlp_endpoint [{}] must be an http(s) URL",
+                self.otlp_endpoint
+            ));
+        }
+        if ![SVC_STORAGE_BACKEND, MEMORY_BACKEND].contains(&self.storage_backend.as_str()) {
+            problems.push(format!(
+                "storage_backend [{}] must be {} or {}",
+                self.storage_backend, SVC_STORAGE_BACKEND, MEMORY_BACKEND
+            ));
+        }
+        // TODO(R4): svc-storage only sets the deleted_at of deleted assets
+        if self.storage_backend == SVC_STORAGE_BACKEND && self.deletion_retention_days > 0 {
+            problems.push(format!(
+                "deletion_retention_days [{}] must be 0 with the {} backend, which can not permanently delete assets",
+                self.deletion_retention_days, SVC_STORAGE_BACKEND
+            ));
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+        Err(ConfigError::Message(format!(
+            "invalid configuration:\n  - {}",
+            problems.join("\n  - ")
+        )))
+    }
+}
+
+/// Check that `origin` is an origin like `http://localhost:3000`.
+fn check_origin(origin: &str) -> Result<(), &'static str> {
+    if HeaderValue::from_str(origin).is_err() {
+        return Err("is not a valid header value");
+    }
+    match origin.split_once("://") {
+        Some(("http" | "https", host)) if !host.is_empty() && !host.contains('/') => Ok(()),
+        _ => Err("must be an http(s) scheme and host, like http://localhost:3000"),
     }
 }
 
@@ -101,26 +359,96 @@ mod tests {
             config.rest_cors_allowed_origin,
             String::from("http://localhost:3000")
         );
+        assert_eq!(config.amqp_enabled, false);
+        assert_eq!(config.amqp_host, String::from("rabbitmq"));
+        assert_eq!(config.amqp_port, 5672);
+        assert_eq!(config.amqp_exchange, String::from("assets"));
+        assert_eq!(config.outbox_file, String::from("outbox.jsonl"));
+        assert_eq!(config.outbox_max_backoff_seconds, 60);
+        assert_eq!(config.event_history_size, 1000);
+        assert_eq!(config.telemetry_enabled, false);
+        assert_eq!(config.telemetry_port_udp, 14550);
+        assert_eq!(config.telemetry_system_ids, String::from(""));
+        assert_eq!(config.telemetry_heartbeat_timeout_seconds, 10);
+        assert_eq!(config.telemetry_min_battery_percent, 20);
+        assert_eq!(config.deletions_file, String::from("deletions.json"));
+        assert_eq!(config.deletion_retention_days, 0);
+        assert_eq!(config.audit_file, String::from("audit.jsonl"));
+        assert_eq!(
+            config.vehicle_models_file,
+            String::from("vehicle_models.json")
+        );
+        assert_eq!(config.reservations_file, String::from("reservations.json"));
+        assert_eq!(config.otlp_endpoint, String::from(""));
+        assert_eq!(config.health_check_interval_seconds, 10);
+        assert_eq!(config.asset_cache_enabled, true);
+        assert_eq!(config.asset_cache_ttl_seconds, 30);
+        assert_eq!(config.asset_cache_max_entries, 1000);
+        assert_eq!(config.storage_timeout_ms, 2000);
+        assert_eq!(config.storage_read_retries, 2);
+        assert_eq!(config.storage_retry_backoff_ms, 100);
+        assert_eq!(config.storage_breaker_failures, 5);
+        assert_eq!(config.storage_breaker_open_seconds, 30);
+        assert_eq!(config.storage_backend, String::from("svc-storage"));
 
         ut_info!("(test_config_from_default) Success.");
     }
 
+    /// Environment variables set by [`test_config_from_env`], removed again
+    /// at its end as other tests read the environment too.
+    const TEST_ENV: [(&str, &str); 36] = [
+        ("DOCKER_PORT_GRPC", "6789"),
+        ("DOCKER_PORT_REST", "9876"),
+        ("STORAGE_HOST_GRPC", "test_host_grpc"),
+        ("STORAGE_PORT_GRPC", "12345"),
+        ("LOG_CONFIG", "config_file.yaml"),
+        ("REST_CONCURRENCY_LIMIT_PER_SERVICE", "255"),
+        ("REST_REQUEST_LIMIT_PER_SECOND", "255"),
+        (
+            "REST_CORS_ALLOWED_ORIGIN",
+            "https://allowed.origin.host:443",
+        ),
+        ("AMQP_ENABLED", "true"),
+        ("AMQP_HOST", "test_host_amqp"),
+        ("AMQP_PORT", "5673"),
+        ("AMQP_EXCHANGE", "test_exchange"),
+        ("OUTBOX_FILE", "/tmp/test_outbox.jsonl"),
+        ("OUTBOX_MAX_BACKOFF_SECONDS", "10"),
+        ("EVENT_HISTORY_SIZE", "50"),
+        ("TELEMETRY_ENABLED", "true"),
+        ("TELEMETRY_PORT_UDP", "14551"),
+        (
+            "TELEMETRY_SYSTEM_IDS",
+            "1=1b8d07d0-bd57-4a8b-8aea-c2d7e5e7c2a1",
+        ),
+        ("TELEMETRY_HEARTBEAT_TIMEOUT_SECONDS", "30"),
+        ("TELEMETRY_MIN_BATTERY_PERCENT", "15"),
+        ("DELETIONS_FILE", "/tmp/test_deletions.json"),
+        ("DELETION_RETENTION_DAYS", "7"),
+        ("AUDIT_FILE", "/tmp/test_audit.jsonl"),
+        ("VEHICLE_MODELS_FILE", "/tmp/test_vehicle_models.json"),
+        ("RESERVATIONS_FILE", "/tmp/test_reservations.json"),
+        ("OTLP_ENDPOINT", "http://localhost:4318"),
+        ("HEALTH_CHECK_INTERVAL_SECONDS", "30"),
+        ("ASSET_CACHE_ENABLED", "false"),
+        ("ASSET_CACHE_TTL_SECONDS", "5"),
+        ("ASSET_CACHE_MAX_ENTRIES", "200"),
+        ("STORAGE_TIMEOUT_MS", "1000"),
+        ("STORAGE_READ_RETRIES", "3"),
+        ("STORAGE_RETRY_BACKOFF_MS", "10"),
+        ("STORAGE_BREAKER_FAILURES", "10"),
+        ("STORAGE_BREAKER_OPEN_SECONDS", "5"),
+        ("STORAGE_BACKEND", "memory"),
+    ];
+
     #[tokio::test]
     async fn test_config_from_env() {
         crate::get_log_handle().await;
         ut_info!("(test_config_from_env) Start.");
 
-        std::env::set_var("DOCKER_PORT_GRPC", "6789");
-        std::env::set_var("DOCKER_PORT_REST", "9876");
-        std::env::set_var("STORAGE_HOST_GRPC", "test_host_grpc");
-        std::env::set_var("STORAGE_PORT_GRPC", "12345");
-        std::env::set_var("LOG_CONFIG", "config_file.yaml");
-        std::env::set_var("REST_CONCURRENCY_LIMIT_PER_SERVICE", "255");
-        std::env::set_var("REST_REQUEST_LIMIT_PER_SECOND", "255");
-        std::env::set_var(
-            "REST_CORS_ALLOWED_ORIGIN",
-            "https://allowed.origin.host:443",
-        );
+        for (name, value) in TEST_ENV {
+            std::env::set_var(name, value);
+        }
         let config = Config::try_from_env();
         assert!(config.is_ok());
         let config = config.unwrap();
@@ -136,7 +464,147 @@ mod tests {
             config.rest_cors_allowed_origin,
             String::from("https://allowed.origin.host:443")
         );
+        assert_eq!(config.amqp_enabled, true);
+        assert_eq!(config.amqp_host, String::from("test_host_amqp"));
+        assert_eq!(config.amqp_port, 5673);
+        assert_eq!(config.amqp_exchange, String::from("test_exchange"));
+        assert_eq!(config.outbox_file, String::from("/tmp/test_outbox.jsonl"));
+        assert_eq!(config.outbox_max_backoff_seconds, 10);
+        assert_eq!(config.event_history_size, 50);
+        assert_eq!(config.telemetry_enabled, true);
+        assert_eq!(config.telemetry_port_udp, 14551);
+        assert_eq!(
+            config.telemetry_system_ids,
+            String::from("1=1b8d07d0-bd57-4a8b-8aea-c2d7e5e7c2a1")
+        );
+        assert_eq!(config.telemetry_heartbeat_timeout_seconds, 30);
+        assert_eq!(config.telemetry_min_battery_percent, 15);
+        assert_eq!(
+            config.deletions_file,
+            String::from("/tmp/test_deletions.json")
+        );
+        assert_eq!(config.deletion_retention_days, 7);
+        assert_eq!(config.audit_file, String::from("/tmp/test_audit.jsonl"));
+        assert_eq!(
+            config.vehicle_models_file,
+            String::from("/tmp/test_vehicle_models.json")
+        );
+        assert_eq!(
+            config.reservations_file,
+            String::from("/tmp/test_reservations.json")
+        );
+        assert_eq!(config.otlp_endpoint, String::from("http://localhost:4318"));
+        assert_eq!(config.health_check_interval_seconds, 30);
+        assert_eq!(config.asset_cache_enabled, false);
+        assert_eq!(config.asset_cache_ttl_seconds, 5);
+        assert_eq!(config.asset_cache_max_entries, 200);
+        assert_eq!(config.storage_timeout_ms, 1000);
+        assert_eq!(config.storage_read_retries, 3);
+        assert_eq!(config.storage_retry_backoff_ms, 10);
+        assert_eq!(config.storage_breaker_failures, 10);
+        assert_eq!(config.storage_breaker_open_seconds, 5);
+        assert_eq!(config.storage_backend, String::from("memory"));
+
+        // Environment variables override the configuration file, which
+        // overrides the defaults
+        std::env::remove_var("DOCKER_PORT_GRPC");
+        std::env::remove_var("DOCKER_PORT_REST");
+        std::env::remove_var("REST_CORS_ALLOWED_ORIGIN");
+        let dir = std::env::temp_dir();
+        let toml = dir.join(format!("{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &toml,
+            concat!(
+                "storage_host_grpc = \"toml_host_grpc\"\n",
+                "docker_port_rest = 9000\n",
+                "rest_cors_allowed_origin = \"https://toml.host\"\n",
+            ),
+        )
+        .unwrap();
+        std::env::set_var(CONFIG_FILE_ENV, toml.to_str().unwrap());
+        let config = Config::try_from_env().unwrap();
+        assert_eq!(config.storage_host_grpc, String::from("test_host_grpc"));
+        assert_eq!(config.docker_port_rest, 9000);
+        assert_eq!(
+            config.rest_cors_allowed_origin,
+            String::from("https://toml.host")
+        );
+        assert_eq!(config.docker_port_grpc, 50051);
+        std::fs::remove_file(&toml).unwrap();
+
+        let yaml = dir.join(format!("{}.yaml", uuid::Uuid::new_v4()));
+        std::fs::write(&yaml, "docker_port_rest: 9001\n").unwrap();
+        std::env::set_var(CONFIG_FILE_ENV, yaml.to_str().unwrap());
+        let config = Config::try_from_env().unwrap();
+        assert_eq!(config.docker_port_rest, 9001);
+        assert_eq!(config.storage_backend, String::from("memory"));
+        std::fs::remove_file(&yaml).unwrap();
+
+        // The configuration file must exist if set
+        assert!(Config::try_from_env().is_err());
+        std::env::remove_var(CONFIG_FILE_ENV);
+        for (name, _) in TEST_ENV {
+            std::env::remove_var(name);
+        }
 
         ut_info!("(test_config_from_env) Success.");
     }
+
+    #[tokio::test]
+    async fn test_config_validate() {
+        crate::get_log_handle().await;
+        ut_info!("(test_config_validate) Start.");
+
+        assert!(Config::default().validate().is_ok());
+
+        let config = Config {
+            rest_cors_allowed_origin: String::from("localhost:3000"),
+            docker_port_rest: 50051,
+            rest_request_limit_per_second: 0,
+            storage_backend: String::from("postgres"),
+            ..Default::default()
+        };
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("rest_cors_allowed_origin [localhost:3000]"));
+        assert!(message.contains("docker_port_rest and docker_port_grpc are both 50051"));
+        assert!(message.contains("rest_request_limit_per_second"));
+        assert!(message.contains("storage_backend [postgres]"));
+        assert!(!message.contains("telemetry_min_battery_percent"));
+
+        for origin in ["https://example.com/path", "ftp://example.com", "http://"] {
+            assert!(check_origin(origin).is_err());
+        }
+        assert!(check_origin("https://allowed.origin.host:443").is_ok());
+
+        // svc-storage can not permanently delete removed assets
+        let config = Config {
+            deletion_retention_days: 30,
+            ..Default::default()
+        };
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("deletion_retention_days [30]"));
+        let config = Config {
+            deletion_retention_days: 30,
+            storage_backend: String::from("memory"),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        // Several origins are separated by commas
+        let config = Config {
+            rest_cors_allowed_origin: String::from("http://localhost:3000, https://app.host"),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        let config = Config {
+            rest_cors_allowed_origin: String::from("http://localhost:3000,app.host,"),
+            ..Default::default()
+        };
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("rest_cors_allowed_origin [app.host]"));
+        assert!(message.contains("rest_cors_allowed_origin []"));
+        assert!(!message.contains("[http://localhost:3000]"));
+
+        ut_info!("(test_config_validate) Success.");
+    }
 }
@@ -0,0 +1,265 @@
+//! Degraded Mode
+//! keeps serving reads while svc-storage is partially down
+//!
+//! Each REST route depends on one or more svc-storage resources. While a
+//! resource is down according to the [`HealthMonitor`], writes and listings
+//! depending on it are rejected. Reads of an asset by id may be served the
+//! assets kept by the [`AssetCache`](crate::cache::AssetCache) once
+//! expired, if looking them up in svc-storage fails. The service recovers as soon as the health
+//! checks find the resource up again.
+
+use crate::health::HealthMonitor;
+use axum::http::Method;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+/// The svc-storage vehicle resource.
+const VEHICLE: &str = "vehicle";
+/// The svc-storage vertiport resource.
+const VERTIPORT: &str = "vertiport";
+/// The svc-storage vertipad resource.
+const VERTIPAD: &str = "vertipad";
+
+pub(crate) static DEGRADED_MODE: OnceCell<DegradedMode> = OnceCell::const_new();
+
+/// Returns DEGRADED_MODE, a [`DegradedMode`] configured using a Config
+/// object generated from environment variables.
+/// Initializes DEGRADED_MODE if it hasn't been initialized yet.
+pub async fn get_degraded_mode() -> &'static DegradedMode {
+    DEGRADED_MODE
+        .get_or_init(|| async move {
+            let config = crate::Config::try_from_env().unwrap_or_default();
+            DegradedMode::new(
+                crate::health::get_health_monitor().await.clone(),
+                Duration::from_secs(config.health_check_interval_seconds.max(1)),
+            )
+        })
+        .await
+}
+
+/// Returns the svc-storage resources a request to `route`, the matched
+/// route pattern, depends on.
+pub fn storage_dependencies(method: &Method, route: &str) -> &'static [&'static str] {
+    match route {
+        "/assets/demo/aircraft" | "/assets/aircraft" | "/assets/aircraft/:id" => &[VEHICLE],
+        // Removing a vehicle model checks if aircraft still use it
+        "/assets/models/:id" if method == Method::DELETE => &[VEHICLE],
+        // Removing a vertiport also removes its vertipads and checks for
+        // hangared aircraft
+        "/assets/vertiports/:id" if method == Method::DELETE => &[VERTIPORT, VERTIPAD, VEHICLE],
+        "/assets/demo/vertiports" | "/assets/vertiports" | "/assets/vertiports/:id" => &[VERTIPORT],
+        "/assets/vertiports/:id/occupancy" => &[VERTIPAD],
+        "/assets/vertiports/:id/summary" | "/assets/capable-aircraft" | "/assets/deleted" => {
+            &[VERTIPORT, VERTIPAD, VEHICLE]
+        }
+        "/assets/demo/vertipads"
+        | "/assets/vertipads"
+        | "/assets/vertipads/:id"
+        | "/assets/vertipads/:id/occupy"
+        | "/assets/vertipads/:id/release" => &[VERTIPAD],
+        // Cancelling a reservation doesn't need the vertipad
+        "/assets/vertipads/:id/reservations"
+        | "/assets/vertipads/:id/reservations/:reservation_id"
+            if method != Method::GET && method != Method::DELETE =>
+        {
+            &[VERTIPAD]
+        }
+        _ => &[],
+    }
+}
+
+/// Indicates if a read of `route`, the matched route pattern, may be
+/// served stale assets from the asset cache.
+///
+/// Only assets looked up by id are cached, listings are always read from
+/// svc-storage.
+pub fn serves_stale(route: &str) -> bool {
+    matches!(
+        route,
+        "/assets/aircraft/:id" | "/assets/vertiports/:id" | "/assets/vertipads/:id"
+    )
+}
+
+tokio::task_local! {
+    static STALE_READS: StaleReads;
+}
+
+/// The stale assets served to a read request.
+#[derive(Clone, Debug, Default)]
+pub struct StaleReads {
+    /// Age of the oldest stale asset served, if any.
+    oldest: Arc<Mutex<Option<Duration>>>,
+}
+
+impl StaleReads {
+    fn lock(&self) -> MutexGuard<'_, Option<Duration>> {
+        match self.oldest.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Run `future` as the handling of a read request, which may be served
+    /// stale assets.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        STALE_READS.scope(self, future).await
+    }
+
+    /// Returns the age of the oldest stale asset served, if any.
+    pub fn age(&self) -> Option<Duration> {
+        *self.lock()
+    }
+
+    fn record(&self, age: Duration) {
+        let mut oldest = self.lock();
+        *oldest = Some(match *oldest {
+            Some(oldest) => oldest.max(age),
+            None => age,
+        });
+    }
+}
+
+/// Indicates if the request the current task handles may be served stale
+/// assets.
+pub fn stale_reads_allowed() -> bool {
+    STALE_READS.try_with(|_| ()).is_ok()
+}
+
+/// Record that a stale asset of the given `age` is served to the request
+/// the current task handles.
+///
+/// Returns false if the request may not be served stale assets.
+pub fn record_stale_read(age: Duration) -> bool {
+    STALE_READS.try_with(|reads| reads.record(age)).is_ok()
+}
+
+/// State of the degraded mode, shared by all requests.
+#[derive(Clone, Debug)]
+pub struct DegradedMode {
+    health: HealthMonitor,
+    retry_after: Duration,
+}
+
+impl DegradedMode {
+    /// Create the degraded mode for the dependencies checked by `health`.
+    ///
+    /// Clients are asked to retry rejected requests after `retry_after`,
+    /// the interval at which the dependencies are checked again.
+    pub fn new(health: HealthMonitor, retry_after: Duration) -> Self {
+        Self {
+            health,
+            retry_after,
+        }
+    }
+
+    /// Returns the time after which clients should retry rejected requests.
+    pub fn retry_after(&self) -> Duration {
+        self.retry_after
+    }
+
+    /// Returns the `dependencies` which were down at the latest check.
+    pub fn down(&self, dependencies: &[&'static str]) -> Vec<&'static str> {
+        dependencies
+            .iter()
+            .copied()
+            .filter(|dependency| self.health.is_down(dependency))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::health::DependencyCheck;
+
+    /// Check of a dependency which is always down.
+    struct DownCheck(&'static str);
+
+    #[tonic::async_trait]
+    impl DependencyCheck for DownCheck {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        async fn check(&self) -> Result<(), String> {
+            Err("connection refused".to_string())
+        }
+    }
+
+    #[test]
+    fn test_storage_dependencies() {
+        assert_eq!(
+            storage_dependencies(&Method::GET, "/assets/aircraft/:id"),
+            &[VEHICLE]
+        );
+        assert_eq!(
+            storage_dependencies(&Method::GET, "/assets/vertiports/:id"),
+            &[VERTIPORT]
+        );
+        assert_eq!(
+            storage_dependencies(&Method::DELETE, "/assets/vertiports/:id"),
+            &[VERTIPORT, VERTIPAD, VEHICLE]
+        );
+        assert_eq!(
+            storage_dependencies(&Method::POST, "/assets/vertipads/:id/reservations"),
+            &[VERTIPAD]
+        );
+        assert!(
+            storage_dependencies(&Method::GET, "/assets/vertipads/:id/reservations").is_empty()
+        );
+        assert!(storage_dependencies(&Method::GET, "/assets/models/:id").is_empty());
+        assert!(storage_dependencies(&Method::GET, "/assets/events").is_empty());
+    }
+
+    #[test]
+    fn test_serves_stale() {
+        assert!(serves_stale("/assets/aircraft/:id"));
+        assert!(serves_stale("/assets/vertipads/:id"));
+        assert!(!serves_stale("/assets/demo/aircraft"));
+        assert!(!serves_stale("/assets/vertiports/:id/summary"));
+    }
+
+    #[tokio::test]
+    async fn test_stale_reads() {
+        crate::get_log_handle().await;
+        ut_info!("(test_stale_reads) Start.");
+
+        assert!(!stale_reads_allowed());
+        assert!(!record_stale_read(Duration::from_secs(1)));
+
+        let reads = StaleReads::default();
+        reads
+            .clone()
+            .scope(async {
+                assert!(stale_reads_allowed());
+                assert!(record_stale_read(Duration::from_secs(5)));
+                assert!(record_stale_read(Duration::from_secs(2)));
+            })
+            .await;
+        // The oldest asset determines the age of the response
+        assert_eq!(reads.age(), Some(Duration::from_secs(5)));
+        assert_eq!(StaleReads::default().age(), None);
+
+        ut_info!("(test_stale_reads) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_down() {
+        crate::get_log_handle().await;
+        ut_info!("(test_down) Start.");
+
+        let health = HealthMonitor::new(vec![Arc::new(DownCheck(VERTIPAD))]);
+        let degraded = DegradedMode::new(health.clone(), Duration::from_secs(10));
+
+        // Nothing is down before the first check
+        assert!(degraded.down(&[VERTIPORT, VERTIPAD]).is_empty());
+
+        health.refresh().await;
+        assert_eq!(degraded.down(&[VERTIPORT, VERTIPAD]), vec![VERTIPAD]);
+        assert!(degraded.down(&[VEHICLE]).is_empty());
+
+        ut_info!("(test_down) Success.");
+    }
+}
@@ -0,0 +1,237 @@
+//! Soft Deletion
+//! keeps the details of removed assets until they are restored or purged
+//!
+//! Removed assets are not deleted from the asset store right away. Instead,
+//! their `deleted_at` is set in the store, which hides them from all
+//! listings. The time, actor and reason of each removal are recorded here,
+//! in a JSON file. Once the retention period has passed, the assets are
+//! permanently deleted from the asset store by the [`purge`] task.
+//!
+//! Only the instance which removed an asset records the details of the
+//! removal, and purges the asset.
+
+#[macro_use]
+pub mod macros;
+pub mod purge;
+
+use crate::events::AssetKind;
+use crate::json_file::{JsonFileStore, JsonRecord};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::OnceCell;
+use utoipa::{IntoParams, ToSchema};
+
+pub(crate) static DELETIONS: OnceCell<DeletionStore> = OnceCell::const_new();
+
+/// Returns DELETIONS, a [`DeletionStore`] configured using a Config object
+/// generated from environment variables.
+/// Initializes DELETIONS if it hasn't been initialized yet, in memory if
+/// the deletions file can not be used.
+pub async fn get_deletions() -> &'static DeletionStore {
+    DELETIONS
+        .get_or_init(|| async move {
+            let config = crate::Config::try_from_env().unwrap_or_default();
+            match DeletionStore::open(&config.deletions_file) {
+                Ok(store) => store,
+                Err(e) => {
+                    deletions_error!("(get_deletions) {}, deletions will be kept in memory.", e);
+                    DeletionStore::default()
+                }
+            }
+        })
+        .await
+}
+
+/// Initializes DELETIONS with the deletions file configured in `config`,
+/// failing if the file can not be used.
+pub async fn init_deletions(
+    config: &crate::Config,
+) -> Result<&'static DeletionStore, std::io::Error> {
+    DELETIONS
+        .get_or_try_init(|| async move { DeletionStore::open(&config.deletions_file) })
+        .await
+}
+
+/// Soft deletion of an asset.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DeletionRecord {
+    /// The kind of the removed asset.
+    pub kind: AssetKind,
+    /// The UUID of the removed asset.
+    pub asset_id: String,
+    /// Time at which the asset has been removed, if the removal has been
+    /// recorded by this instance.
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// The actor who removed the asset, if provided.
+    pub deleted_by: Option<String>,
+    /// The reason the asset has been removed, if provided.
+    pub reason: Option<String>,
+}
+
+/// Filter for the listing of removed assets.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct DeletionFilter {
+    /// Only return removed assets of this kind.
+    pub kind: Option<AssetKind>,
+}
+
+impl JsonRecord for DeletionRecord {
+    fn key(&self) -> &str {
+        &self.asset_id
+    }
+}
+
+/// Keeps the records of the removals made by this instance.
+///
+/// If no file path is provided, the records are kept in memory only.
+#[derive(Clone, Debug, Default)]
+pub struct DeletionStore {
+    records: JsonFileStore<DeletionRecord>,
+}
+
+impl DeletionStore {
+    /// Open the deletions stored at `path`.
+    ///
+    /// Fails if the file can not be used.
+    pub fn open(path: &str) -> Result<Self, std::io::Error> {
+        if path.is_empty() {
+            deletions_warn!(
+                "(DeletionStore::open) no deletions file configured, deletions will be kept in memory."
+            );
+        }
+
+        let records = JsonFileStore::open(path)
+            .map_err(|e| std::io::Error::new(e.kind(), format!("deletions {}", e)))?;
+        deletions_info!(
+            "(DeletionStore::open) opened deletions file [{}] with {} removed asset(s).",
+            path,
+            records.len()
+        );
+        Ok(Self { records })
+    }
+
+    /// Record the removal of an asset, replacing an earlier record of the
+    /// same asset.
+    pub fn record(&self, record: DeletionRecord) -> Result<(), std::io::Error> {
+        self.records.insert(record.clone())?;
+        deletions_debug!("(record) {:?}", record);
+
+        Ok(())
+    }
+
+    /// Forget the removal of an asset once it has been restored or purged,
+    /// returning its record, if any.
+    pub fn forget(&self, asset_id: &str) -> Result<Option<DeletionRecord>, std::io::Error> {
+        let record = self.records.remove(asset_id)?;
+        if let Some(record) = &record {
+            deletions_debug!("(forget) {:?}", record);
+        }
+
+        Ok(record)
+    }
+
+    /// Returns the removal record of an asset, if removed.
+    pub fn get(&self, asset_id: &str) -> Option<DeletionRecord> {
+        self.records.get(asset_id)
+    }
+
+    /// Returns the removed assets passing the filter, oldest removal first.
+    pub fn list(&self, filter: &DeletionFilter) -> Vec<DeletionRecord> {
+        let mut list: Vec<DeletionRecord> = self
+            .records
+            .values()
+            .into_iter()
+            .filter(|record| match filter.kind {
+                Some(kind) => record.kind == kind,
+                None => true,
+            })
+            .collect();
+        list.sort_by_key(|record| record.deleted_at);
+        list
+    }
+
+    /// Returns the assets which have been removed before `before`.
+    pub fn expired(&self, before: DateTime<Utc>) -> Vec<DeletionRecord> {
+        self.list(&DeletionFilter::default())
+            .into_iter()
+            .filter(|record| record.deleted_at.is_some_and(|time| time < before))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn record(kind: AssetKind, asset_id: &str, deleted_at: DateTime<Utc>) -> DeletionRecord {
+        DeletionRecord {
+            kind,
+            asset_id: asset_id.to_string(),
+            deleted_at: Some(deleted_at),
+            deleted_by: Some("operator".to_string()),
+            reason: Some("decommissioned".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_forget() {
+        crate::get_log_handle().await;
+        ut_info!("(test_record_forget) Start.");
+
+        let store = DeletionStore::default();
+        let now = Utc::now();
+        let aircraft = record(AssetKind::Aircraft, "aircraft", now);
+
+        store.record(aircraft.clone()).unwrap();
+        assert_eq!(store.get("aircraft"), Some(aircraft.clone()));
+
+        // A later removal of the same asset replaces the record
+        let removed_again = record(AssetKind::Aircraft, "aircraft", now + Duration::hours(1));
+        store.record(removed_again.clone()).unwrap();
+        assert_eq!(store.get("aircraft"), Some(removed_again.clone()));
+
+        assert_eq!(store.forget("aircraft").unwrap(), Some(removed_again));
+        assert_eq!(store.get("aircraft"), None);
+        assert_eq!(store.forget("aircraft").unwrap(), None);
+
+        ut_info!("(test_record_forget) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_list_expired() {
+        crate::get_log_handle().await;
+        ut_info!("(test_list_expired) Start.");
+
+        let store = DeletionStore::default();
+        let now = Utc::now();
+        store
+            .record(record(AssetKind::Vertipad, "pad", now - Duration::days(40)))
+            .unwrap();
+        store
+            .record(record(
+                AssetKind::Aircraft,
+                "aircraft",
+                now - Duration::days(1),
+            ))
+            .unwrap();
+
+        let list = store.list(&DeletionFilter::default());
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].asset_id, "pad");
+        let list = store.list(&DeletionFilter {
+            kind: Some(AssetKind::Aircraft),
+        });
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].asset_id, "aircraft");
+
+        let expired = store.expired(now - Duration::days(30));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].asset_id, "pad");
+
+        store.forget("pad").unwrap();
+        assert!(store.expired(now - Duration::days(30)).is_empty());
+
+        ut_info!("(test_list_expired) Success.");
+    }
+}
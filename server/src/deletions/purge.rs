@@ -0,0 +1,231 @@
+//! Retention purge
+//!
+//! Permanently deletes removed assets from the asset store once their
+//! retention period has passed.
+//!
+//! Assets which have been restored in the meantime are kept, and their
+//! removal is forgotten. Purged assets are recorded in the audit log.
+
+use super::{DeletionRecord, DeletionStore};
+use crate::audit::{AuditAction, AuditContext, AuditLog};
+use crate::cache::AssetCache;
+use crate::events::AssetKind;
+use crate::store::AssetStore;
+use crate::Config;
+use chrono::{Duration, Utc};
+
+/// Interval at which expired assets are purged.
+const PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Permanently delete a removed asset from the asset store, unless it
+/// has been restored.
+///
+/// Returns `false` if the asset has been restored.
+async fn purge_asset(assets: &dyn AssetStore, record: &DeletionRecord) -> Result<bool, String> {
+    let id = &record.asset_id;
+    let deleted = match record.kind {
+        AssetKind::Aircraft => assets.vehicles().is_deleted(id).await,
+        AssetKind::Vertiport => assets.vertiports().is_deleted(id).await,
+        AssetKind::Vertipad => assets.vertipads().is_deleted(id).await,
+        AssetKind::AssetGroup => return Err("asset groups can not be purged".to_string()),
+    };
+    if !deleted.map_err(|e| e.to_string())? {
+        return Ok(false);
+    }
+
+    let result = match record.kind {
+        AssetKind::Aircraft => assets.vehicles().delete(id).await,
+        AssetKind::Vertiport => assets.vertiports().delete(id).await,
+        AssetKind::Vertipad => assets.vertipads().delete(id).await,
+        AssetKind::AssetGroup => return Err("asset groups can not be purged".to_string()),
+    };
+    result.map(|_| true).map_err(|e| e.to_string())
+}
+
+/// Permanently delete all assets removed more than `retention` ago.
+///
+/// Assets which could not be deleted are retried on the next run. Purged
+/// assets are removed from the `cache`, so they are no longer found once
+/// their removal is forgotten.
+pub async fn purge_expired(
+    assets: &dyn AssetStore,
+    store: &DeletionStore,
+    cache: &AssetCache,
+    audit: &AuditLog,
+    retention: Duration,
+) {
+    for record in store.expired(Utc::now() - retention) {
+        let result = purge_asset(assets, &record).await;
+        cache.invalidate(record.kind, &record.asset_id);
+        let purged = match result {
+            Ok(purged) => purged,
+            Err(e) => {
+                deletions_warn!(
+                    "(purge_expired) could not purge {:?} [{}]: {}",
+                    record.kind,
+                    record.asset_id,
+                    e
+                );
+                continue;
+            }
+        };
+        if purged {
+            let context = AuditContext {
+                reason: Some(format!(
+                    "retention period of {} day(s) passed",
+                    retention.num_days()
+                )),
+                ..Default::default()
+            };
+            audit.record(
+                record.kind,
+                &record.asset_id,
+                AuditAction::Purged,
+                &context,
+                vec![],
+            );
+        }
+
+        match store.forget(&record.asset_id) {
+            Ok(_) if purged => deletions_info!(
+                "(purge_expired) purged {:?} [{}] removed at {:?}.",
+                record.kind,
+                record.asset_id,
+                record.deleted_at
+            ),
+            Ok(_) => deletions_info!(
+                "(purge_expired) {:?} [{}] has been restored, not purged.",
+                record.kind,
+                record.asset_id
+            ),
+            Err(e) => deletions_error!(
+                "(purge_expired) could not update deletions file for {:?} [{}]: {}",
+                record.kind,
+                record.asset_id,
+                e
+            ),
+        }
+    }
+}
+
+/// Periodically purges the removed assets of the global
+/// [`DeletionStore`](super::get_deletions) once their retention period
+/// has passed, unless the retention is set to 0 days.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: Runs until the process is stopped.
+pub async fn purge_task(config: Config) {
+    if config.deletion_retention_days == 0 {
+        deletions_info!("(purge_task) retention disabled, removed assets are never purged.");
+        return;
+    }
+
+    let store = super::get_deletions().await.clone();
+    let cache = crate::cache::get_asset_cache().await.clone();
+    let audit = crate::audit::get_audit_log().await.clone();
+    let retention = Duration::days(config.deletion_retention_days as i64);
+    let assets = crate::store::get_store().await.clone();
+    deletions_info!(
+        "(purge_task) purging removed assets after {} day(s).",
+        retention.num_days()
+    );
+
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        purge_expired(assets.as_ref(), &store, &cache, &audit, retention).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::Metrics;
+    use crate::store::MemoryStore;
+    use svc_storage_client_grpc::prelude::*;
+
+    #[tokio::test]
+    async fn test_purge_expired() {
+        crate::get_log_handle().await;
+        ut_info!("(test_purge_expired) Start.");
+
+        let assets = MemoryStore::default();
+        let deletions = DeletionStore::default();
+        let cache = AssetCache::new(&Config::default(), Metrics::new());
+        let audit = AuditLog::in_memory(Metrics::new());
+        let mut ids = vec![];
+        for days in [10, 1, 10] {
+            let vertipad = assets
+                .vertipads()
+                .insert(vertipad::Data::default())
+                .await
+                .unwrap();
+            assets.vertipads().soft_delete(&vertipad.id).await.unwrap();
+            deletions
+                .record(DeletionRecord {
+                    kind: AssetKind::Vertipad,
+                    asset_id: vertipad.id.clone(),
+                    deleted_at: Some(Utc::now() - Duration::days(days)),
+                    deleted_by: None,
+                    reason: None,
+                })
+                .unwrap();
+            ids.push(vertipad.id);
+        }
+        assets.vertipads().restore(&ids[2]).await.unwrap();
+
+        purge_expired(&assets, &deletions, &cache, &audit, Duration::days(5)).await;
+        assert!(assets.vertipads().get_by_id(&ids[0]).await.is_err());
+        assert!(deletions.get(&ids[0]).is_none());
+        let history = audit.history(AssetKind::Vertipad, &ids[0]).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].action, AuditAction::Purged);
+        // Assets still within the retention period are kept
+        assert!(assets.vertipads().get_by_id(&ids[1]).await.is_ok());
+        assert!(deletions.get(&ids[1]).is_some());
+        // Restored assets are kept, and their removal forgotten
+        assert!(assets.vertipads().get_by_id(&ids[2]).await.is_ok());
+        assert!(deletions.get(&ids[2]).is_none());
+        assert!(audit
+            .query(&Default::default())
+            .unwrap()
+            .iter()
+            .all(|entry| entry.asset_id == ids[0]));
+
+        ut_info!("(test_purge_expired) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_purge_failed() {
+        crate::get_log_handle().await;
+        ut_info!("(test_purge_failed) Start.");
+
+        let assets = MemoryStore::default();
+        let deletions = DeletionStore::default();
+        let cache = AssetCache::new(&Config::default(), Metrics::new());
+        let audit = AuditLog::in_memory(Metrics::new());
+        let vertipad = assets
+            .vertipads()
+            .insert(vertipad::Data::default())
+            .await
+            .unwrap();
+        assets.vertipads().soft_delete(&vertipad.id).await.unwrap();
+        deletions
+            .record(DeletionRecord {
+                kind: AssetKind::Vertipad,
+                asset_id: vertipad.id.clone(),
+                deleted_at: Some(Utc::now() - Duration::days(10)),
+                deleted_by: None,
+                reason: None,
+            })
+            .unwrap();
+
+        // Assets which could not be deleted are not recorded as purged
+        assets.fail("vertipad", "delete");
+        purge_expired(&assets, &deletions, &cache, &audit, Duration::days(5)).await;
+        assert!(assets.vertipads().get_by_id(&vertipad.id).await.is_ok());
+        assert!(deletions.get(&vertipad.id).is_some());
+        assert!(audit.query(&Default::default()).unwrap().is_empty());
+
+        ut_info!("(test_purge_failed) Success.");
+    }
+}
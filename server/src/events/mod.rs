@@ -0,0 +1,638 @@
+//! Asset Events
+//! provides change notifications for assets and their durable delivery to AMQP
+
+#[macro_use]
+pub mod macros;
+pub mod outbox;
+pub mod relay;
+
+use chrono::{DateTime, Utc};
+use futures::stream::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::OnceCell;
+use utoipa::{IntoParams, ToSchema};
+
+use outbox::Outbox;
+
+/// Number of events buffered for each subscriber before it starts
+/// missing events.
+const SUBSCRIBER_BUFFER_SIZE: usize = 256;
+
+/// Default number of recent events kept for resuming subscriptions.
+const DEFAULT_HISTORY_SIZE: usize = 1000;
+
+pub(crate) static NOTIFIER: OnceCell<ChangeNotifier> = OnceCell::const_new();
+
+/// Returns NOTIFIER, a [`ChangeNotifier`] configured using a Config object
+/// generated from environment variables.
+/// Initializes NOTIFIER if it hasn't been initialized yet, with an
+/// in-memory outbox if the outbox file can not be used.
+pub async fn get_notifier() -> &'static ChangeNotifier {
+    NOTIFIER
+        .get_or_init(|| async move {
+            let config = crate::Config::try_from_env().unwrap_or_default();
+            match ChangeNotifier::new(&config) {
+                Ok(notifier) => notifier,
+                Err(e) => {
+                    events_error!("(get_notifier) {}, events will be kept in memory.", e);
+                    ChangeNotifier::with_outbox(Some(Outbox::in_memory()))
+                        .with_history_size(config.event_history_size as usize)
+                }
+            }
+        })
+        .await
+}
+
+/// Initializes NOTIFIER with the outbox file configured in `config`,
+/// failing if the file can not be used.
+pub async fn init_notifier(
+    config: &crate::Config,
+) -> Result<&'static ChangeNotifier, std::io::Error> {
+    NOTIFIER
+        .get_or_try_init(|| async move { ChangeNotifier::new(config) })
+        .await
+}
+
+/// Kind of asset a change event refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum AssetKind {
+    /// An [`Aircraft`](crate::rest::structs::Aircraft)
+    Aircraft,
+    /// A [`Vertiport`](crate::rest::structs::Vertiport)
+    Vertiport,
+    /// A [`Vertipad`](crate::rest::structs::Vertipad)
+    Vertipad,
+    /// An [`AssetGroup`](crate::rest::structs::AssetGroup)
+    AssetGroup,
+}
+
+impl AssetKind {
+    /// Lowercase name of the asset kind, used in AMQP routing keys.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssetKind::Aircraft => "aircraft",
+            AssetKind::Vertiport => "vertiport",
+            AssetKind::Vertipad => "vertipad",
+            AssetKind::AssetGroup => "asset_group",
+        }
+    }
+}
+
+/// Type of change that happened to an asset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum AssetAction {
+    /// The asset has been registered.
+    Created,
+    /// One or more fields of the asset have been updated.
+    Updated,
+    /// The asset has been removed.
+    Removed,
+}
+
+impl AssetAction {
+    /// Lowercase name of the action, used in AMQP routing keys.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssetAction::Created => "created",
+            AssetAction::Updated => "updated",
+            AssetAction::Removed => "removed",
+        }
+    }
+}
+
+/// A change event for a single asset.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AssetEvent {
+    /// Monotonically increasing sequence number of the event.
+    pub sequence: u64,
+    /// The kind of asset that changed.
+    pub kind: AssetKind,
+    /// The UUID of the asset that changed.
+    pub asset_id: String,
+    /// The UUID of the [`Operator`](crate::rest::structs::Operator)
+    /// owning the asset, if known.
+    pub operator_id: Option<String>,
+    /// The type of change.
+    pub action: AssetAction,
+    /// Time at which the change was recorded.
+    pub timestamp: DateTime<Utc>,
+    /// Optional payload describing the change.
+    #[schema(value_type = Option<Object>)]
+    pub data: Option<serde_json::Value>,
+    /// Epoch of the sequence numbers, changing when they are reset by a
+    /// restart of the service.
+    #[serde(default)]
+    pub epoch: String,
+}
+
+impl AssetEvent {
+    /// AMQP routing key for this event, e.g. `asset.vertipad.updated`.
+    pub fn routing_key(&self) -> String {
+        format!("asset.{}.{}", self.kind.as_str(), self.action.as_str())
+    }
+}
+
+/// Filter to select the asset events a subscriber is interested in.
+///
+/// Fields which are not provided match all events.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct AssetEventFilter {
+    /// Only return events for assets owned by this operator UUID.
+    ///
+    /// Not supported by the REST API yet, as the owners of the assets are
+    /// not known.
+    pub operator_id: Option<String>,
+    /// Only return events for this kind of asset.
+    pub kind: Option<AssetKind>,
+    /// Only return events for the asset with this UUID.
+    pub asset_id: Option<String>,
+}
+
+impl AssetEventFilter {
+    /// Check if the given event passes this filter.
+    pub fn matches(&self, event: &AssetEvent) -> bool {
+        if let Some(operator_id) = &self.operator_id {
+            if event.operator_id.as_ref() != Some(operator_id) {
+                return false;
+            }
+        }
+        if let Some(kind) = &self.kind {
+            if &event.kind != kind {
+                return false;
+            }
+        }
+        if let Some(asset_id) = &self.asset_id {
+            if &event.asset_id != asset_id {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Errors returned when resuming a subscription from a sequence number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResumeError {
+    /// The requested events are no longer kept in the history.
+    Expired {
+        /// Oldest sequence number still available.
+        oldest: u64,
+    },
+    /// The requested sequence number has not been issued (yet), or was
+    /// issued in another epoch, which happens if the sequence numbers have
+    /// been reset by a restart.
+    Unknown {
+        /// Last sequence number issued.
+        last: u64,
+    },
+}
+
+impl std::fmt::Display for ResumeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ResumeError::Expired { oldest } => write!(
+                f,
+                "events are no longer available, oldest available sequence is {}",
+                oldest
+            ),
+            ResumeError::Unknown { last } => {
+                write!(f, "unknown sequence, last issued sequence is {}", last)
+            }
+        }
+    }
+}
+
+/// Error ending a subscription which could not keep up with the events.
+///
+/// The subscriber missed events, and should resubscribe with the `last`
+/// sequence number to receive them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lagged {
+    /// Last sequence number received by the subscriber before the missed
+    /// events, whether it passed the filter or not.
+    pub last: u64,
+    /// Number of missed events.
+    pub skipped: u64,
+}
+
+impl std::fmt::Display for Lagged {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "subscriber missed {} event(s), resume after sequence {}",
+            self.skipped, self.last
+        )
+    }
+}
+
+impl std::error::Error for Lagged {}
+
+/// Filter deciding which events are sent to a subscriber.
+pub trait EventFilter: Send + 'static {
+    /// Check if the given event passes this filter.
+    fn matches(&self, event: &AssetEvent) -> bool;
+}
+
+impl EventFilter for AssetEventFilter {
+    fn matches(&self, event: &AssetEvent) -> bool {
+        AssetEventFilter::matches(self, event)
+    }
+}
+
+#[derive(Debug)]
+struct NotifierState {
+    next_sequence: u64,
+    history: VecDeque<AssetEvent>,
+    history_size: usize,
+}
+
+/// Emits asset change events.
+///
+/// Every emitted event gets a sequence number and the epoch of the
+/// notifier, a random id telling apart the sequence numbers issued before
+/// and after a restart. When AMQP is enabled, each event
+/// is written to the [`Outbox`] before returning so it will be delivered
+/// by the [`relay`] even if the broker is unavailable at the time.
+/// Events are also broadcast to all in-process subscribers, and the most
+/// recent events are kept so subscribers can resume after a reconnect.
+#[derive(Clone, Debug)]
+pub struct ChangeNotifier {
+    epoch: String,
+    state: Arc<Mutex<NotifierState>>,
+    outbox: Option<Outbox>,
+    sender: broadcast::Sender<AssetEvent>,
+}
+
+impl ChangeNotifier {
+    /// Create a new [`ChangeNotifier`] using the provided configuration.
+    ///
+    /// The outbox is only used if AMQP is enabled, as there would be
+    /// nothing to relay the events to otherwise. Fails if the outbox file
+    /// can not be used.
+    pub fn new(config: &crate::Config) -> Result<Self, std::io::Error> {
+        let outbox = match config.amqp_enabled {
+            true => Some(Outbox::open(&config.outbox_file)?),
+            false => None,
+        };
+
+        Ok(Self::with_outbox(outbox).with_history_size(config.event_history_size as usize))
+    }
+
+    /// Create a new [`ChangeNotifier`] writing to the provided [`Outbox`], if any.
+    pub fn with_outbox(outbox: Option<Outbox>) -> Self {
+        let next_sequence = match &outbox {
+            Some(outbox) => outbox.last_sequence() + 1,
+            None => 1,
+        };
+
+        let (sender, _) = broadcast::channel(SUBSCRIBER_BUFFER_SIZE);
+        Self {
+            epoch: uuid::Uuid::new_v4().to_string(),
+            state: Arc::new(Mutex::new(NotifierState {
+                next_sequence,
+                history: VecDeque::new(),
+                history_size: DEFAULT_HISTORY_SIZE,
+            })),
+            outbox,
+            sender,
+        }
+    }
+
+    /// Set the number of recent events kept for resuming subscriptions.
+    pub fn with_history_size(self, history_size: usize) -> Self {
+        {
+            let mut state = self.lock();
+            state.history_size = history_size;
+            while state.history.len() > history_size {
+                state.history.pop_front();
+            }
+        }
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, NotifierState> {
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Returns the epoch of the sequence numbers issued by this notifier.
+    pub fn epoch(&self) -> &str {
+        &self.epoch
+    }
+
+    /// Returns the [`Outbox`] used by this notifier, if any.
+    pub fn outbox(&self) -> Option<&Outbox> {
+        self.outbox.as_ref()
+    }
+
+    /// Record a change for an asset.
+    ///
+    /// Errors writing to the outbox are logged but not returned, since
+    /// the change itself has already been stored by the time this is called.
+    pub fn emit(
+        &self,
+        kind: AssetKind,
+        action: AssetAction,
+        asset_id: &str,
+        data: Option<serde_json::Value>,
+    ) -> AssetEvent {
+        // Keep the lock while publishing so the order of the events is
+        // the same for the outbox, the history and the subscribers.
+        let mut state = self.lock();
+
+        let event = AssetEvent {
+            sequence: state.next_sequence,
+            kind,
+            asset_id: asset_id.to_string(),
+            // TODO(R5): svc-storage does not store asset owners yet
+            operator_id: None,
+            action,
+            timestamp: Utc::now(),
+            data,
+            epoch: self.epoch.clone(),
+        };
+        state.next_sequence += 1;
+
+        events_debug!("(emit) {:?}", event);
+        if let Some(outbox) = &self.outbox {
+            if let Err(e) = outbox.enqueue(event.clone()) {
+                events_error!(
+                    "(emit) could not write event [{}] to outbox: {}",
+                    event.sequence,
+                    e
+                );
+            }
+        }
+
+        if state.history_size > 0 {
+            if state.history.len() >= state.history_size {
+                state.history.pop_front();
+            }
+            state.history.push_back(event.clone());
+        }
+
+        // Sending only fails if there are no subscribers, which is fine
+        let _ = self.sender.send(event.clone());
+
+        event
+    }
+
+    /// Subscribe to all future events matching the provided filter.
+    ///
+    /// The stream ends with a [`Lagged`] error if the subscriber can not
+    /// keep up and misses events.
+    pub fn subscribe<F: EventFilter>(
+        &self,
+        filter: F,
+    ) -> impl Stream<Item = Result<AssetEvent, Lagged>> + Send + 'static {
+        let state = self.lock();
+        live_events(self.sender.subscribe(), state.next_sequence - 1, filter)
+    }
+
+    /// Subscribe to events matching the provided filter, starting with
+    /// the events emitted after the `after` sequence number.
+    ///
+    /// If `after` is `None`, only future events are returned. Resuming is
+    /// rejected if the `epoch` of the `after` sequence number is provided
+    /// and differs from the notifier's. The stream ends with a [`Lagged`]
+    /// error if the subscriber can not keep up and misses events.
+    pub fn subscribe_from<F: EventFilter>(
+        &self,
+        after: Option<u64>,
+        epoch: Option<&str>,
+        filter: F,
+    ) -> Result<impl Stream<Item = Result<AssetEvent, Lagged>> + Send + 'static, ResumeError> {
+        // Subscribe while holding the lock, so no events can be emitted
+        // between collecting the missed events and the live subscription.
+        let state = self.lock();
+        let receiver = self.sender.subscribe();
+
+        let mut missed = vec![];
+        if let Some(after) = after {
+            let last = state.next_sequence - 1;
+            if after > last || epoch.is_some_and(|epoch| epoch != self.epoch) {
+                return Err(ResumeError::Unknown { last });
+            }
+
+            let oldest = match state.history.front() {
+                Some(event) => event.sequence,
+                None => state.next_sequence,
+            };
+            if after + 1 < oldest {
+                return Err(ResumeError::Expired { oldest });
+            }
+
+            missed = state
+                .history
+                .iter()
+                .filter(|event| event.sequence > after && filter.matches(event))
+                .cloned()
+                .collect();
+        }
+        let last = state.next_sequence - 1;
+        drop(state);
+
+        let missed = futures::stream::iter(missed).map(Ok);
+        Ok(missed.chain(live_events(receiver, last, filter)))
+    }
+}
+
+/// Turns a broadcast receiver into a stream of events passing the filter,
+/// starting after the `last` sequence number.
+///
+/// The stream ends with a [`Lagged`] error once events have been missed,
+/// so the subscriber never silently skips sequence numbers.
+fn live_events<F: EventFilter>(
+    receiver: broadcast::Receiver<AssetEvent>,
+    last: u64,
+    filter: F,
+) -> impl Stream<Item = Result<AssetEvent, Lagged>> + Send + 'static {
+    futures::stream::unfold(
+        Some((receiver, last, filter)),
+        |state| async move {
+            let (mut receiver, mut last, filter) = state?;
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        last = event.sequence;
+                        if filter.matches(&event) {
+                            return Some((Ok(event), Some((receiver, last, filter))));
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        events_warn!(
+                            "(live_events) subscriber lagging behind, skipped {} event(s) after sequence {}.",
+                            skipped,
+                            last
+                        );
+                        return Some((Err(Lagged { last, skipped }), None));
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_notifier_emit_sequence() {
+        crate::get_log_handle().await;
+        ut_info!("(test_notifier_emit_sequence) Start.");
+
+        let notifier = ChangeNotifier::with_outbox(None);
+        let first = notifier.emit(AssetKind::Aircraft, AssetAction::Created, "a", None);
+        let second = notifier.emit(AssetKind::Vertipad, AssetAction::Updated, "b", None);
+
+        assert_eq!(first.sequence, 1);
+        assert_eq!(second.sequence, 2);
+        assert_eq!(first.routing_key(), "asset.aircraft.created");
+        assert_eq!(second.routing_key(), "asset.vertipad.updated");
+
+        ut_info!("(test_notifier_emit_sequence) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_notifier_emit_to_outbox() {
+        crate::get_log_handle().await;
+        ut_info!("(test_notifier_emit_to_outbox) Start.");
+
+        let outbox = Outbox::in_memory();
+        let notifier = ChangeNotifier::with_outbox(Some(outbox.clone()));
+        notifier.emit(AssetKind::Vertiport, AssetAction::Removed, "c", None);
+
+        assert_eq!(outbox.backlog(), 1);
+        let pending = outbox.peek();
+        assert!(pending.is_some());
+        assert_eq!(pending.unwrap().asset_id, "c");
+
+        ut_info!("(test_notifier_emit_to_outbox) Success.");
+    }
+
+    #[test]
+    fn test_event_filter_matches() {
+        let notifier = ChangeNotifier::with_outbox(None);
+        let event = notifier.emit(AssetKind::Vertipad, AssetAction::Updated, "pad", None);
+
+        assert!(AssetEventFilter::default().matches(&event));
+        assert!(AssetEventFilter {
+            kind: Some(AssetKind::Vertipad),
+            asset_id: Some("pad".to_string()),
+            ..Default::default()
+        }
+        .matches(&event));
+        assert!(!AssetEventFilter {
+            kind: Some(AssetKind::Aircraft),
+            ..Default::default()
+        }
+        .matches(&event));
+        assert!(!AssetEventFilter {
+            asset_id: Some("other".to_string()),
+            ..Default::default()
+        }
+        .matches(&event));
+        assert!(!AssetEventFilter {
+            operator_id: Some("operator".to_string()),
+            ..Default::default()
+        }
+        .matches(&event));
+    }
+
+    #[tokio::test]
+    async fn test_notifier_subscribe() {
+        crate::get_log_handle().await;
+        ut_info!("(test_notifier_subscribe) Start.");
+
+        let notifier = ChangeNotifier::with_outbox(None);
+        let stream = notifier.subscribe(AssetEventFilter {
+            kind: Some(AssetKind::Aircraft),
+            ..Default::default()
+        });
+        futures::pin_mut!(stream);
+
+        notifier.emit(AssetKind::Vertipad, AssetAction::Updated, "pad", None);
+        notifier.emit(AssetKind::Aircraft, AssetAction::Updated, "aircraft", None);
+
+        let event = stream.next().await;
+        assert!(event.is_some());
+        let event = event.unwrap().unwrap();
+        assert_eq!(event.kind, AssetKind::Aircraft);
+        assert_eq!(event.asset_id, "aircraft");
+
+        ut_info!("(test_notifier_subscribe) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_notifier_subscribe_from() {
+        crate::get_log_handle().await;
+        ut_info!("(test_notifier_subscribe_from) Start.");
+
+        let notifier = ChangeNotifier::with_outbox(None).with_history_size(2);
+        for id in ["a", "b", "c"] {
+            notifier.emit(AssetKind::Aircraft, AssetAction::Updated, id, None);
+        }
+
+        // Event 1 has been dropped from the history
+        let result = notifier.subscribe_from(Some(0), None, AssetEventFilter::default());
+        assert!(matches!(result, Err(ResumeError::Expired { oldest: 2 })));
+
+        // Sequence 4 has not been issued yet
+        let result = notifier.subscribe_from(Some(4), None, AssetEventFilter::default());
+        assert!(matches!(result, Err(ResumeError::Unknown { last: 3 })));
+
+        // Sequence 2 of another epoch was issued before a restart
+        let restarted = ChangeNotifier::with_outbox(None).with_history_size(2);
+        let result = notifier.subscribe_from(
+            Some(2),
+            Some(restarted.epoch()),
+            AssetEventFilter::default(),
+        );
+        assert!(matches!(result, Err(ResumeError::Unknown { last: 3 })));
+
+        // Missed events are replayed before new events
+        let stream = notifier
+            .subscribe_from(Some(1), Some(notifier.epoch()), AssetEventFilter::default())
+            .unwrap();
+        futures::pin_mut!(stream);
+        notifier.emit(AssetKind::Aircraft, AssetAction::Updated, "d", None);
+
+        let mut sequences = vec![];
+        for _ in 0..3 {
+            sequences.push(stream.next().await.unwrap().unwrap().sequence);
+        }
+        assert_eq!(sequences, vec![2, 3, 4]);
+
+        ut_info!("(test_notifier_subscribe_from) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_notifier_subscribe_lagged() {
+        crate::get_log_handle().await;
+        ut_info!("(test_notifier_subscribe_lagged) Start.");
+
+        let notifier = ChangeNotifier::with_outbox(None);
+        notifier.emit(AssetKind::Aircraft, AssetAction::Created, "a", None);
+        let stream = notifier.subscribe(AssetEventFilter::default());
+        futures::pin_mut!(stream);
+        notifier.emit(AssetKind::Aircraft, AssetAction::Updated, "a", None);
+        assert_eq!(stream.next().await.unwrap().unwrap().sequence, 2);
+
+        // Overflow the buffer of the subscriber
+        for _ in 0..SUBSCRIBER_BUFFER_SIZE + 1 {
+            notifier.emit(AssetKind::Aircraft, AssetAction::Updated, "a", None);
+        }
+        let lagged = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(lagged, Lagged { last: 2, skipped: 1 });
+        assert!(stream.next().await.is_none());
+
+        ut_info!("(test_notifier_subscribe_lagged) Success.");
+    }
+}
@@ -0,0 +1,405 @@
+//! Durable outbox for asset events
+//!
+//! Events are appended to a local JSON lines file before they are relayed
+//! to the message broker. Delivered events are acknowledged with an `ack`
+//! record, and the file is compacted once it holds more acknowledged than
+//! pending events.
+//! On startup, all events which have not been acknowledged are restored.
+
+use super::AssetEvent;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::Notify;
+use utoipa::ToSchema;
+
+/// The outbox file is compacted once it holds more than this many `ack`
+/// records per pending event.
+const COMPACTION_RATIO: usize = 1;
+
+/// Single record in the outbox file.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Record {
+    /// Highest sequence number used so far, written when compacting.
+    Sequence { last: u64 },
+    /// A new event waiting to be delivered.
+    Enqueue { event: AssetEvent },
+    /// An event which has been delivered.
+    Ack { sequence: u64 },
+}
+
+/// Statistics of the outbox.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct OutboxStats {
+    /// Number of events waiting to be delivered.
+    pub backlog: usize,
+    /// Time at which the oldest pending event was recorded.
+    pub oldest_pending: Option<DateTime<Utc>>,
+    /// Number of events delivered since startup.
+    pub delivered_total: u64,
+    /// Number of failed delivery attempts since startup.
+    pub failed_attempts_total: u64,
+}
+
+#[derive(Debug)]
+struct OutboxState {
+    file: Option<File>,
+    pending: VecDeque<AssetEvent>,
+    last_sequence: u64,
+    /// Number of `ack` records written since the file was compacted.
+    acked: usize,
+}
+
+/// Outbox holding asset events until they are delivered.
+///
+/// If no file path is provided, the outbox is kept in memory only.
+#[derive(Clone, Debug)]
+pub struct Outbox {
+    path: Option<PathBuf>,
+    state: Arc<Mutex<OutboxState>>,
+    notify: Arc<Notify>,
+    delivered_total: Arc<AtomicU64>,
+    failed_attempts_total: Arc<AtomicU64>,
+}
+
+impl Outbox {
+    /// Open the outbox stored at `path`, restoring any pending events.
+    ///
+    /// Fails if the file can not be used, as the events would not survive
+    /// a restart otherwise.
+    pub fn open(path: &str) -> Result<Self, std::io::Error> {
+        if path.is_empty() {
+            events_warn!("(Outbox::open) no outbox file configured, events will be kept in memory.");
+            return Ok(Self::in_memory());
+        }
+
+        let outbox = Self::try_open(PathBuf::from(path))
+            .map_err(|e| std::io::Error::new(e.kind(), format!("outbox file [{}]: {}", path, e)))?;
+        events_info!(
+            "(Outbox::open) opened outbox [{}] with {} pending event(s).",
+            path,
+            outbox.backlog()
+        );
+        Ok(outbox)
+    }
+
+    /// Create a new, empty, in-memory outbox.
+    pub fn in_memory() -> Self {
+        Self::new(
+            None,
+            OutboxState {
+                file: None,
+                pending: VecDeque::new(),
+                last_sequence: 0,
+                acked: 0,
+            },
+        )
+    }
+
+    fn new(path: Option<PathBuf>, state: OutboxState) -> Self {
+        Self {
+            path,
+            state: Arc::new(Mutex::new(state)),
+            notify: Arc::new(Notify::new()),
+            delivered_total: Arc::new(AtomicU64::new(0)),
+            failed_attempts_total: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    fn try_open(path: PathBuf) -> Result<Self, std::io::Error> {
+        let mut pending: VecDeque<AssetEvent> = VecDeque::new();
+        let mut last_sequence = 0;
+
+        if path.exists() {
+            let reader = BufReader::new(File::open(&path)?);
+            for (line_number, line) in reader.lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<Record>(&line) {
+                    Ok(Record::Sequence { last }) => {
+                        last_sequence = last_sequence.max(last);
+                    }
+                    Ok(Record::Enqueue { event }) => {
+                        last_sequence = last_sequence.max(event.sequence);
+                        pending.push_back(event);
+                    }
+                    Ok(Record::Ack { sequence }) => {
+                        last_sequence = last_sequence.max(sequence);
+                        pending.retain(|event| event.sequence != sequence);
+                    }
+                    Err(e) => {
+                        // A partially written last line is expected after a crash
+                        events_warn!(
+                            "(Outbox::try_open) skipping invalid record on line {}: {}",
+                            line_number + 1,
+                            e
+                        );
+                    }
+                }
+            }
+        }
+
+        let file = compact(&path, last_sequence, &pending)?;
+        Ok(Self::new(
+            Some(path),
+            OutboxState {
+                file: Some(file),
+                pending,
+                last_sequence,
+                acked: 0,
+            },
+        ))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, OutboxState> {
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Durably store a new event.
+    ///
+    /// The event is only added to the pending queue once it has been
+    /// written to the outbox file.
+    pub fn enqueue(&self, event: AssetEvent) -> Result<(), std::io::Error> {
+        let mut state = self.lock();
+        if let Some(file) = state.file.as_mut() {
+            write_record(
+                file,
+                &Record::Enqueue {
+                    event: event.clone(),
+                },
+            )?;
+            file.sync_data()?;
+        }
+        state.last_sequence = state.last_sequence.max(event.sequence);
+        state.pending.push_back(event);
+        drop(state);
+
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    /// Returns the oldest pending event without removing it.
+    pub fn peek(&self) -> Option<AssetEvent> {
+        self.lock().pending.front().cloned()
+    }
+
+    /// Mark the event with the given sequence number as delivered.
+    pub fn ack(&self, sequence: u64) -> Result<(), std::io::Error> {
+        let mut state = self.lock();
+        let before = state.pending.len();
+        state.pending.retain(|event| event.sequence != sequence);
+        if state.pending.len() == before {
+            return Ok(());
+        }
+        self.delivered_total.fetch_add(1, Ordering::Relaxed);
+
+        let (Some(path), Some(file)) = (&self.path, state.file.as_mut()) else {
+            return Ok(());
+        };
+        write_record(file, &Record::Ack { sequence })?;
+        file.sync_data()?;
+        state.acked += 1;
+
+        // Keep the file from growing while events keep coming in
+        if state.acked > state.pending.len() * COMPACTION_RATIO {
+            state.file = Some(compact(path, state.last_sequence, &state.pending)?);
+            state.acked = 0;
+        }
+
+        Ok(())
+    }
+
+    /// Register a failed delivery attempt.
+    pub fn record_failure(&self) {
+        self.failed_attempts_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Wait until a new event has been enqueued.
+    pub async fn notified(&self) {
+        self.notify.notified().await
+    }
+
+    /// Number of events waiting to be delivered.
+    pub fn backlog(&self) -> usize {
+        self.lock().pending.len()
+    }
+
+    /// Highest sequence number known to the outbox.
+    pub fn last_sequence(&self) -> u64 {
+        self.lock().last_sequence
+    }
+
+    /// Returns the current [`OutboxStats`].
+    pub fn stats(&self) -> OutboxStats {
+        let state = self.lock();
+        OutboxStats {
+            backlog: state.pending.len(),
+            oldest_pending: state.pending.front().map(|event| event.timestamp),
+            delivered_total: self.delivered_total.load(Ordering::Relaxed),
+            failed_attempts_total: self.failed_attempts_total.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Rewrite the outbox file at `path` so it only contains the pending
+/// events, returning the file opened for appending.
+///
+/// The events are written to a temporary file first, so a crash while
+/// compacting never loses the pending events.
+fn compact(
+    path: &PathBuf,
+    last_sequence: u64,
+    pending: &VecDeque<AssetEvent>,
+) -> Result<File, std::io::Error> {
+    let temp_path = path.with_extension("tmp");
+    let mut file = File::create(&temp_path)?;
+    write_record(
+        &mut file,
+        &Record::Sequence {
+            last: last_sequence,
+        },
+    )?;
+    for event in pending.iter() {
+        write_record(
+            &mut file,
+            &Record::Enqueue {
+                event: event.clone(),
+            },
+        )?;
+    }
+    file.sync_data()?;
+    std::fs::rename(&temp_path, path)?;
+    OpenOptions::new().append(true).open(path)
+}
+
+fn write_record(file: &mut File, record: &Record) -> Result<(), std::io::Error> {
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{AssetAction, AssetKind};
+
+    fn event(sequence: u64) -> AssetEvent {
+        AssetEvent {
+            sequence,
+            kind: AssetKind::Aircraft,
+            asset_id: uuid::Uuid::new_v4().to_string(),
+            operator_id: None,
+            action: AssetAction::Created,
+            timestamp: Utc::now(),
+            data: None,
+            epoch: String::from("epoch"),
+        }
+    }
+
+    fn temp_path() -> String {
+        std::env::temp_dir()
+            .join(format!("svc-assets-outbox-{}.jsonl", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_outbox_enqueue_ack() {
+        crate::get_log_handle().await;
+        ut_info!("(test_outbox_enqueue_ack) Start.");
+
+        let outbox = Outbox::in_memory();
+        outbox.enqueue(event(1)).unwrap();
+        outbox.enqueue(event(2)).unwrap();
+        assert_eq!(outbox.backlog(), 2);
+        assert_eq!(outbox.last_sequence(), 2);
+        assert_eq!(outbox.peek().unwrap().sequence, 1);
+
+        outbox.ack(1).unwrap();
+        assert_eq!(outbox.backlog(), 1);
+        assert_eq!(outbox.peek().unwrap().sequence, 2);
+
+        // Acknowledging an unknown event is a no-op
+        outbox.ack(1).unwrap();
+        outbox.record_failure();
+
+        let stats = outbox.stats();
+        assert_eq!(stats.backlog, 1);
+        assert_eq!(stats.delivered_total, 1);
+        assert_eq!(stats.failed_attempts_total, 1);
+
+        ut_info!("(test_outbox_enqueue_ack) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_outbox_restore_from_file() {
+        crate::get_log_handle().await;
+        ut_info!("(test_outbox_restore_from_file) Start.");
+
+        let path = temp_path();
+        {
+            let outbox = Outbox::open(&path).unwrap();
+            outbox.enqueue(event(1)).unwrap();
+            outbox.enqueue(event(2)).unwrap();
+            outbox.enqueue(event(3)).unwrap();
+            outbox.ack(2).unwrap();
+        }
+
+        let outbox = Outbox::open(&path).unwrap();
+        assert_eq!(outbox.backlog(), 2);
+        assert_eq!(outbox.last_sequence(), 3);
+        assert_eq!(outbox.peek().unwrap().sequence, 1);
+
+        outbox.ack(1).unwrap();
+        outbox.ack(3).unwrap();
+        assert_eq!(outbox.backlog(), 0);
+
+        // All events were delivered, so nothing should be restored
+        // while the sequence numbers should continue where they left off
+        let outbox = Outbox::open(&path).unwrap();
+        assert_eq!(outbox.backlog(), 0);
+        assert_eq!(outbox.last_sequence(), 3);
+
+        let _ = std::fs::remove_file(&path);
+        ut_info!("(test_outbox_restore_from_file) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_outbox_compaction() {
+        crate::get_log_handle().await;
+        ut_info!("(test_outbox_compaction) Start.");
+
+        let path = temp_path();
+        let lines = || std::fs::read_to_string(&path).unwrap().lines().count();
+        let outbox = Outbox::open(&path).unwrap();
+        outbox.enqueue(event(1)).unwrap();
+        outbox.enqueue(event(2)).unwrap();
+        outbox.ack(1).unwrap();
+        assert_eq!(lines(), 4);
+
+        // The file is compacted while events are still pending
+        outbox.enqueue(event(3)).unwrap();
+        outbox.ack(2).unwrap();
+        assert_eq!(lines(), 2);
+        assert!(!PathBuf::from(&path).with_extension("tmp").exists());
+
+        outbox.enqueue(event(4)).unwrap();
+        let outbox = Outbox::open(&path).unwrap();
+        assert_eq!(outbox.backlog(), 2);
+        assert_eq!(outbox.last_sequence(), 4);
+
+        let _ = std::fs::remove_file(&path);
+        ut_info!("(test_outbox_compaction) Success.");
+    }
+}
@@ -0,0 +1,294 @@
+//! Relay delivering events from the [`Outbox`] to AMQP
+//!
+//! Events are only removed from the outbox after the broker confirmed
+//! them, which gives at-least-once delivery semantics. Consumers should
+//! use the event's `sequence` to detect duplicates.
+
+use super::outbox::Outbox;
+use super::AssetEvent;
+use crate::Config;
+use lapin::options::{BasicPublishOptions, ConfirmSelectOptions, ExchangeDeclareOptions};
+use lapin::types::FieldTable;
+use lapin::{BasicProperties, Channel, Connection, ConnectionProperties, ExchangeKind};
+use std::time::Duration;
+
+/// Initial delay before retrying a failed delivery.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Errors which can occur while publishing an event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublishError {
+    /// Could not connect to the broker.
+    Connection(String),
+    /// The broker did not accept the event.
+    Rejected(String),
+    /// The event could not be serialized.
+    Serialization(String),
+}
+
+impl std::fmt::Display for PublishError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PublishError::Connection(e) => write!(f, "connection error: {}", e),
+            PublishError::Rejected(e) => write!(f, "event rejected: {}", e),
+            PublishError::Serialization(e) => write!(f, "serialization error: {}", e),
+        }
+    }
+}
+
+/// Target the relay delivers events to.
+#[tonic::async_trait]
+pub trait EventPublisher: Send {
+    /// Publish a single event, returning once the event has been
+    /// confirmed by the receiving end.
+    async fn publish(&mut self, event: &AssetEvent) -> Result<(), PublishError>;
+}
+
+/// [`EventPublisher`] publishing events to an AMQP topic exchange.
+///
+/// The connection is established lazily and re-established after errors.
+pub struct AmqpPublisher {
+    uri: String,
+    exchange: String,
+    connection: Option<Connection>,
+    channel: Option<Channel>,
+}
+
+impl AmqpPublisher {
+    /// Create a new [`AmqpPublisher`] using the provided configuration.
+    pub fn new(config: &Config) -> Self {
+        Self {
+            uri: format!("amqp://{}:{}", config.amqp_host, config.amqp_port),
+            exchange: config.amqp_exchange.clone(),
+            connection: None,
+            channel: None,
+        }
+    }
+
+    async fn get_channel(&mut self) -> Result<Channel, PublishError> {
+        if let Some(channel) = &self.channel {
+            if channel.status().connected() {
+                return Ok(channel.clone());
+            }
+        }
+
+        events_info!("(get_channel) connecting to AMQP broker.");
+        let connection = Connection::connect(&self.uri, ConnectionProperties::default())
+            .await
+            .map_err(|e| PublishError::Connection(e.to_string()))?;
+        let channel = connection
+            .create_channel()
+            .await
+            .map_err(|e| PublishError::Connection(e.to_string()))?;
+        channel
+            .confirm_select(ConfirmSelectOptions::default())
+            .await
+            .map_err(|e| PublishError::Connection(e.to_string()))?;
+        channel
+            .exchange_declare(
+                &self.exchange,
+                ExchangeKind::Topic,
+                ExchangeDeclareOptions {
+                    durable: true,
+                    ..Default::default()
+                },
+                FieldTable::default(),
+            )
+            .await
+            .map_err(|e| PublishError::Connection(e.to_string()))?;
+
+        self.connection = Some(connection);
+        self.channel = Some(channel.clone());
+        Ok(channel)
+    }
+
+    fn reset(&mut self) {
+        self.channel = None;
+        self.connection = None;
+    }
+}
+
+#[tonic::async_trait]
+impl EventPublisher for AmqpPublisher {
+    async fn publish(&mut self, event: &AssetEvent) -> Result<(), PublishError> {
+        let payload =
+            serde_json::to_vec(event).map_err(|e| PublishError::Serialization(e.to_string()))?;
+        let channel = self.get_channel().await?;
+
+        let result = match channel
+            .basic_publish(
+                &self.exchange,
+                &event.routing_key(),
+                BasicPublishOptions::default(),
+                &payload,
+                BasicProperties::default()
+                    .with_content_type("application/json".into())
+                    .with_delivery_mode(2),
+            )
+            .await
+        {
+            Ok(confirm) => match confirm.await {
+                Ok(confirmation) if confirmation.is_nack() => Err(PublishError::Rejected(
+                    "broker returned a negative acknowledgement".to_string(),
+                )),
+                Ok(_) => Ok(()),
+                Err(e) => Err(PublishError::Connection(e.to_string())),
+            },
+            Err(e) => Err(PublishError::Connection(e.to_string())),
+        };
+
+        if let Err(PublishError::Connection(_)) = &result {
+            self.reset();
+        }
+        result
+    }
+}
+
+/// Calculate the next retry delay, doubling the previous delay up to `max`.
+fn next_backoff(current: Duration, max: Duration) -> Duration {
+    std::cmp::min(current * 2, max)
+}
+
+/// Deliver pending outbox events through the given publisher.
+///
+/// Returns when all pending events have been delivered, or with the
+/// publish error of the first event that could not be delivered.
+pub async fn deliver_pending<P: EventPublisher>(
+    outbox: &Outbox,
+    publisher: &mut P,
+) -> Result<usize, PublishError> {
+    let mut delivered = 0;
+    while let Some(event) = outbox.peek() {
+        if let Err(e) = publisher.publish(&event).await {
+            outbox.record_failure();
+            return Err(e);
+        }
+
+        if let Err(e) = outbox.ack(event.sequence) {
+            // The event will be delivered again after a restart, which is
+            // allowed with at-least-once semantics.
+            events_error!(
+                "(deliver_pending) could not acknowledge event [{}]: {}",
+                event.sequence,
+                e
+            );
+        }
+        delivered += 1;
+    }
+
+    Ok(delivered)
+}
+
+/// Relay loop delivering outbox events through the given publisher.
+///
+/// Failed deliveries are retried with an exponential backoff.
+/// The loop runs until the process is stopped.
+pub async fn relay<P: EventPublisher>(outbox: Outbox, mut publisher: P, max_backoff: Duration) {
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        match deliver_pending(&outbox, &mut publisher).await {
+            Ok(delivered) => {
+                if delivered > 0 {
+                    events_debug!("(relay) delivered {} event(s).", delivered);
+                }
+                backoff = INITIAL_BACKOFF;
+                // Also wake up periodically in case a notification got lost
+                let _ = tokio::time::timeout(max_backoff, outbox.notified()).await;
+            }
+            Err(e) => {
+                events_warn!(
+                    "(relay) could not deliver event, {} event(s) pending, retrying in {:?}: {}",
+                    outbox.backlog(),
+                    backoff,
+                    e
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = next_backoff(backoff, max_backoff);
+            }
+        }
+    }
+}
+
+/// Starts the relay for the global [`ChangeNotifier`](super::ChangeNotifier)
+/// outbox, if AMQP has been enabled in the configuration.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: Needs a running AMQP broker to work.
+pub async fn relay_task(config: Config) {
+    if !config.amqp_enabled {
+        events_info!("(relay_task) AMQP disabled, not starting event relay.");
+        return;
+    }
+
+    let Some(outbox) = super::get_notifier().await.outbox().cloned() else {
+        events_error!("(relay_task) no outbox available, not starting event relay.");
+        return;
+    };
+
+    events_info!("(relay_task) starting event relay.");
+    let max_backoff = Duration::from_secs(config.outbox_max_backoff_seconds);
+    relay(outbox, AmqpPublisher::new(&config), max_backoff).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{AssetAction, AssetKind, ChangeNotifier};
+
+    /// Publisher failing the first `failures` attempts.
+    struct FlakyPublisher {
+        failures: usize,
+        published: Vec<u64>,
+    }
+
+    #[tonic::async_trait]
+    impl EventPublisher for FlakyPublisher {
+        async fn publish(&mut self, event: &AssetEvent) -> Result<(), PublishError> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(PublishError::Connection("broker down".to_string()));
+            }
+            self.published.push(event.sequence);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_next_backoff() {
+        let max = Duration::from_secs(2);
+        assert_eq!(
+            next_backoff(Duration::from_millis(500), max),
+            Duration::from_secs(1)
+        );
+        assert_eq!(next_backoff(Duration::from_millis(1500), max), max);
+    }
+
+    #[tokio::test]
+    async fn test_deliver_pending_retries() {
+        crate::get_log_handle().await;
+        ut_info!("(test_deliver_pending_retries) Start.");
+
+        let outbox = Outbox::in_memory();
+        let notifier = ChangeNotifier::with_outbox(Some(outbox.clone()));
+        notifier.emit(AssetKind::Aircraft, AssetAction::Created, "a", None);
+        notifier.emit(AssetKind::Aircraft, AssetAction::Updated, "a", None);
+
+        let mut publisher = FlakyPublisher {
+            failures: 1,
+            published: vec![],
+        };
+
+        // Broker is down, nothing should be removed from the outbox
+        let result = deliver_pending(&outbox, &mut publisher).await;
+        assert!(result.is_err());
+        assert_eq!(outbox.backlog(), 2);
+        assert_eq!(outbox.stats().failed_attempts_total, 1);
+
+        // Broker is back, all events should be delivered in order
+        let result = deliver_pending(&outbox, &mut publisher).await;
+        assert_eq!(result, Ok(2));
+        assert_eq!(outbox.backlog(), 0);
+        assert_eq!(publisher.published, vec![1, 2]);
+
+        ut_info!("(test_deliver_pending_retries) Success.");
+    }
+}
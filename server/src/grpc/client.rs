@@ -1,5 +1,10 @@
 //! gRPC client helpers implementation
+use super::policy::{get_storage_policy, is_transient, BreakerState};
+use std::future::Future;
+use std::time::Instant;
 use tokio::sync::OnceCell;
+use tonic::Status;
+use tracing::Instrument;
 
 // FIXME: import other microservices' GRPC clients instead, this is just an example.
 use svc_storage_client_grpc::prelude::Clients;
@@ -37,6 +42,119 @@ impl GrpcClients {
     }
 }
 
+/// Await a call to a svc-storage `resource`, recording its latency and
+/// result and a client span as child of the current span.
+///
+/// The call is given up after the storage timeout, and failed right away
+/// while the circuit breaker of the resource is open. It is not retried,
+/// use [`storage_read`] for idempotent reads.
+///
+/// The svc-storage client does not accept request metadata, so the trace
+/// context is not propagated to svc-storage itself.
+pub async fn storage_call<T>(
+    resource: &'static str,
+    operation: &'static str,
+    call: impl Future<Output = Result<T, Status>>,
+) -> Result<T, Status> {
+    let policy = get_storage_policy().await;
+    let breaker = policy.breaker(resource);
+    if let Err(retry_in) = breaker.acquire() {
+        grpc_debug!(
+            "(storage_call) {} {} failed, circuit open.",
+            resource,
+            operation
+        );
+        return Err(Status::unavailable(format!(
+            "svc-storage {} circuit open, retry in {}s",
+            resource,
+            retry_in.as_secs().max(1)
+        )));
+    }
+
+    let span = tracing::info_span!(
+        "storage_call",
+        otel.name = %format!("svc-storage {}/{}", resource, operation),
+        otel.kind = "client",
+        rpc.system = "grpc",
+        rpc.service = resource,
+        rpc.method = operation,
+        otel.status_message = tracing::field::Empty,
+    );
+
+    let start = Instant::now();
+    let result = match policy.timeout() {
+        Some(timeout) => match tokio::time::timeout(timeout, call)
+            .instrument(span.clone())
+            .await
+        {
+            Ok(result) => result,
+            Err(_) => Err(Status::deadline_exceeded(format!(
+                "svc-storage {} {} timed out after {:?}",
+                resource, operation, timeout
+            ))),
+        },
+        None => call.instrument(span.clone()).await,
+    };
+    breaker.record(result.as_ref().map(|_| ()));
+    if let Err(e) = &result {
+        grpc_debug!(
+            "(storage_call) {} {} failed after {:?}: {}",
+            resource,
+            operation,
+            start.elapsed(),
+            e
+        );
+        span.record("otel.status_message", e.message());
+    }
+
+    crate::metrics::get_metrics().await.record_storage_call(
+        resource,
+        operation,
+        result.is_ok(),
+        start.elapsed(),
+    );
+    result
+}
+
+/// Await an idempotent read from a svc-storage `resource`, made by `call`,
+/// like [`storage_call`].
+///
+/// Reads failing with a transient error are made again, up to the
+/// configured number of retries and with an exponential backoff, unless
+/// the circuit breaker of the resource has opened.
+pub async fn storage_read<T, F, Fut>(
+    resource: &'static str,
+    operation: &'static str,
+    mut call: F,
+) -> Result<T, Status>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Status>>,
+{
+    let policy = get_storage_policy().await;
+    let mut retry = 0;
+    loop {
+        match storage_call(resource, operation, call()).await {
+            Err(status)
+                if retry < policy.read_retries()
+                    && is_transient(&status)
+                    && policy.breaker(resource).state() == BreakerState::Closed =>
+            {
+                let backoff = policy.backoff(retry);
+                grpc_debug!(
+                    "(storage_read) retrying {} {} in {:?}.",
+                    resource,
+                    operation,
+                    backoff
+                );
+                tokio::time::sleep(backoff).await;
+                retry += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use svc_storage_client_grpc::prelude::Client;
@@ -65,4 +183,49 @@ mod tests {
 
         ut_info!("(test_grpc_clients_default) Success.");
     }
+
+    #[tokio::test]
+    async fn test_storage_read() {
+        crate::get_log_handle().await;
+        ut_info!("(test_storage_read) Start.");
+
+        // Transient errors are retried
+        let mut attempts = 0;
+        let result = storage_read("test_retried", "get_by_id", || {
+            attempts += 1;
+            let attempt = attempts;
+            async move {
+                match attempt {
+                    1 | 2 => Err(Status::unavailable("connection refused")),
+                    _ => Ok(attempt),
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+
+        // Other errors are not
+        let mut attempts = 0;
+        let result: Result<(), Status> = storage_read("test_not_found", "get_by_id", || {
+            attempts += 1;
+            async { Err(Status::not_found("vehicle not found")) }
+        })
+        .await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::NotFound);
+        assert_eq!(attempts, 1);
+
+        ut_info!("(test_storage_read) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_storage_call_timeout() {
+        crate::get_log_handle().await;
+        ut_info!("(test_storage_call_timeout) Start.");
+
+        let result: Result<(), Status> =
+            storage_call("test_timeout", "update", std::future::pending()).await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::DeadlineExceeded);
+
+        ut_info!("(test_storage_call_timeout) Success.");
+    }
 }
@@ -0,0 +1,295 @@
+//! Call policy of the svc-storage clients
+//!
+//! Each svc-storage call is given up after a timeout. Idempotent reads are
+//! retried with an exponential backoff when they fail with a transient
+//! error. Each svc-storage client has its own [`CircuitBreaker`], so a hung
+//! resource fails calls right away instead of tying up requests until they
+//! time out.
+
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+use tonic::{Code, Status};
+
+pub(crate) static STORAGE_POLICY: OnceCell<CallPolicy> = OnceCell::const_new();
+
+/// Returns STORAGE_POLICY, the [`CallPolicy`] of the svc-storage clients
+/// configured using a Config object generated from environment variables.
+/// Initializes STORAGE_POLICY if it hasn't been initialized yet.
+pub async fn get_storage_policy() -> &'static CallPolicy {
+    STORAGE_POLICY
+        .get_or_init(|| async move {
+            let config = crate::Config::try_from_env().unwrap_or_default();
+            CallPolicy::new(&config)
+        })
+        .await
+}
+
+/// Indicates if a call failing with `status` may succeed if made again.
+pub fn is_transient(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::Unavailable
+            | Code::DeadlineExceeded
+            | Code::ResourceExhausted
+            | Code::Aborted
+            | Code::Unknown
+    )
+}
+
+/// State of a [`CircuitBreaker`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Calls are made.
+    Closed,
+    /// Calls are failed without being made.
+    Open,
+    /// A single trial call is made to find out if the resource recovered.
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct BreakerInner {
+    state: BreakerState,
+    /// Number of consecutive transient failures.
+    failures: u32,
+    /// Time at which the breaker opened or the trial call started.
+    since: Instant,
+}
+
+/// Fails calls to a svc-storage resource right away once `threshold`
+/// consecutive calls failed with a transient error.
+///
+/// After `open_duration`, a single trial call is let through. The breaker
+/// closes if it succeeds and opens again otherwise. A new trial call is let
+/// through if the previous one hasn't completed after `open_duration`.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    resource: &'static str,
+    inner: Arc<Mutex<BreakerInner>>,
+    threshold: u32,
+    open_duration: Duration,
+}
+
+impl CircuitBreaker {
+    /// Create a closed breaker for the svc-storage `resource`, never
+    /// opening if `threshold` is 0.
+    pub fn new(resource: &'static str, threshold: u32, open_duration: Duration) -> Self {
+        Self {
+            resource,
+            inner: Arc::new(Mutex::new(BreakerInner {
+                state: BreakerState::Closed,
+                failures: 0,
+                since: Instant::now(),
+            })),
+            threshold,
+            open_duration,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BreakerInner> {
+        match self.inner.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Returns the current state of the breaker.
+    pub fn state(&self) -> BreakerState {
+        self.lock().state
+    }
+
+    /// Check if a call can be made, returning the time until the next
+    /// call is tried otherwise.
+    pub fn acquire(&self) -> Result<(), Duration> {
+        let mut inner = self.lock();
+        if inner.state == BreakerState::Closed {
+            return Ok(());
+        }
+
+        let elapsed = inner.since.elapsed();
+        if elapsed < self.open_duration {
+            return Err(self.open_duration - elapsed);
+        }
+
+        grpc_info!(
+            "(acquire) svc-storage {} circuit half open, trying a call.",
+            self.resource
+        );
+        inner.state = BreakerState::HalfOpen;
+        inner.since = Instant::now();
+        Ok(())
+    }
+
+    /// Record the result of a call let through by [`acquire`](Self::acquire).
+    pub fn record(&self, result: Result<(), &Status>) {
+        let mut inner = self.lock();
+        match result {
+            Err(status) if is_transient(status) => {
+                inner.failures += 1;
+                let open = inner.state == BreakerState::HalfOpen
+                    || (self.threshold > 0 && inner.failures >= self.threshold);
+                if open && inner.state != BreakerState::Open {
+                    grpc_warn!(
+                        "(record) svc-storage {} circuit opened after {} failure(s): {}",
+                        self.resource,
+                        inner.failures,
+                        status
+                    );
+                    inner.state = BreakerState::Open;
+                    inner.since = Instant::now();
+                }
+            }
+            // Other errors show the resource is responding
+            _ => {
+                if inner.state != BreakerState::Closed {
+                    grpc_info!("(record) svc-storage {} circuit closed.", self.resource);
+                }
+                inner.state = BreakerState::Closed;
+                inner.failures = 0;
+            }
+        }
+    }
+}
+
+/// Timeouts, retries and circuit breakers of the svc-storage calls.
+#[derive(Clone, Debug)]
+pub struct CallPolicy {
+    timeout: Option<Duration>,
+    read_retries: u32,
+    retry_backoff: Duration,
+    breaker_threshold: u32,
+    breaker_open_duration: Duration,
+    breakers: Arc<Mutex<HashMap<&'static str, CircuitBreaker>>>,
+}
+
+impl CallPolicy {
+    /// Create the policy using the provided configuration.
+    pub fn new(config: &crate::Config) -> Self {
+        Self {
+            timeout: match config.storage_timeout_ms {
+                0 => None,
+                timeout => Some(Duration::from_millis(timeout)),
+            },
+            read_retries: config.storage_read_retries,
+            retry_backoff: Duration::from_millis(config.storage_retry_backoff_ms),
+            breaker_threshold: config.storage_breaker_failures,
+            breaker_open_duration: Duration::from_secs(config.storage_breaker_open_seconds),
+            breakers: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the time after which a call is given up, if any.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Returns the number of times a failed read is retried.
+    pub fn read_retries(&self) -> u32 {
+        self.read_retries
+    }
+
+    /// Returns the time to wait before retrying a read for the `retry`th
+    /// time, starting at 0.
+    ///
+    /// The backoff doubles with each retry, and is randomized by up to half
+    /// so concurrent reads are not retried all at once.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .retry_backoff
+            .saturating_mul(2u32.saturating_pow(retry));
+        backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+
+    /// Returns the circuit breaker of the svc-storage `resource`.
+    pub fn breaker(&self, resource: &'static str) -> CircuitBreaker {
+        let mut breakers = match self.breakers.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        breakers
+            .entry(resource)
+            .or_insert_with(|| {
+                CircuitBreaker::new(resource, self.breaker_threshold, self.breaker_open_duration)
+            })
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_breaker() {
+        crate::get_log_handle().await;
+        ut_info!("(test_breaker) Start.");
+
+        let breaker = CircuitBreaker::new("vehicle", 2, Duration::from_millis(50));
+        let unavailable = Status::unavailable("connection refused");
+
+        // Errors other than transient ones don't count
+        breaker.record(Err(&unavailable));
+        breaker.record(Err(&Status::not_found("vehicle not found")));
+        breaker.record(Err(&unavailable));
+        assert_eq!(breaker.state(), BreakerState::Closed);
+
+        breaker.record(Err(&unavailable));
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(breaker.acquire().unwrap_err() <= Duration::from_millis(50));
+
+        // A failed trial call opens the breaker again
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(breaker.acquire().is_ok());
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        assert!(breaker.acquire().is_err());
+        breaker.record(Err(&unavailable));
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        // A successful trial call closes it
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(breaker.acquire().is_ok());
+        breaker.record(Ok(()));
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(breaker.acquire().is_ok());
+
+        let disabled = CircuitBreaker::new("vehicle", 0, Duration::from_secs(60));
+        for _ in 0..10 {
+            disabled.record(Err(&unavailable));
+        }
+        assert_eq!(disabled.state(), BreakerState::Closed);
+
+        ut_info!("(test_breaker) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_policy() {
+        crate::get_log_handle().await;
+        ut_info!("(test_policy) Start.");
+
+        let config = crate::Config {
+            storage_timeout_ms: 0,
+            storage_retry_backoff_ms: 100,
+            ..Default::default()
+        };
+        let policy = CallPolicy::new(&config);
+        assert_eq!(policy.timeout(), None);
+
+        let backoff = policy.backoff(2);
+        assert!(backoff >= Duration::from_millis(200));
+        assert!(backoff <= Duration::from_millis(400));
+
+        // Breakers are shared by all calls to a resource
+        let breaker = policy.breaker("vertipad");
+        breaker.record(Err(&Status::unavailable("connection refused")));
+        assert_eq!(policy.breaker("vertipad").lock().failures, 1);
+        assert_eq!(policy.breaker("vertiport").lock().failures, 0);
+
+        assert!(is_transient(&Status::deadline_exceeded("timeout")));
+        assert!(!is_transient(&Status::invalid_argument("invalid id")));
+
+        ut_info!("(test_policy) Success.");
+    }
+}
@@ -1,20 +1,116 @@
 //! gRPC server implementation
 
 pub mod grpc_server {
-    #![allow(unused_qualifications, missing_docs)]
+    #![allow(unused_qualifications, missing_docs, non_camel_case_types)]
     tonic::include_proto!("grpc");
 }
 pub use grpc_server::rpc_service_server::{RpcService, RpcServiceServer};
-pub use grpc_server::{ReadyRequest, ReadyResponse};
+pub use grpc_server::{
+    AssetAction, AssetEvent, AssetKind, ReadyRequest, ReadyResponse, WatchAssetsRequest,
+};
 
+use crate::events;
 use crate::shutdown_signal;
+#[cfg(not(feature = "stub_server"))]
+use crate::trace::continue_from_metadata;
 use crate::Config;
 
+use futures::stream::{Stream, StreamExt};
 use std::fmt::Debug;
 use std::net::SocketAddr;
+use std::pin::Pin;
 use tonic::transport::Server;
 use tonic::{Request, Response, Status};
 
+/// Stream of asset events returned by the `watchAssets` RPC
+pub type AssetEventStream = Pin<Box<dyn Stream<Item = Result<AssetEvent, Status>> + Send>>;
+
+impl From<events::AssetKind> for AssetKind {
+    fn from(kind: events::AssetKind) -> Self {
+        match kind {
+            events::AssetKind::Aircraft => AssetKind::Aircraft,
+            events::AssetKind::Vertiport => AssetKind::Vertiport,
+            events::AssetKind::Vertipad => AssetKind::Vertipad,
+            events::AssetKind::AssetGroup => AssetKind::AssetGroup,
+        }
+    }
+}
+
+impl From<events::AssetAction> for AssetAction {
+    fn from(action: events::AssetAction) -> Self {
+        match action {
+            events::AssetAction::Created => AssetAction::Created,
+            events::AssetAction::Updated => AssetAction::Updated,
+            events::AssetAction::Removed => AssetAction::Removed,
+        }
+    }
+}
+
+impl From<events::AssetEvent> for AssetEvent {
+    fn from(event: events::AssetEvent) -> Self {
+        AssetEvent {
+            sequence: event.sequence,
+            kind: AssetKind::from(event.kind) as i32,
+            asset_id: event.asset_id,
+            operator_id: event.operator_id,
+            action: AssetAction::from(event.action) as i32,
+            timestamp: Some(std::time::SystemTime::from(event.timestamp).into()),
+            data: event.data.map(|data| data.to_string()),
+            epoch: event.epoch,
+        }
+    }
+}
+
+impl events::EventFilter for WatchAssetsRequest {
+    fn matches(&self, event: &events::AssetEvent) -> bool {
+        let kind = AssetKind::from(event.kind) as i32;
+        (self.kinds.is_empty() || self.kinds.contains(&kind))
+            && (self.asset_ids.is_empty() || self.asset_ids.contains(&event.asset_id))
+    }
+}
+
+/// Start the server span of a gRPC request, continuing the trace of its
+/// `traceparent` metadata if any.
+#[cfg(not(feature = "stub_server"))]
+fn start_span<T>(method: &str, request: &Request<T>) -> tracing::Span {
+    let span = tracing::info_span!(
+        "request",
+        otel.name = %format!("grpc.RpcService/{}", method),
+        otel.kind = "server",
+        rpc.system = "grpc",
+        rpc.method = method,
+        rpc.grpc.status_code = tracing::field::Empty,
+        otel.status_message = tracing::field::Empty,
+    );
+    continue_from_metadata(&span, request.metadata());
+    span
+}
+
+/// Record the status code and duration of a gRPC request started at
+/// `start`, ending its span and returning its result.
+#[cfg(not(feature = "stub_server"))]
+async fn record_request<T>(
+    method: &str,
+    start: std::time::Instant,
+    span: tracing::Span,
+    result: Result<Response<T>, Status>,
+) -> Result<Response<T>, Status> {
+    let code = match &result {
+        Ok(_) => tonic::Code::Ok,
+        Err(status) => status.code(),
+    };
+    span.record("rpc.grpc.status_code", code as i32);
+    if let Err(status) = &result {
+        span.record("otel.status_message", status.message());
+    }
+    crate::metrics::get_metrics().await.record_grpc_request(
+        method,
+        &format!("{:?}", code),
+        start.elapsed(),
+    );
+    result
+}
+
 /// struct to implement the gRPC server functions
 #[derive(Debug, Default, Copy, Clone)]
 pub struct ServerImpl {}
@@ -22,18 +118,98 @@ pub struct ServerImpl {}
 #[cfg(not(feature = "stub_server"))]
 #[tonic::async_trait]
 impl RpcService for ServerImpl {
-    /// Returns ready:true when service is available
+    /// Returns ready:true when all required dependencies are available
     async fn is_ready(
         &self,
         request: Request<ReadyRequest>,
     ) -> Result<Response<ReadyResponse>, Status> {
         grpc_info!("(is_ready) assets server.");
         grpc_debug!("(is_ready) request: {:?}", request);
-        let response = ReadyResponse { ready: true };
-        Ok(Response::new(response))
+        let start = std::time::Instant::now();
+        let span = start_span("is_ready", &request);
+        let response = ReadyResponse {
+            ready: crate::health::get_health_monitor().await.is_ready(),
+        };
+        record_request("is_ready", start, span, Ok(Response::new(response))).await
+    }
+
+    type watchAssetsStream = AssetEventStream;
+
+    /// Streams change events of the requested assets.
+    ///
+    /// When `after_sequence` is provided, the events emitted after this
+    /// sequence number are replayed first. Returns [`tonic::Code::OutOfRange`]
+    /// if these events are no longer available, or if the `epoch` of the
+    /// sequence number is not the current one. The stream ends with
+    /// [`tonic::Code::Aborted`] if the client can not keep up and misses
+    /// events, the message holding the sequence number to resume after.
+    async fn watch_assets(
+        &self,
+        request: Request<WatchAssetsRequest>,
+    ) -> Result<Response<Self::watchAssetsStream>, Status> {
+        grpc_info!("(watch_assets) assets server.");
+        grpc_debug!("(watch_assets) request: {:?}", request);
+        let start = std::time::Instant::now();
+        let span = start_span("watch_assets", &request);
+        let request = request.into_inner();
+        let after_sequence = request.after_sequence;
+        let epoch = request.epoch.clone();
+
+        let result = events::get_notifier()
+            .await
+            .subscribe_from(after_sequence, epoch.as_deref(), request)
+            .map(|stream| {
+                let stream = stream.map(|result| match result {
+                    Ok(event) => Ok(AssetEvent::from(event)),
+                    // The client resumes with the sequence in the message
+                    Err(lagged) => Err(Status::aborted(lagged.to_string())),
+                });
+                Response::new(Box::pin(stream) as Self::watchAssetsStream)
+            })
+            .map_err(|e| {
+                grpc_warn!("(watch_assets) could not resume stream: {}", e);
+                Status::out_of_range(e.to_string())
+            });
+        // Only the time to open the stream is recorded
+        record_request("watch_assets", start, span, result).await
+    }
+}
+
+/// Keep the serving status of the gRPC health service in line with the
+/// readiness of the service.
+#[cfg(not(feature = "stub_server"))]
+async fn report_health(mut health_reporter: tonic_health::server::HealthReporter) {
+    let mut ready = crate::health::get_health_monitor().await.subscribe();
+    loop {
+        let serving = *ready.borrow_and_update();
+        grpc_debug!("(report_health) serving: {}.", serving);
+        match serving {
+            true => {
+                health_reporter
+                    .set_serving::<RpcServiceServer<ServerImpl>>()
+                    .await
+            }
+            false => {
+                health_reporter
+                    .set_not_serving::<RpcServiceServer<ServerImpl>>()
+                    .await
+            }
+        }
+
+        if ready.changed().await.is_err() {
+            return;
+        }
     }
 }
 
+/// The stub server is always serving.
+#[cfg(feature = "stub_server")]
+async fn report_health(mut health_reporter: tonic_health::server::HealthReporter) {
+    health_reporter
+        .set_serving::<RpcServiceServer<ServerImpl>>()
+        .await;
+}
+
 /// Starts the grpc servers for this microservice using the provided configuration
 ///
 /// # Example:
@@ -60,10 +236,8 @@ pub async fn grpc_server(config: Config, shutdown_rx: Option<tokio::sync::onesho
     };
 
     let imp = ServerImpl::default();
-    let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
-    health_reporter
-        .set_serving::<RpcServiceServer<ServerImpl>>()
-        .await;
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    tokio::spawn(report_health(health_reporter));
 
     //start server
     grpc_info!(
@@ -95,6 +269,46 @@ impl RpcService for ServerImpl {
         let response = ReadyResponse { ready: true };
         Ok(Response::new(response))
     }
+
+    type watchAssetsStream = AssetEventStream;
+
+    async fn watch_assets(
+        &self,
+        request: Request<WatchAssetsRequest>,
+    ) -> Result<Response<Self::watchAssetsStream>, Status> {
+        grpc_warn!("(watch_assets MOCK) assets server.");
+        grpc_debug!("(watch_assets MOCK) request: {:?}", request);
+        let request = request.into_inner();
+
+        // Replay a fixed set of mock events, so the stream ends
+        let notifier = events::ChangeNotifier::with_outbox(None);
+        notifier.emit(
+            events::AssetKind::Aircraft,
+            events::AssetAction::Created,
+            &uuid::Uuid::new_v4().to_string(),
+            None,
+        );
+        notifier.emit(
+            events::AssetKind::Vertipad,
+            events::AssetAction::Updated,
+            &uuid::Uuid::new_v4().to_string(),
+            Some(serde_json::json!({ "occupied": true })),
+        );
+
+        let events = notifier
+            .subscribe_from(Some(0), None, request)
+            .map_err(|e| {
+                grpc_warn!("(watch_assets MOCK) could not resume stream: {}", e);
+                Status::out_of_range(e.to_string())
+            })?;
+        drop(notifier);
+
+        let stream = events.map(|result| match result {
+            Ok(event) => Ok(AssetEvent::from(event)),
+            Err(lagged) => Err(Status::aborted(lagged.to_string())),
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
 }
 
 #[cfg(test)]
@@ -106,6 +320,8 @@ mod tests {
         crate::get_log_handle().await;
         ut_info!("(test_grpc_server_is_ready) Start.");
 
+        // Ready once the (stubbed) dependencies have been checked
+        crate::health::get_health_monitor().await.refresh().await;
         let imp = ServerImpl::default();
         let result = imp.is_ready(Request::new(ReadyRequest {})).await;
         assert!(result.is_ok());
@@ -114,4 +330,126 @@ mod tests {
 
         ut_info!("(test_grpc_server_is_ready) Success.");
     }
+
+    #[cfg(not(feature = "stub_server"))]
+    #[tokio::test]
+    async fn test_grpc_server_watch_assets() {
+        crate::get_log_handle().await;
+        ut_info!("(test_grpc_server_watch_assets) Start.");
+
+        let imp = ServerImpl::default();
+        let asset_id = uuid::Uuid::new_v4().to_string();
+        let request = WatchAssetsRequest {
+            asset_ids: vec![asset_id.clone()],
+            ..Default::default()
+        };
+        let mut stream = imp
+            .watch_assets(Request::new(request))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let notifier = events::get_notifier().await;
+        notifier.emit(
+            events::AssetKind::Aircraft,
+            events::AssetAction::Created,
+            &uuid::Uuid::new_v4().to_string(),
+            None,
+        );
+        let expected = notifier.emit(
+            events::AssetKind::Aircraft,
+            events::AssetAction::Updated,
+            &asset_id,
+            None,
+        );
+
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.sequence, expected.sequence);
+        assert_eq!(event.asset_id, asset_id);
+        assert_eq!(event.action, AssetAction::Updated as i32);
+
+        // Resuming after an unknown sequence number is not possible
+        let request = WatchAssetsRequest {
+            after_sequence: Some(u64::MAX),
+            ..Default::default()
+        };
+        let result = imp.watch_assets(Request::new(request)).await;
+        assert_eq!(result.err().unwrap().code(), tonic::Code::OutOfRange);
+
+        // Resuming from the sequence numbers of another epoch neither
+        let request = WatchAssetsRequest {
+            after_sequence: Some(event.sequence),
+            epoch: Some(uuid::Uuid::new_v4().to_string()),
+            ..Default::default()
+        };
+        let result = imp.watch_assets(Request::new(request)).await;
+        assert_eq!(result.err().unwrap().code(), tonic::Code::OutOfRange);
+
+        // Resuming from the current epoch replays the missed events
+        let request = WatchAssetsRequest {
+            asset_ids: vec![asset_id.clone()],
+            after_sequence: Some(event.sequence - 1),
+            epoch: Some(event.epoch.clone()),
+            ..Default::default()
+        };
+        let mut stream = imp
+            .watch_assets(Request::new(request))
+            .await
+            .unwrap()
+            .into_inner();
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.sequence, expected.sequence);
+
+        ut_info!("(test_grpc_server_watch_assets) Success.");
+    }
+
+    #[test]
+    fn test_watch_assets_request_filter() {
+        use events::EventFilter;
+
+        let notifier = events::ChangeNotifier::with_outbox(None);
+        let event = notifier.emit(
+            events::AssetKind::Vertipad,
+            events::AssetAction::Updated,
+            "pad",
+            None,
+        );
+
+        assert!(WatchAssetsRequest::default().matches(&event));
+        assert!(WatchAssetsRequest {
+            kinds: vec![AssetKind::Aircraft as i32, AssetKind::Vertipad as i32],
+            ..Default::default()
+        }
+        .matches(&event));
+        assert!(!WatchAssetsRequest {
+            kinds: vec![AssetKind::Aircraft as i32],
+            ..Default::default()
+        }
+        .matches(&event));
+        assert!(!WatchAssetsRequest {
+            asset_ids: vec!["other".to_string()],
+            ..Default::default()
+        }
+        .matches(&event));
+    }
+
+    #[test]
+    fn test_asset_event_from() {
+        let notifier = events::ChangeNotifier::with_outbox(None);
+        let event = notifier.emit(
+            events::AssetKind::Aircraft,
+            events::AssetAction::Removed,
+            "aircraft",
+            Some(serde_json::json!({ "reason": "test" })),
+        );
+
+        let grpc_event = AssetEvent::from(event.clone());
+        assert_eq!(grpc_event.sequence, event.sequence);
+        assert_eq!(grpc_event.kind, AssetKind::Aircraft as i32);
+        assert_eq!(grpc_event.action, AssetAction::Removed as i32);
+        assert_eq!(grpc_event.asset_id, "aircraft");
+        assert!(grpc_event.timestamp.is_some());
+        assert_eq!(grpc_event.data, Some(r#"{"reason":"test"}"#.to_string()));
+        assert_eq!(grpc_event.epoch, notifier.epoch());
+    }
 }
@@ -0,0 +1,102 @@
+//! Checks of the dependencies of the service
+
+use super::DependencyCheck;
+use crate::store::SharedStore;
+use crate::Config;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+
+/// Returns the checks of all dependencies of the service.
+///
+/// The AMQP broker is only checked if AMQP is enabled.
+pub fn dependency_checks(config: &Config, store: &SharedStore) -> Vec<Arc<dyn DependencyCheck>> {
+    let resources = [
+        StorageResource::Vehicle,
+        StorageResource::Vertiport,
+        StorageResource::Vertipad,
+    ];
+    let mut checks: Vec<Arc<dyn DependencyCheck>> = resources
+        .into_iter()
+        .map(|resource| {
+            Arc::new(StorageCheck {
+                resource,
+                store: store.clone(),
+            }) as Arc<dyn DependencyCheck>
+        })
+        .collect();
+
+    if config.amqp_enabled {
+        checks.push(Arc::new(AmqpCheck {
+            address: format!("{}:{}", config.amqp_host, config.amqp_port),
+        }));
+    }
+    checks
+}
+
+/// A svc-storage resource the service depends on.
+#[derive(Clone, Copy, Debug)]
+enum StorageResource {
+    Vehicle,
+    Vertiport,
+    Vertipad,
+}
+
+impl StorageResource {
+    fn name(&self) -> &'static str {
+        match self {
+            StorageResource::Vehicle => "vehicle",
+            StorageResource::Vertiport => "vertiport",
+            StorageResource::Vertipad => "vertipad",
+        }
+    }
+}
+
+/// Checks a svc-storage resource using the `is_ready` call of the asset
+/// store.
+struct StorageCheck {
+    resource: StorageResource,
+    store: SharedStore,
+}
+
+#[tonic::async_trait]
+impl DependencyCheck for StorageCheck {
+    fn name(&self) -> &str {
+        self.resource.name()
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        let name = self.resource.name();
+        let result = match self.resource {
+            StorageResource::Vehicle => self.store.vehicles().is_ready().await,
+            StorageResource::Vertiport => self.store.vertiports().is_ready().await,
+            StorageResource::Vertipad => self.store.vertipads().is_ready().await,
+        };
+        result.map_err(|e| format!("svc-storage {} unavailable: {}", name, e))
+    }
+}
+
+/// Checks that the AMQP broker accepts connections.
+///
+/// Events are kept in the outbox while the broker is down, so the service
+/// remains ready.
+struct AmqpCheck {
+    address: String,
+}
+
+#[tonic::async_trait]
+impl DependencyCheck for AmqpCheck {
+    fn name(&self) -> &str {
+        "amqp"
+    }
+
+    fn required(&self) -> bool {
+        false
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        TcpStream::connect(&self.address)
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("AMQP broker [{}] unreachable: {}", self.address, e))
+    }
+}
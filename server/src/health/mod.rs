@@ -0,0 +1,326 @@
+//! Health
+//! tracks the availability of the service's dependencies
+//!
+//! The dependencies are checked periodically by the [`health_task`], and
+//! on demand by the `/health/ready` REST endpoint. The latest
+//! [`HealthReport`] is shared by the REST endpoints, the gRPC `isReady`
+//! call and the gRPC health service, so they all report the same state.
+
+#[macro_use]
+pub mod macros;
+pub mod checks;
+
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, OnceCell};
+use utoipa::ToSchema;
+
+/// Time after which a dependency check is considered failed.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub(crate) static HEALTH_MONITOR: OnceCell<HealthMonitor> = OnceCell::const_new();
+
+/// Returns HEALTH_MONITOR, a [`HealthMonitor`] checking the dependencies
+/// configured using a Config object generated from environment variables.
+/// Initializes HEALTH_MONITOR if it hasn't been initialized yet.
+pub async fn get_health_monitor() -> &'static HealthMonitor {
+    HEALTH_MONITOR
+        .get_or_init(|| async move {
+            let config = crate::Config::try_from_env().unwrap_or_default();
+            let store = crate::store::get_store().await;
+            HealthMonitor::new(checks::dependency_checks(&config, store))
+        })
+        .await
+}
+
+/// A dependency of the service which can be checked.
+#[tonic::async_trait]
+pub trait DependencyCheck: Send + Sync {
+    /// The name of the dependency, such as `vehicle`.
+    fn name(&self) -> &str;
+
+    /// Indicates if the service can't handle requests without the
+    /// dependency.
+    fn required(&self) -> bool {
+        true
+    }
+
+    /// Check if the dependency is available.
+    async fn check(&self) -> Result<(), String>;
+}
+
+/// Availability of a dependency.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyStatus {
+    /// The dependency is available.
+    Up,
+    /// The dependency could not be reached or reported it is not ready.
+    Down,
+}
+
+/// Result of checking a single dependency.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DependencyHealth {
+    /// The name of the dependency.
+    pub name: String,
+    /// The availability of the dependency.
+    pub status: DependencyStatus,
+    /// Indicates if the service is not ready while the dependency is down.
+    pub required: bool,
+    /// Time the check took, in milliseconds.
+    pub latency_ms: f64,
+    /// The reason the dependency is down, if any.
+    pub error: Option<String>,
+}
+
+/// Result of checking all dependencies.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct HealthReport {
+    /// Indicates if all required dependencies are up.
+    pub ready: bool,
+    /// Time of the check, `None` if the dependencies have not been checked
+    /// yet.
+    pub checked_at: Option<DateTime<Utc>>,
+    /// The result of each dependency check.
+    pub dependencies: Vec<DependencyHealth>,
+}
+
+impl HealthReport {
+    /// Create a report from the results of the dependency checks.
+    pub fn new(dependencies: Vec<DependencyHealth>) -> Self {
+        Self {
+            ready: dependencies.iter().all(|dependency| {
+                !dependency.required || dependency.status == DependencyStatus::Up
+            }),
+            checked_at: Some(Utc::now()),
+            dependencies,
+        }
+    }
+}
+
+/// Check a single dependency, failing it if it takes longer than
+/// [`CHECK_TIMEOUT`].
+async fn run_check(check: &dyn DependencyCheck) -> DependencyHealth {
+    let start = Instant::now();
+    let result = match tokio::time::timeout(CHECK_TIMEOUT, check.check()).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("no response within {:?}", CHECK_TIMEOUT)),
+    };
+
+    if let Err(e) = &result {
+        health_warn!("(run_check) {} is down: {}", check.name(), e);
+    }
+    DependencyHealth {
+        name: check.name().to_string(),
+        status: match result {
+            Ok(_) => DependencyStatus::Up,
+            Err(_) => DependencyStatus::Down,
+        },
+        required: check.required(),
+        latency_ms: start.elapsed().as_secs_f64() * 1000.0,
+        error: result.err(),
+    }
+}
+
+/// Checks the dependencies of the service and keeps the latest report.
+///
+/// The service is not ready until the dependencies have been checked.
+#[derive(Clone)]
+pub struct HealthMonitor {
+    checks: Arc<Vec<Arc<dyn DependencyCheck>>>,
+    report: Arc<Mutex<HealthReport>>,
+    ready: Arc<watch::Sender<bool>>,
+}
+
+impl std::fmt::Debug for HealthMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HealthMonitor")
+            .field("report", &self.report())
+            .finish()
+    }
+}
+
+impl HealthMonitor {
+    /// Create a monitor for the provided dependency checks.
+    pub fn new(checks: Vec<Arc<dyn DependencyCheck>>) -> Self {
+        let (ready, _) = watch::channel(false);
+        Self {
+            checks: Arc::new(checks),
+            report: Arc::new(Mutex::new(HealthReport::default())),
+            ready: Arc::new(ready),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HealthReport> {
+        match self.report.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Returns the latest report.
+    pub fn report(&self) -> HealthReport {
+        self.lock().clone()
+    }
+
+    /// Indicates if all required dependencies were up at the latest check.
+    pub fn is_ready(&self) -> bool {
+        *self.ready.borrow()
+    }
+
+    /// Indicates if the dependency `name` was down at the latest check.
+    pub fn is_down(&self, name: &str) -> bool {
+        self.lock().dependencies.iter().any(|dependency| {
+            dependency.name == name && dependency.status == DependencyStatus::Down
+        })
+    }
+
+    /// Subscribe to changes of the readiness of the service.
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.ready.subscribe()
+    }
+
+    /// Check all dependencies concurrently, keeping and returning the
+    /// report.
+    pub async fn refresh(&self) -> HealthReport {
+        let dependencies =
+            join_all(self.checks.iter().map(|check| run_check(check.as_ref()))).await;
+        let report = HealthReport::new(dependencies);
+
+        // Update the report and readiness together, so they never disagree
+        let mut latest = self.lock();
+        *latest = report.clone();
+        let ready = report.ready;
+        self.ready.send_if_modified(|current| {
+            let modified = *current != ready;
+            if modified {
+                health_info!("(refresh) service ready changed to {}.", ready);
+            }
+            *current = ready;
+            modified
+        });
+        drop(latest);
+        report
+    }
+}
+
+/// Checks the dependencies of the global [`HealthMonitor`] every
+/// `health_check_interval_seconds`.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: Runs until the process is stopped.
+pub async fn health_task(config: crate::Config) {
+    let interval = Duration::from_secs(config.health_check_interval_seconds.max(1));
+    health_info!("(health_task) checking dependencies every {:?}.", interval);
+
+    let monitor = get_health_monitor().await;
+    loop {
+        let report = monitor.refresh().await;
+        health_debug!("(health_task) {:?}", report);
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check returning a fixed result, after an optional delay.
+    struct FixedCheck {
+        name: &'static str,
+        required: bool,
+        result: Result<(), String>,
+        delay: Duration,
+    }
+
+    #[tonic::async_trait]
+    impl DependencyCheck for FixedCheck {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn required(&self) -> bool {
+            self.required
+        }
+
+        async fn check(&self) -> Result<(), String> {
+            tokio::time::sleep(self.delay).await;
+            self.result.clone()
+        }
+    }
+
+    fn check(name: &'static str, required: bool, result: Result<(), String>) -> FixedCheck {
+        FixedCheck {
+            name,
+            required,
+            result,
+            delay: Duration::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh() {
+        crate::get_log_handle().await;
+        ut_info!("(test_refresh) Start.");
+
+        let monitor = HealthMonitor::new(vec![
+            Arc::new(check("vehicle", true, Ok(()))),
+            Arc::new(check("amqp", false, Err("connection refused".to_string()))),
+        ]);
+        let mut ready = monitor.subscribe();
+        assert!(!monitor.is_ready());
+        assert_eq!(monitor.report().checked_at, None);
+
+        // Optional dependencies don't affect readiness
+        let report = monitor.refresh().await;
+        assert!(report.ready);
+        assert!(monitor.is_ready());
+        assert!(ready.has_changed().unwrap());
+        assert!(*ready.borrow_and_update());
+        assert_eq!(monitor.report(), report);
+        assert_eq!(report.dependencies.len(), 2);
+        assert_eq!(report.dependencies[0].status, DependencyStatus::Up);
+        assert_eq!(report.dependencies[0].error, None);
+        assert_eq!(report.dependencies[1].status, DependencyStatus::Down);
+        assert_eq!(
+            report.dependencies[1].error,
+            Some("connection refused".to_string())
+        );
+
+        // Unchanged readiness is not notified
+        monitor.refresh().await;
+        assert!(!ready.has_changed().unwrap());
+
+        ut_info!("(test_refresh) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_required_down() {
+        crate::get_log_handle().await;
+        ut_info!("(test_required_down) Start.");
+
+        let monitor = HealthMonitor::new(vec![
+            Arc::new(check("vehicle", true, Ok(()))),
+            Arc::new(FixedCheck {
+                delay: CHECK_TIMEOUT * 2,
+                ..check("vertiport", true, Ok(()))
+            }),
+        ]);
+
+        // Checks not responding in time are failed
+        let report = monitor.refresh().await;
+        assert!(!report.ready);
+        assert!(!monitor.is_ready());
+        assert_eq!(report.dependencies[1].status, DependencyStatus::Down);
+        assert!(report.dependencies[1].latency_ms >= CHECK_TIMEOUT.as_millis() as f64);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["dependencies"][1]["status"], "down");
+        assert_eq!(json["dependencies"][1]["name"], "vertiport");
+
+        ut_info!("(test_required_down) Success.");
+    }
+}
@@ -0,0 +1,212 @@
+//! JSON File Store
+//! keeps records in memory, written to a JSON file on every change
+//!
+//! Used by the stores of this service which are small enough to be
+//! rewritten as a whole on every change, such as the removed assets and the
+//! vehicle model catalog.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Record kept in a [`JsonFileStore`].
+pub trait JsonRecord: Clone + Serialize + DeserializeOwned {
+    /// The key of the record, a record replaces the record with the same
+    /// key.
+    fn key(&self) -> &str;
+}
+
+/// Records kept in memory and written to a JSON file on every change.
+///
+/// If no file path is provided, the records are kept in memory only.
+#[derive(Clone, Debug)]
+pub struct JsonFileStore<T> {
+    path: Option<PathBuf>,
+    records: Arc<Mutex<HashMap<String, T>>>,
+}
+
+impl<T> Default for JsonFileStore<T> {
+    fn default() -> Self {
+        Self {
+            path: None,
+            records: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<T: JsonRecord> JsonFileStore<T> {
+    /// Open the records stored at `path`, or keep the records in memory
+    /// only if `path` is empty.
+    ///
+    /// Fails if the file can not be used.
+    pub fn open(path: &str) -> Result<Self, std::io::Error> {
+        if path.is_empty() {
+            return Ok(Self::default());
+        }
+
+        Self::try_open(PathBuf::from(path))
+            .map_err(|e| std::io::Error::new(e.kind(), format!("file [{}]: {}", path, e)))
+    }
+
+    fn try_open(path: PathBuf) -> Result<Self, std::io::Error> {
+        let mut records = HashMap::new();
+        if path.exists() {
+            let list: Vec<T> = serde_json::from_reader(std::fs::File::open(&path)?)?;
+            for record in list {
+                records.insert(record.key().to_string(), record);
+            }
+        }
+
+        let store = Self {
+            path: Some(path),
+            records: Arc::new(Mutex::new(records)),
+        };
+        store.save(&store.lock())?;
+        Ok(store)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, T>> {
+        match self.records.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Write all records to the file, if any.
+    ///
+    /// The records are written to a temporary file first, so a crash while
+    /// writing never leaves a partially written file behind.
+    fn save(&self, records: &HashMap<String, T>) -> Result<(), std::io::Error> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let mut list: Vec<(&String, &T)> = records.iter().collect();
+        list.sort_by_key(|(key, _)| *key);
+        let list: Vec<&T> = list.into_iter().map(|(_, record)| record).collect();
+        let temp_path = path.with_extension("tmp");
+        let file = std::fs::File::create(&temp_path)?;
+        serde_json::to_writer(&file, &list)?;
+        file.sync_data()?;
+        std::fs::rename(temp_path, path)
+    }
+
+    /// Apply a change to the records, keeping it only once it has been
+    /// written to the file.
+    pub fn modify<R>(
+        &self,
+        change: impl FnOnce(&mut HashMap<String, T>) -> R,
+    ) -> Result<R, std::io::Error> {
+        let mut records = self.lock();
+        let mut changed = records.clone();
+        let result = change(&mut changed);
+        self.save(&changed)?;
+        *records = changed;
+        Ok(result)
+    }
+
+    /// Add a record, returning the record it replaced, if any.
+    pub fn insert(&self, record: T) -> Result<Option<T>, std::io::Error> {
+        self.modify(|records| records.insert(record.key().to_string(), record))
+    }
+
+    /// Remove the record with the given key, returning it, if any.
+    pub fn remove(&self, key: &str) -> Result<Option<T>, std::io::Error> {
+        let mut records = self.lock();
+        let Some(record) = records.remove(key) else {
+            return Ok(None);
+        };
+
+        if let Err(e) = self.save(&records) {
+            records.insert(key.to_string(), record);
+            return Err(e);
+        }
+        Ok(Some(record))
+    }
+
+    /// Returns the record with the given key, if any.
+    pub fn get(&self, key: &str) -> Option<T> {
+        self.lock().get(key).cloned()
+    }
+
+    /// Returns all records, in no particular order.
+    pub fn values(&self) -> Vec<T> {
+        self.lock().values().cloned().collect()
+    }
+
+    /// Returns the number of records.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if there are no records.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct Record {
+        id: String,
+        value: u32,
+    }
+
+    impl JsonRecord for Record {
+        fn key(&self) -> &str {
+            &self.id
+        }
+    }
+
+    fn record(id: &str, value: u32) -> Record {
+        Record {
+            id: id.to_string(),
+            value,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_json_file_store() {
+        crate::get_log_handle().await;
+        ut_info!("(test_json_file_store) Start.");
+
+        let path = std::env::temp_dir().join(format!(
+            "svc-assets-json-file-{}.json",
+            uuid::Uuid::new_v4()
+        ));
+        let path_str = path.to_string_lossy().to_string();
+
+        let store = JsonFileStore::open(&path_str).unwrap();
+        assert_eq!(store.insert(record("a", 1)).unwrap(), None);
+        store.insert(record("b", 2)).unwrap();
+        assert_eq!(store.insert(record("a", 3)).unwrap(), Some(record("a", 1)));
+        assert_eq!(store.remove("b").unwrap(), Some(record("b", 2)));
+        assert_eq!(store.remove("b").unwrap(), None);
+
+        // Records are restored from the file
+        let store: JsonFileStore<Record> = JsonFileStore::open(&path_str).unwrap();
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.get("a"), Some(record("a", 3)));
+
+        // Changes are only kept once written to the file
+        std::fs::remove_file(&path).unwrap();
+        std::fs::create_dir(path.with_extension("tmp")).unwrap();
+        assert!(store.insert(record("c", 4)).is_err());
+        assert!(store.remove("a").is_err());
+        assert_eq!(store.values(), vec![record("a", 3)]);
+
+        // The file can not be opened while it is a directory
+        std::fs::remove_dir(path.with_extension("tmp")).unwrap();
+        std::fs::create_dir(&path).unwrap();
+        assert!(JsonFileStore::<Record>::open(&path_str).is_err());
+
+        std::fs::remove_dir(path).ok();
+        ut_info!("(test_json_file_store) Success.");
+    }
+}
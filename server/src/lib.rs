@@ -6,8 +6,26 @@ use tokio::sync::OnceCell;
 #[macro_use]
 pub mod test_util;
 
+pub mod audit;
+pub mod cache;
+pub mod catalog;
 pub mod config;
+pub mod degraded;
+pub mod deletions;
+pub mod events;
 pub mod grpc;
+pub mod health;
+pub mod json_file;
+pub mod matching;
+pub mod metrics;
+pub mod occupancy;
+pub mod registration;
+pub mod reload;
+pub mod request_id;
+pub mod reservations;
+pub mod store;
+pub mod telemetry;
+pub mod trace;
 
 pub use crate::config::Config;
 
@@ -25,6 +43,15 @@ pub struct Cli {
     /// Target file to write the OpenAPI Spec
     #[arg(long)]
     pub openapi: Option<String>,
+
+    /// Path of a TOML or YAML configuration file, overridden by
+    /// environment variables
+    #[arg(long)]
+    pub config: Option<String>,
+
+    /// Print the effective configuration and exit
+    #[arg(long)]
+    pub print_config: bool,
 }
 
 // --------------------------------------------------
@@ -39,7 +66,7 @@ pub(crate) async fn get_log_handle() -> Option<log4rs::Handle> {
             // Set up basic logger to make sure we can write to stdout
             let stdout = log4rs::append::console::ConsoleAppender::builder()
                 .encoder(Box::new(log4rs::encode::pattern::PatternEncoder::new(
-                    "{d(%Y-%m-%d %H:%M:%S)} | {I} | {h({l}):5.5} | {f}:{L} | {m}{n}",
+                    "{d(%Y-%m-%d %H:%M:%S)} | {I} | {X(request_id)(-)} | {h({l}):5.5} | {f}:{L} | {m}{n}",
                 )))
                 .build();
             match log4rs::config::Config::builder()
@@ -6,8 +6,28 @@ use svc_assets::*;
 #[tokio::main]
 #[cfg(not(tarpaulin_include))]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Will use default config settings if no environment vars are found.
-    let config = Config::try_from_env().unwrap_or_default();
+    let args = Cli::parse();
+
+    // Allow option to only generate the spec file to a given location
+    // use `make rust-openapi` to generate the OpenAPI specification.
+    // The spec doesn't depend on the configuration.
+    if let Some(target) = args.openapi {
+        return rest::generate_openapi_spec(&target);
+    }
+
+    // Make the configuration file available to everything reading the
+    // configuration from the environment.
+    if let Some(path) = &args.config {
+        std::env::set_var(svc_assets::config::CONFIG_FILE_ENV, path);
+    }
+
+    // Options which are not set use their defaults.
+    let config = Config::try_from_env()?;
+
+    if args.print_config {
+        println!("{}", serde_json::to_string_pretty(&config)?);
+        return Ok(config.validate()?);
+    }
 
     // Try to load log configuration from the provided log file.
     // Will default to stdout debug logging if the file can not be loaded.
@@ -15,12 +35,36 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     info!("(main) Server startup.");
 
-    // Allow option to only generate the spec file to a given location
-    // use `make rust-openapi` to generate the OpenAPI specification
-    let args = Cli::parse();
-    if let Some(target) = args.openapi {
-        return rest::generate_openapi_spec(&target);
-    }
+    config.validate()?;
+
+    // Changes are only made once they can be recorded in the audit log
+    audit::init_audit_log(&config).await?;
+
+    // Events are only emitted once they can be kept in the outbox
+    events::init_notifier(&config).await?;
+
+    // Removed assets, vehicle models and reservations are kept in files
+    deletions::init_deletions(&config).await?;
+    catalog::init_catalog(&config).await?;
+    reservations::init_reservations(&config).await?;
+
+    // Spans of requests and svc-storage calls
+    trace::init_tracing(&config)?;
+
+    // Asset event relay
+    tokio::spawn(events::relay::relay_task(config.clone()));
+
+    // Aircraft telemetry listener
+    tokio::spawn(telemetry::listener::listener_task(config.clone()));
+
+    // Configuration reloads on SIGHUP
+    tokio::spawn(reload::reload_task());
+
+    // Dependency health checks
+    tokio::spawn(health::health_task(config.clone()));
+
+    // Retention purge of removed assets
+    tokio::spawn(deletions::purge::purge_task(config.clone()));
 
     // REST Server
     tokio::spawn(rest::server::rest_server(config.clone(), None));
@@ -30,6 +74,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     info!("(main) Server shutdown.");
 
+    // Make sure the spans of the last requests are exported
+    trace::shutdown_tracing();
+
     // Make sure all log message are written/ displayed before shutdown
     log::logger().flush();
 
@@ -0,0 +1,187 @@
+//! Capability Matching
+//! checks if aircraft are able to fly a mission
+//!
+//! An aircraft is capable of a mission if its
+//! [`VehicleModel`](crate::catalog::VehicleModel) can carry the payload
+//! over the distance, it is available and it is not due for maintenance
+//! before the mission ends. Distances between vertiports are great-circle
+//! distances between the centers of their areas.
+
+use crate::catalog::VehicleModel;
+use crate::rest::structs::{Aircraft, AssetStatus};
+use chrono::{DateTime, Duration, Utc};
+use svc_storage_client_grpc::prelude::{GeoPoint, GeoPolygon};
+
+/// Mean radius of the earth in kilometers.
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// A flight an aircraft is searched for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mission {
+    /// Mass of the payload in kilograms.
+    pub payload_kg: f64,
+    /// Distance of the flight in kilometers.
+    pub distance_km: f64,
+    /// Time at which the flight departs.
+    pub departure: DateTime<Utc>,
+}
+
+impl Mission {
+    /// Returns the time at which the flight ends when flown at the cruise
+    /// speed of `model`.
+    pub fn arrival(&self, model: &VehicleModel) -> DateTime<Utc> {
+        if model.cruise_speed_kph <= 0.0 {
+            return self.departure;
+        }
+
+        let seconds = self.distance_km / model.cruise_speed_kph * 3600.0;
+        self.departure + Duration::seconds(seconds.ceil() as i64)
+    }
+}
+
+/// Check if `aircraft` of vehicle model `model` is able to fly `mission`.
+pub fn is_capable(aircraft: &Aircraft, model: &VehicleModel, mission: &Mission) -> bool {
+    if aircraft.basics.status != AssetStatus::Available {
+        return false;
+    }
+    if model.max_payload_kg < mission.payload_kg || model.max_range_km < mission.distance_km {
+        return false;
+    }
+
+    match aircraft.next_maintenance {
+        Some(next_maintenance) => next_maintenance > mission.arrival(model),
+        None => true,
+    }
+}
+
+/// Returns the center of the exterior ring of `polygon`, if it has any
+/// points.
+pub fn center(polygon: &GeoPolygon) -> Option<GeoPoint> {
+    let mut points = polygon.exterior.as_ref()?.points.as_slice();
+    // A closed ring repeats its first point as its last point
+    if points.len() > 1 && points.first() == points.last() {
+        points = &points[..points.len() - 1];
+    }
+    if points.is_empty() {
+        return None;
+    }
+
+    let count = points.len() as f64;
+    Some(GeoPoint {
+        latitude: points.iter().map(|point| point.latitude).sum::<f64>() / count,
+        longitude: points.iter().map(|point| point.longitude).sum::<f64>() / count,
+    })
+}
+
+/// Returns the great-circle distance in kilometers between two points.
+pub fn distance_km(from: &GeoPoint, to: &GeoPoint) -> f64 {
+    let d_latitude = (to.latitude - from.latitude).to_radians();
+    let d_longitude = (to.longitude - from.longitude).to_radians();
+    let a = (d_latitude / 2.0).sin().powi(2)
+        + from.latitude.to_radians().cos()
+            * to.latitude.to_radians().cos()
+            * (d_longitude / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::VehicleDimensions;
+    use svc_storage_client_grpc::prelude::GeoLineString;
+
+    fn model() -> VehicleModel {
+        VehicleModel {
+            id: uuid::Uuid::new_v4().to_string(),
+            manufacturer: "Arrow".to_string(),
+            model_name: "Cargo".to_string(),
+            max_payload_kg: 250.0,
+            max_range_km: 120.0,
+            cruise_speed_kph: 180.0,
+            charge_time_minutes: 45.0,
+            dimensions: VehicleDimensions::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn point(latitude: f64, longitude: f64) -> GeoPoint {
+        GeoPoint {
+            latitude,
+            longitude,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_is_capable() {
+        crate::get_log_handle().await;
+        ut_info!("(test_is_capable) Start.");
+
+        let model = model();
+        let mut aircraft = Aircraft::random();
+        aircraft.apply_vehicle_model(&model);
+        let mission = Mission {
+            payload_kg: 200.0,
+            distance_km: 90.0,
+            departure: Utc::now(),
+        };
+        // 90 km at 180 km/h take half an hour
+        assert_eq!(
+            mission.arrival(&model),
+            mission.departure + Duration::minutes(30)
+        );
+        assert!(is_capable(&aircraft, &model, &mission));
+
+        // Payload and distance must not exceed the vehicle model's maximum
+        let heavy = Mission {
+            payload_kg: 300.0,
+            ..mission
+        };
+        assert!(!is_capable(&aircraft, &model, &heavy));
+        let far = Mission {
+            distance_km: 150.0,
+            ..mission
+        };
+        assert!(!is_capable(&aircraft, &model, &far));
+
+        // Maintenance must not be due before the flight ends
+        aircraft.next_maintenance = Some(mission.departure + Duration::minutes(20));
+        assert!(!is_capable(&aircraft, &model, &mission));
+        aircraft.next_maintenance = Some(mission.departure + Duration::hours(1));
+        assert!(is_capable(&aircraft, &model, &mission));
+
+        aircraft.basics.status = AssetStatus::Unavailable;
+        assert!(!is_capable(&aircraft, &model, &mission));
+
+        ut_info!("(test_is_capable) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_distance() {
+        crate::get_log_handle().await;
+        ut_info!("(test_distance) Start.");
+
+        let polygon = GeoPolygon {
+            exterior: Some(GeoLineString {
+                points: vec![
+                    point(52.0, 4.0),
+                    point(52.0, 5.0),
+                    point(53.0, 5.0),
+                    point(53.0, 4.0),
+                    point(52.0, 4.0),
+                ],
+            }),
+            interiors: vec![],
+        };
+        assert_eq!(center(&polygon), Some(point(52.5, 4.5)));
+        assert_eq!(center(&GeoPolygon::default()), None);
+
+        // One degree of latitude is about 111 km
+        let distance = distance_km(&point(52.0, 4.0), &point(53.0, 4.0));
+        assert!((distance - 111.2).abs() < 0.1);
+        assert_eq!(distance_km(&point(52.0, 4.0), &point(52.0, 4.0)), 0.0);
+
+        ut_info!("(test_distance) Success.");
+    }
+}
@@ -0,0 +1,425 @@
+//! Metrics
+//! collects request and storage call metrics exposed to Prometheus
+//!
+//! Counters and latency histograms are kept in memory and rendered in the
+//! Prometheus text exposition format by the `/metrics` REST endpoint.
+
+#[macro_use]
+pub mod macros;
+
+use crate::events::outbox::OutboxStats;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+pub(crate) static METRICS: OnceCell<Metrics> = OnceCell::const_new();
+
+/// Returns METRICS, the [`Metrics`] shared by the REST and gRPC servers.
+/// Initializes METRICS if it hasn't been initialized yet.
+pub async fn get_metrics() -> &'static Metrics {
+    METRICS.get_or_init(|| async move { Metrics::new() }).await
+}
+
+/// REST requests, by method, route and status code.
+const REST_REQUESTS: &str = "svc_assets_rest_requests_total";
+/// REST request latencies, by method and route.
+const REST_REQUEST_DURATION: &str = "svc_assets_rest_request_duration_seconds";
+/// REST requests rejected by the rate and concurrency limits.
+const REST_RATE_LIMITED: &str = "svc_assets_rest_rate_limited_total";
+/// gRPC requests, by method and status code.
+const GRPC_REQUESTS: &str = "svc_assets_grpc_requests_total";
+/// gRPC request latencies, by method.
+const GRPC_REQUEST_DURATION: &str = "svc_assets_grpc_request_duration_seconds";
+/// svc-storage client calls, by resource, operation and result.
+const STORAGE_CALLS: &str = "svc_assets_storage_calls_total";
+/// svc-storage client call latencies, by resource and operation.
+const STORAGE_CALL_DURATION: &str = "svc_assets_storage_call_duration_seconds";
+/// Asset cache lookups, by asset kind and result.
+const ASSET_CACHE_LOOKUPS: &str = "svc_assets_asset_cache_lookups_total";
+/// Audit log entries which could not be written to the audit file.
+const AUDIT_WRITE_FAILURES: &str = "svc_assets_audit_write_failures_total";
+
+/// Asset events waiting in the outbox to be delivered.
+const OUTBOX_BACKLOG: &str = "svc_assets_outbox_backlog";
+/// Asset events delivered from the outbox.
+const OUTBOX_DELIVERED: &str = "svc_assets_outbox_delivered_total";
+/// Failed attempts to deliver asset events from the outbox.
+const OUTBOX_FAILED_ATTEMPTS: &str = "svc_assets_outbox_failed_attempts_total";
+
+/// Outbox metrics in the order they are rendered, with their type and help.
+const OUTBOX_FAMILIES: [(&str, &str, &str); 3] = [
+    (
+        OUTBOX_BACKLOG,
+        "gauge",
+        "Number of asset events waiting in the outbox to be delivered.",
+    ),
+    (
+        OUTBOX_DELIVERED,
+        "counter",
+        "Number of asset events delivered from the outbox since startup.",
+    ),
+    (
+        OUTBOX_FAILED_ATTEMPTS,
+        "counter",
+        "Number of failed attempts to deliver asset events from the outbox since startup.",
+    ),
+];
+
+/// All metrics in the order they are rendered, with their type and help.
+const FAMILIES: [(&str, &str, &str); 9] = [
+    (REST_REQUESTS, "counter", "Number of REST requests handled."),
+    (
+        REST_REQUEST_DURATION,
+        "histogram",
+        "Duration of REST requests in seconds.",
+    ),
+    (
+        REST_RATE_LIMITED,
+        "counter",
+        "Number of REST requests rejected by the rate and concurrency limits.",
+    ),
+    (GRPC_REQUESTS, "counter", "Number of gRPC requests handled."),
+    (
+        GRPC_REQUEST_DURATION,
+        "histogram",
+        "Duration of gRPC requests in seconds.",
+    ),
+    (
+        STORAGE_CALLS,
+        "counter",
+        "Number of svc-storage client calls.",
+    ),
+    (
+        STORAGE_CALL_DURATION,
+        "histogram",
+        "Duration of svc-storage client calls in seconds.",
+    ),
+    (
+        ASSET_CACHE_LOOKUPS,
+        "counter",
+        "Number of asset lookups by id answered from the cache (hit) or svc-storage (miss).",
+    ),
+    (
+        AUDIT_WRITE_FAILURES,
+        "counter",
+        "Number of audit log entries which could not be written to the audit file.",
+    ),
+];
+
+/// Upper bounds in seconds of the latency histogram buckets.
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Label names and values of a single series.
+type Labels = Vec<(&'static str, String)>;
+
+/// Observations of a single latency series.
+#[derive(Clone, Debug, Default)]
+struct Histogram {
+    /// Number of observations per bucket, not cumulative.
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(bucket) = BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    counters: BTreeMap<&'static str, BTreeMap<Labels, u64>>,
+    histograms: BTreeMap<&'static str, BTreeMap<Labels, Histogram>>,
+}
+
+/// Request and storage call metrics of the service.
+#[derive(Clone, Debug, Default)]
+pub struct Metrics {
+    registry: Arc<Mutex<Registry>>,
+}
+
+/// Format labels as `{name="value",...}`, escaping the values.
+fn format_labels(labels: &[(&'static str, String)], extra: Option<(&str, String)>) -> String {
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| (*name, value.clone()))
+        .chain(extra)
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", name, value)
+        })
+        .collect();
+
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", labels.join(","))
+    }
+}
+
+impl Metrics {
+    /// Create empty metrics.
+    pub fn new() -> Self {
+        let metrics = Self::default();
+        // Unlabeled counters are rendered even if nothing has been counted
+        for name in [REST_RATE_LIMITED, AUDIT_WRITE_FAILURES] {
+            metrics
+                .lock()
+                .counters
+                .entry(name)
+                .or_default()
+                .insert(vec![], 0);
+        }
+        metrics
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Registry> {
+        match self.registry.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn increment(&self, name: &'static str, labels: Labels) {
+        *self
+            .lock()
+            .counters
+            .entry(name)
+            .or_default()
+            .entry(labels)
+            .or_default() += 1;
+    }
+
+    fn observe(&self, name: &'static str, labels: Labels, duration: Duration) {
+        self.lock()
+            .histograms
+            .entry(name)
+            .or_default()
+            .entry(labels)
+            .or_default()
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Record a handled REST request.
+    ///
+    /// The `route` is the matched route pattern, such as
+    /// `/assets/aircraft/:id`, so all requests of a route share a series.
+    pub fn record_rest_request(&self, method: &str, route: &str, status: u16, duration: Duration) {
+        let labels = vec![("method", method.to_string()), ("route", route.to_string())];
+        let mut counted = labels.clone();
+        counted.push(("status", status.to_string()));
+        self.increment(REST_REQUESTS, counted);
+        self.observe(REST_REQUEST_DURATION, labels, duration);
+    }
+
+    /// Record a REST request rejected by the rate or concurrency limits.
+    pub fn record_rate_limited(&self) {
+        self.increment(REST_RATE_LIMITED, vec![]);
+    }
+
+    /// Record a handled gRPC request, with the name of its status code.
+    pub fn record_grpc_request(&self, method: &str, code: &str, duration: Duration) {
+        self.increment(
+            GRPC_REQUESTS,
+            vec![("method", method.to_string()), ("code", code.to_string())],
+        );
+        self.observe(
+            GRPC_REQUEST_DURATION,
+            vec![("method", method.to_string())],
+            duration,
+        );
+    }
+
+    /// Record a call to a svc-storage `resource`, such as `vehicle`.
+    pub fn record_storage_call(
+        &self,
+        resource: &str,
+        operation: &str,
+        success: bool,
+        duration: Duration,
+    ) {
+        let labels = vec![
+            ("resource", resource.to_string()),
+            ("operation", operation.to_string()),
+        ];
+        let mut counted = labels.clone();
+        counted.push(("result", if success { "ok" } else { "error" }.to_string()));
+        self.increment(STORAGE_CALLS, counted);
+        self.observe(STORAGE_CALL_DURATION, labels, duration);
+    }
+
+    /// Record a lookup of an asset of `kind`, such as `vehicle`, in the
+    /// asset cache.
+    pub fn record_cache_lookup(&self, kind: &str, hit: bool) {
+        self.increment(
+            ASSET_CACHE_LOOKUPS,
+            vec![
+                ("kind", kind.to_string()),
+                ("result", if hit { "hit" } else { "miss" }.to_string()),
+            ],
+        );
+    }
+
+    /// Record an audit log entry which could not be written to the audit
+    /// file.
+    pub fn record_audit_write_failure(&self) {
+        self.increment(AUDIT_WRITE_FAILURES, vec![]);
+    }
+
+    /// Render all metrics in the Prometheus text exposition format.
+    ///
+    /// The outbox metrics are only rendered if the `outbox` statistics are
+    /// provided, as the outbox is only used if AMQP is enabled.
+    pub fn render(&self, outbox: Option<&OutboxStats>) -> String {
+        let registry = self.lock();
+        let mut out = String::new();
+
+        for (name, kind, help) in FAMILIES {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+
+            for (labels, value) in registry.counters.get(name).into_iter().flatten() {
+                let _ = writeln!(out, "{}{} {}", name, format_labels(labels, None), value);
+            }
+
+            for (labels, histogram) in registry.histograms.get(name).into_iter().flatten() {
+                let mut cumulative = 0;
+                for (bound, count) in BUCKETS.iter().zip(histogram.buckets) {
+                    cumulative += count;
+                    let _ = writeln!(
+                        out,
+                        "{}_bucket{} {}",
+                        name,
+                        format_labels(labels, Some(("le", bound.to_string()))),
+                        cumulative
+                    );
+                }
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{} {}",
+                    name,
+                    format_labels(labels, Some(("le", "+Inf".to_string()))),
+                    histogram.count
+                );
+                let labels = format_labels(labels, None);
+                let _ = writeln!(out, "{}_sum{} {}", name, labels, histogram.sum);
+                let _ = writeln!(out, "{}_count{} {}", name, labels, histogram.count);
+            }
+        }
+
+        if let Some(stats) = outbox {
+            let values = [
+                stats.backlog as u64,
+                stats.delivered_total,
+                stats.failed_attempts_total,
+            ];
+            for ((name, kind, help), value) in OUTBOX_FAMILIES.into_iter().zip(values) {
+                let _ = writeln!(out, "# HELP {} {}", name, help);
+                let _ = writeln!(out, "# TYPE {} {}", name, kind);
+                let _ = writeln!(out, "{} {}", name, value);
+            }
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_render() {
+        crate::get_log_handle().await;
+        ut_info!("(test_render) Start.");
+
+        let metrics = Metrics::new();
+        metrics.record_rest_request(
+            "GET",
+            "/assets/aircraft/:id",
+            200,
+            Duration::from_millis(20),
+        );
+        metrics.record_rest_request("GET", "/assets/aircraft/:id", 404, Duration::from_millis(3));
+        metrics.record_rate_limited();
+        metrics.record_grpc_request("is_ready", "Ok", Duration::from_millis(1));
+        metrics.record_storage_call("vehicle", "search", false, Duration::from_secs(20));
+        metrics.record_cache_lookup("vertipad", true);
+        metrics.record_cache_lookup("vertipad", true);
+        metrics.record_audit_write_failure();
+
+        let text = metrics.render(None);
+        ut_debug!("(test_render) {}", text);
+        assert!(text.contains("# TYPE svc_assets_rest_requests_total counter\n"));
+        assert!(text.contains(
+            "svc_assets_rest_requests_total{method=\"GET\",route=\"/assets/aircraft/:id\",status=\"404\"} 1\n"
+        ));
+        assert!(text.contains(
+            "svc_assets_rest_request_duration_seconds_bucket{method=\"GET\",route=\"/assets/aircraft/:id\",le=\"0.005\"} 1\n"
+        ));
+        assert!(text.contains(
+            "svc_assets_rest_request_duration_seconds_bucket{method=\"GET\",route=\"/assets/aircraft/:id\",le=\"0.025\"} 2\n"
+        ));
+        assert!(text.contains(
+            "svc_assets_rest_request_duration_seconds_count{method=\"GET\",route=\"/assets/aircraft/:id\"} 2\n"
+        ));
+        assert!(text.contains("svc_assets_rest_rate_limited_total 1\n"));
+        assert!(
+            text.contains("svc_assets_grpc_requests_total{method=\"is_ready\",code=\"Ok\"} 1\n")
+        );
+        assert!(text.contains(
+            "svc_assets_storage_calls_total{resource=\"vehicle\",operation=\"search\",result=\"error\"} 1\n"
+        ));
+        // Observations above the largest bucket are only counted in +Inf
+        assert!(text.contains(
+            "svc_assets_storage_call_duration_seconds_bucket{resource=\"vehicle\",operation=\"search\",le=\"10\"} 0\n"
+        ));
+        assert!(text.contains(
+            "svc_assets_storage_call_duration_seconds_bucket{resource=\"vehicle\",operation=\"search\",le=\"+Inf\"} 1\n"
+        ));
+        assert!(text.contains(
+            "svc_assets_asset_cache_lookups_total{kind=\"vertipad\",result=\"hit\"} 2\n"
+        ));
+        assert!(text.contains("svc_assets_audit_write_failures_total 1\n"));
+        assert!(!text.contains("svc_assets_outbox_backlog"));
+
+        let text = metrics.render(Some(&OutboxStats {
+            backlog: 3,
+            oldest_pending: None,
+            delivered_total: 12,
+            failed_attempts_total: 2,
+        }));
+        assert!(text.contains("# TYPE svc_assets_outbox_backlog gauge\n"));
+        assert!(text.contains("svc_assets_outbox_backlog 3\n"));
+        assert!(text.contains("svc_assets_outbox_delivered_total 12\n"));
+        assert!(text.contains("svc_assets_outbox_failed_attempts_total 2\n"));
+
+        ut_info!("(test_render) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_label_escaping() {
+        crate::get_log_handle().await;
+        ut_info!("(test_label_escaping) Start.");
+
+        assert_eq!(
+            format_labels(&[("route", "a\"b\\c\nd".to_string())], None),
+            "{route=\"a\\\"b\\\\c\\nd\"}"
+        );
+        assert_eq!(format_labels(&[], None), "");
+
+        ut_info!("(test_label_escaping) Success.");
+    }
+}
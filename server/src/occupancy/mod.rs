@@ -0,0 +1,320 @@
+//! Vertipad Occupancy
+//! keeps track of which aircraft occupies which vertipad
+
+#[macro_use]
+pub mod macros;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::OnceCell;
+use utoipa::ToSchema;
+
+/// Number of occupancy records kept for each vertipad.
+const HISTORY_SIZE: usize = 100;
+
+pub(crate) static OCCUPANCY: OnceCell<OccupancyStore> = OnceCell::const_new();
+
+/// Returns OCCUPANCY, the [`OccupancyStore`] of this service.
+/// Initializes OCCUPANCY if it hasn't been initialized yet.
+pub async fn get_occupancy() -> &'static OccupancyStore {
+    OCCUPANCY
+        .get_or_init(|| async move { OccupancyStore::default() })
+        .await
+}
+
+/// Occupancy of a vertipad by an aircraft.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct OccupancyRecord {
+    /// The id of the occupied [`Vertipad`](crate::rest::structs::Vertipad).
+    pub vertipad_id: String,
+    /// The id of the [`Aircraft`](crate::rest::structs::Aircraft)
+    /// occupying the vertipad.
+    pub aircraft_id: String,
+    /// The id of the flight the vertipad is occupied for, if any.
+    pub flight_id: Option<String>,
+    /// Time at which the vertipad has been occupied.
+    pub occupied_at: DateTime<Utc>,
+    /// Time at which the vertipad has been released, `None` while occupied.
+    pub released_at: Option<DateTime<Utc>>,
+}
+
+/// Errors returned when occupying or releasing a vertipad.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OccupancyError {
+    /// The vertipad is already occupied.
+    Occupied(OccupancyRecord),
+    /// The vertipad is not occupied.
+    NotOccupied,
+    /// The vertipad is occupied by another aircraft.
+    OccupiedByOther(OccupancyRecord),
+    /// The vertipad is marked occupied in storage, without an occupancy
+    /// recorded by this instance of the service.
+    OccupiedInStorage,
+}
+
+impl std::fmt::Display for OccupancyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OccupancyError::Occupied(record) => write!(
+                f,
+                "vertipad [{}] is already occupied by aircraft [{}]",
+                record.vertipad_id, record.aircraft_id
+            ),
+            OccupancyError::NotOccupied => write!(f, "vertipad is not occupied"),
+            OccupancyError::OccupiedByOther(record) => write!(
+                f,
+                "vertipad [{}] is occupied by another aircraft [{}]",
+                record.vertipad_id, record.aircraft_id
+            ),
+            OccupancyError::OccupiedInStorage => {
+                write!(f, "vertipad is marked occupied in storage")
+            }
+        }
+    }
+}
+
+/// Keeps the current occupancy and the occupancy history of all vertipads.
+///
+/// The history is kept in memory only.
+#[derive(Clone, Debug, Default)]
+pub struct OccupancyStore {
+    history: Arc<Mutex<HashMap<String, VecDeque<OccupancyRecord>>>>,
+}
+
+impl OccupancyStore {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, VecDeque<OccupancyRecord>>> {
+        match self.history.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Occupy a vertipad with the given aircraft.
+    ///
+    /// Fails if the vertipad is already occupied, even by the same aircraft.
+    pub fn occupy(
+        &self,
+        vertipad_id: &str,
+        aircraft_id: &str,
+        flight_id: Option<String>,
+    ) -> Result<OccupancyRecord, OccupancyError> {
+        let mut history = self.lock();
+        let records = history.entry(vertipad_id.to_string()).or_default();
+        if let Some(current) = records.back().filter(|r| r.released_at.is_none()) {
+            return Err(OccupancyError::Occupied(current.clone()));
+        }
+
+        let record = OccupancyRecord {
+            vertipad_id: vertipad_id.to_string(),
+            aircraft_id: aircraft_id.to_string(),
+            flight_id,
+            occupied_at: Utc::now(),
+            released_at: None,
+        };
+        if records.len() >= HISTORY_SIZE {
+            records.pop_front();
+        }
+        records.push_back(record.clone());
+        occupancy_debug!("(occupy) {:?}", record);
+
+        Ok(record)
+    }
+
+    /// Release a vertipad occupied by the given aircraft.
+    pub fn release(
+        &self,
+        vertipad_id: &str,
+        aircraft_id: &str,
+    ) -> Result<OccupancyRecord, OccupancyError> {
+        let mut history = self.lock();
+        let Some(current) = history
+            .get_mut(vertipad_id)
+            .and_then(|records| records.back_mut())
+            .filter(|r| r.released_at.is_none())
+        else {
+            return Err(OccupancyError::NotOccupied);
+        };
+        if current.aircraft_id != aircraft_id {
+            return Err(OccupancyError::OccupiedByOther(current.clone()));
+        }
+
+        current.released_at = Some(Utc::now());
+        occupancy_debug!("(release) {:?}", current);
+
+        Ok(current.clone())
+    }
+
+    /// Release a vertipad marked occupied in storage without a recorded
+    /// occupancy, e.g. since it has been occupied before a restart or
+    /// through another instance of the service.
+    ///
+    /// The time the vertipad has been occupied at is not known, the
+    /// release time is used instead.
+    pub fn release_unrecorded(
+        &self,
+        vertipad_id: &str,
+        aircraft_id: &str,
+    ) -> Result<OccupancyRecord, OccupancyError> {
+        let mut history = self.lock();
+        let records = history.entry(vertipad_id.to_string()).or_default();
+        if let Some(current) = records.back().filter(|r| r.released_at.is_none()) {
+            return Err(OccupancyError::Occupied(current.clone()));
+        }
+
+        let now = Utc::now();
+        let record = OccupancyRecord {
+            vertipad_id: vertipad_id.to_string(),
+            aircraft_id: aircraft_id.to_string(),
+            flight_id: None,
+            occupied_at: now,
+            released_at: Some(now),
+        };
+        if records.len() >= HISTORY_SIZE {
+            records.pop_front();
+        }
+        records.push_back(record.clone());
+        occupancy_debug!("(release_unrecorded) {:?}", record);
+
+        Ok(record)
+    }
+
+    /// Undo an [`occupy`](Self::occupy), [`release`](Self::release) or
+    /// [`release_unrecorded`](Self::release_unrecorded) resulting in the
+    /// given record.
+    ///
+    /// Used when the change could not be stored. The record made up by
+    /// `release_unrecorded` is dropped, it never describes the current
+    /// occupancy of the vertipad.
+    pub fn undo(&self, record: &OccupancyRecord) {
+        let mut history = self.lock();
+        let Some(records) = history.get_mut(&record.vertipad_id) else {
+            return;
+        };
+        if records.back() != Some(record) {
+            occupancy_warn!(
+                "(undo) record is no longer the latest, not undoing {:?}",
+                record
+            );
+            return;
+        }
+
+        match record.released_at {
+            // Made by release_unrecorded, released at its occupied_at time
+            Some(released_at) if released_at == record.occupied_at => {
+                records.pop_back();
+            }
+            Some(_) => {
+                if let Some(current) = records.back_mut() {
+                    current.released_at = None;
+                }
+            }
+            None => {
+                records.pop_back();
+            }
+        }
+    }
+
+    /// Returns the current occupancy of a vertipad, if occupied.
+    pub fn current(&self, vertipad_id: &str) -> Option<OccupancyRecord> {
+        self.lock()
+            .get(vertipad_id)
+            .and_then(|records| records.back())
+            .filter(|r| r.released_at.is_none())
+            .cloned()
+    }
+
+    /// Returns the occupancy history of a vertipad, oldest first.
+    pub fn history(&self, vertipad_id: &str) -> Vec<OccupancyRecord> {
+        self.lock()
+            .get(vertipad_id)
+            .map(|records| records.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_occupy_release() {
+        crate::get_log_handle().await;
+        ut_info!("(test_occupy_release) Start.");
+
+        let store = OccupancyStore::default();
+        assert!(store.current("pad").is_none());
+        assert_eq!(
+            store.release("pad", "aircraft_1"),
+            Err(OccupancyError::NotOccupied)
+        );
+
+        let record = store
+            .occupy("pad", "aircraft_1", Some("flight".to_string()))
+            .unwrap();
+        assert_eq!(store.current("pad"), Some(record.clone()));
+
+        // Double occupancy is rejected
+        assert_eq!(
+            store.occupy("pad", "aircraft_2", None),
+            Err(OccupancyError::Occupied(record.clone()))
+        );
+        assert_eq!(
+            store.release("pad", "aircraft_2"),
+            Err(OccupancyError::OccupiedByOther(record))
+        );
+
+        let released = store.release("pad", "aircraft_1").unwrap();
+        assert!(released.released_at.is_some());
+        assert!(store.current("pad").is_none());
+
+        store.occupy("pad", "aircraft_2", None).unwrap();
+        let history = store.history("pad");
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0], released);
+        assert_eq!(history[1].aircraft_id, "aircraft_2");
+
+        ut_info!("(test_occupy_release) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_undo() {
+        crate::get_log_handle().await;
+        ut_info!("(test_undo) Start.");
+
+        let store = OccupancyStore::default();
+        let occupied = store.occupy("pad", "aircraft", None).unwrap();
+        let released = store.release("pad", "aircraft").unwrap();
+
+        store.undo(&released);
+        assert_eq!(store.current("pad"), Some(occupied.clone()));
+
+        store.undo(&occupied);
+        assert!(store.current("pad").is_none());
+        assert!(store.history("pad").is_empty());
+
+        ut_info!("(test_undo) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_release_unrecorded() {
+        crate::get_log_handle().await;
+        ut_info!("(test_release_unrecorded) Start.");
+
+        let store = OccupancyStore::default();
+        let released = store.release_unrecorded("pad", "aircraft").unwrap();
+        assert_eq!(released.released_at, Some(released.occupied_at));
+        assert!(store.current("pad").is_none());
+        assert_eq!(store.history("pad"), vec![released.clone()]);
+
+        // Failing to store the release leaves the vertipad occupied in
+        // storage only, as the aircraft occupying it is still not known
+        store.undo(&released);
+        assert!(store.current("pad").is_none());
+        assert!(store.history("pad").is_empty());
+        assert!(store.release_unrecorded("pad", "aircraft").is_ok());
+
+        ut_info!("(test_release_unrecorded) Success.");
+    }
+}
@@ -0,0 +1,246 @@
+//! Registration Marks
+//! validates aircraft registration numbers per aviation authority
+//!
+//! A registration mark starts with the nationality prefix of the state of
+//! registry, for example `N` for the US or `D-` for Germany. Each prefix has
+//! its own [`MarkValidator`], checking the remainder of the mark against the
+//! rules of that state's aviation authority. Additional validators can be
+//! added to [`RegistrationValidators`] with
+//! [`register`](RegistrationValidators::register).
+
+use std::sync::Arc;
+
+/// Letters which are not used in registration marks, as they are easily
+/// confused with the digits 1 and 0.
+const AMBIGUOUS_LETTERS: [char; 2] = ['I', 'O'];
+
+/// Validates the registration marks of a single aviation authority.
+pub trait MarkValidator: Send + Sync {
+    /// The nationality prefix of the marks, including a trailing hyphen if
+    /// the marks have one.
+    fn prefix(&self) -> &str;
+
+    /// Check the part of a mark following the prefix.
+    fn validate(&self, suffix: &str) -> Result<(), String>;
+}
+
+/// US registration marks (N-numbers) as assigned by the FAA.
+///
+/// An N-number is `N` followed by one to five characters: a digit from 1 to
+/// 9, up to four more digits and up to two letters at the end. The letters
+/// `I` and `O` are not used.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NNumberValidator;
+
+impl MarkValidator for NNumberValidator {
+    fn prefix(&self) -> &str {
+        "N"
+    }
+
+    fn validate(&self, suffix: &str) -> Result<(), String> {
+        let digits = suffix.chars().take_while(|c| c.is_ascii_digit()).count();
+        let letters = &suffix[digits..];
+
+        if suffix.is_empty() || suffix.len() > 5 {
+            return Err("an N-number has 1 to 5 characters after the N".to_string());
+        }
+        if !suffix.starts_with(|c: char| ('1'..='9').contains(&c)) {
+            return Err("an N-number starts with a digit from 1 to 9 after the N".to_string());
+        }
+        if letters.len() > 2 || !letters.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err("an N-number may only end with up to 2 letters".to_string());
+        }
+        if letters.contains(AMBIGUOUS_LETTERS) {
+            return Err("an N-number can not contain the letters I and O".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// Registration marks made of a nationality prefix, a hyphen and a fixed
+/// number of letters, as used by most European aviation authorities.
+#[derive(Clone, Debug)]
+pub struct HyphenatedMarkValidator {
+    /// The nationality prefix including the hyphen, for example `D-`.
+    pub prefix: String,
+    /// The number of letters following the prefix.
+    pub letters: usize,
+}
+
+impl HyphenatedMarkValidator {
+    /// Create a validator for marks of `letters` letters following
+    /// `nationality` and a hyphen.
+    pub fn new(nationality: &str, letters: usize) -> Self {
+        Self {
+            prefix: format!("{}-", nationality),
+            letters,
+        }
+    }
+}
+
+impl MarkValidator for HyphenatedMarkValidator {
+    fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn validate(&self, suffix: &str) -> Result<(), String> {
+        if suffix.len() != self.letters || !suffix.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err(format!(
+                "a {} mark has {} letters after the prefix",
+                self.prefix, self.letters
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Nationality prefixes of the EU member states with the number of letters
+/// following the prefix.
+const EU_MARKS: [(&str, usize); 27] = [
+    ("5B", 3),
+    ("9A", 3),
+    ("9H", 3),
+    ("CS", 3),
+    ("D", 4),
+    ("EC", 3),
+    ("EI", 3),
+    ("ES", 3),
+    ("F", 4),
+    ("HA", 3),
+    ("I", 4),
+    ("LX", 3),
+    ("LY", 3),
+    ("LZ", 3),
+    ("OE", 3),
+    ("OH", 3),
+    ("OK", 3),
+    ("OM", 3),
+    ("OO", 3),
+    ("OY", 3),
+    ("PH", 3),
+    ("S5", 3),
+    ("SE", 3),
+    ("SP", 3),
+    ("SX", 3),
+    ("YL", 3),
+    ("YR", 3),
+];
+
+/// The validators of all known nationality prefixes.
+///
+/// The default validators cover US N-numbers and the marks of the EU
+/// member states.
+#[derive(Clone)]
+pub struct RegistrationValidators {
+    validators: Vec<Arc<dyn MarkValidator>>,
+}
+
+/// Returns a registration mark in its normalized upper case form, without
+/// validating it.
+pub fn normalize_mark(mark: &str) -> String {
+    mark.trim().to_ascii_uppercase()
+}
+
+impl Default for RegistrationValidators {
+    fn default() -> Self {
+        let mut validators = Self::empty();
+        validators.register(NNumberValidator);
+        for (nationality, letters) in EU_MARKS {
+            validators.register(HyphenatedMarkValidator::new(nationality, letters));
+        }
+        validators
+    }
+}
+
+impl std::fmt::Debug for RegistrationValidators {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.validators.iter().map(|validator| validator.prefix()))
+            .finish()
+    }
+}
+
+impl RegistrationValidators {
+    /// Create a set without any validators, rejecting all marks.
+    pub fn empty() -> Self {
+        Self { validators: vec![] }
+    }
+
+    /// Add the validator of a nationality prefix, replacing any validator
+    /// of the same prefix.
+    pub fn register(&mut self, validator: impl MarkValidator + 'static) {
+        self.validators
+            .retain(|existing| existing.prefix() != validator.prefix());
+        self.validators.push(Arc::new(validator));
+    }
+
+    /// Validate a registration mark, returning it in its normalized
+    /// upper case form.
+    ///
+    /// The validator with the longest prefix matching the mark is used.
+    pub fn validate(&self, mark: &str) -> Result<String, String> {
+        let mark = normalize_mark(mark);
+        let validator = self
+            .validators
+            .iter()
+            .filter(|validator| mark.starts_with(validator.prefix()))
+            .max_by_key(|validator| validator.prefix().len())
+            .ok_or_else(|| format!("unknown nationality prefix of [{}]", mark))?;
+
+        validator
+            .validate(&mark[validator.prefix().len()..])
+            .map_err(|e| format!("invalid registration mark [{}]: {}", mark, e))?;
+        Ok(mark)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_n_numbers() {
+        crate::get_log_handle().await;
+        ut_info!("(test_n_numbers) Start.");
+
+        let validators = RegistrationValidators::default();
+        for mark in ["N1", "N12345", "N1234Z", "N123AB", "n737ab "] {
+            assert!(validators.validate(mark).is_ok(), "{}", mark);
+        }
+        assert_eq!(validators.validate(" n737ab").unwrap(), "N737AB");
+        // Marks are normalized without being validated
+        assert_eq!(normalize_mark(" n0123 "), "N0123");
+
+        for mark in ["N", "N0123", "N123456", "NA123", "N12A3", "N1ABC", "N12IO"] {
+            assert!(validators.validate(mark).is_err(), "{}", mark);
+        }
+
+        ut_info!("(test_n_numbers) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_hyphenated_marks() {
+        crate::get_log_handle().await;
+        ut_info!("(test_hyphenated_marks) Start.");
+
+        let validators = RegistrationValidators::default();
+        for mark in ["D-ABCD", "F-GKXA", "EC-MYT", "9H-AEM", "ph-bxa"] {
+            assert!(validators.validate(mark).is_ok(), "{}", mark);
+        }
+        for mark in ["D-ABC", "EC-MYTA", "PH-B1A", "PHBXA", "ZZ-ABC", ""] {
+            assert!(validators.validate(mark).is_err(), "{}", mark);
+        }
+
+        // Validators can be added and replaced
+        let mut validators = RegistrationValidators::empty();
+        assert!(validators.validate("G-ABCD").is_err());
+        validators.register(HyphenatedMarkValidator::new("G", 4));
+        assert!(validators.validate("G-ABCD").is_ok());
+        validators.register(HyphenatedMarkValidator::new("G", 3));
+        assert!(validators.validate("G-ABCD").is_err());
+
+        ut_info!("(test_hyphenated_marks) Success.");
+    }
+}
@@ -0,0 +1,259 @@
+//! Config Reload
+//! applies configuration changes without restarting the service
+//!
+//! The configuration is loaded again on `SIGHUP` by the [`reload_task`],
+//! and on demand by the `/admin/config/reload` REST endpoint. The
+//! [`LIVE_OPTIONS`] are applied right away, and the log configuration file
+//! is loaded again. Other options which changed keep their current value
+//! and are reported as requiring a restart.
+
+#[macro_use]
+pub mod macros;
+
+use crate::Config;
+use config::ConfigError;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{watch, Mutex, OnceCell};
+use utoipa::ToSchema;
+
+/// Options applied without restarting the service.
+pub const LIVE_OPTIONS: [&str; 4] = [
+    "rest_request_limit_per_second",
+    "rest_concurrency_limit_per_service",
+    "rest_cors_allowed_origin",
+    "log_config",
+];
+
+pub(crate) static CONFIG_RELOADER: OnceCell<ConfigReloader> = OnceCell::const_new();
+
+/// Returns CONFIG_RELOADER, a [`ConfigReloader`] starting from a Config
+/// object generated from environment variables.
+/// Initializes CONFIG_RELOADER if it hasn't been initialized yet.
+pub async fn get_config_reloader() -> &'static ConfigReloader {
+    CONFIG_RELOADER
+        .get_or_init(|| async move {
+            let config = Config::try_from_env().unwrap_or_default();
+            ConfigReloader::new(config)
+        })
+        .await
+}
+
+/// Options which changed with a reload.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ReloadReport {
+    /// Options applied right away.
+    pub applied: Vec<String>,
+    /// Options which keep their current value until the service restarts.
+    pub restart_required: Vec<String>,
+    /// Options which could not be applied, like a log configuration file
+    /// which does not load. They keep their current value.
+    pub failed: Vec<String>,
+}
+
+/// Holds the effective configuration, updated by reloads.
+///
+/// Components applying the [`LIVE_OPTIONS`] are notified of changes
+/// through [`subscribe`](Self::subscribe).
+#[derive(Clone, Debug)]
+pub struct ConfigReloader {
+    config: Arc<watch::Sender<Config>>,
+    /// Makes reloads happen one at a time.
+    reloading: Arc<Mutex<()>>,
+}
+
+impl ConfigReloader {
+    /// Create a reloader starting from the `config` the service started
+    /// with.
+    pub fn new(config: Config) -> Self {
+        Self {
+            config: Arc::new(watch::channel(config).0),
+            reloading: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Returns the effective configuration.
+    pub fn current(&self) -> Config {
+        self.config.borrow().clone()
+    }
+
+    /// Returns a receiver notified when live options are applied.
+    pub fn subscribe(&self) -> watch::Receiver<Config> {
+        self.config.subscribe()
+    }
+
+    /// Load the configuration from the configuration file and environment
+    /// variables, and apply it.
+    pub async fn reload(&self) -> Result<ReloadReport, ConfigError> {
+        let result = match Config::try_from_env() {
+            Ok(loaded) => self.apply(loaded).await,
+            Err(e) => Err(e),
+        };
+        match &result {
+            Ok(report) => reload_info!(
+                "(reload) applied {:?}, restart required for {:?}, failed {:?}.",
+                report.applied,
+                report.restart_required,
+                report.failed
+            ),
+            Err(e) => reload_error!("(reload) configuration not reloaded: {}", e),
+        }
+        result
+    }
+
+    /// Apply the live options of a `loaded` configuration, which is
+    /// rejected as a whole if it is invalid.
+    pub async fn apply(&self, loaded: Config) -> Result<ReloadReport, ConfigError> {
+        loaded.validate()?;
+        let _reloading = self.reloading.lock().await;
+
+        let current = self.current();
+        let mut config = current.clone();
+        config.rest_request_limit_per_second = loaded.rest_request_limit_per_second;
+        config.rest_concurrency_limit_per_service = loaded.rest_concurrency_limit_per_service;
+        config
+            .rest_cors_allowed_origin
+            .clone_from(&loaded.rest_cors_allowed_origin);
+
+        // The log configuration file is loaded again even if its path did
+        // not change, to apply changed log levels.
+        let mut failed = vec![];
+        match crate::load_logger_config_from_file(&loaded.log_config).await {
+            Ok(()) => config.log_config.clone_from(&loaded.log_config),
+            Err(e) => {
+                reload_warn!("(apply) log configuration not reloaded: {}", e);
+                failed.push("log_config".to_string());
+            }
+        }
+
+        let mut report = changes(&current, &loaded, &config)?;
+        report
+            .restart_required
+            .retain(|option| !failed.contains(option));
+        report.failed = failed;
+        if !report.applied.is_empty() {
+            self.config.send_replace(config);
+        }
+        Ok(report)
+    }
+}
+
+/// Compare the `current` and `loaded` configurations, given the options
+/// applied in the `effective` one.
+fn changes(
+    current: &Config,
+    loaded: &Config,
+    effective: &Config,
+) -> Result<ReloadReport, ConfigError> {
+    let to_map = |config: &Config| match serde_json::to_value(config) {
+        Ok(serde_json::Value::Object(map)) => Ok(map),
+        Ok(_) => Err(ConfigError::Message("config is not an object".to_string())),
+        Err(e) => Err(ConfigError::Message(e.to_string())),
+    };
+    let current = to_map(current)?;
+    let loaded = to_map(loaded)?;
+    let effective = to_map(effective)?;
+
+    let mut report = ReloadReport::default();
+    for (option, value) in loaded {
+        if current.get(&option) == Some(&value) {
+            continue;
+        }
+        if effective.get(&option) == Some(&value) {
+            report.applied.push(option);
+        } else {
+            report.restart_required.push(option);
+        }
+    }
+    Ok(report)
+}
+
+/// Reload the configuration each time the service receives `SIGHUP`.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: Needs a signal to be sent to the process.
+pub async fn reload_task() {
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            reload_error!("(reload_task) could not listen for SIGHUP: {}", e);
+            return;
+        }
+    };
+
+    reload_info!("(reload_task) reloading the configuration on SIGHUP.");
+    let reloader = get_config_reloader().await;
+    while hangups.recv().await.is_some() {
+        reload_info!("(reload_task) SIGHUP received.");
+        let _ = reloader.reload().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_apply() {
+        crate::get_log_handle().await;
+        ut_info!("(test_apply) Start.");
+
+        let reloader = ConfigReloader::new(Config::default());
+        let mut updates = reloader.subscribe();
+
+        let loaded = Config {
+            rest_request_limit_per_second: 10,
+            rest_cors_allowed_origin: String::from("https://allowed.origin.host"),
+            docker_port_rest: 9000,
+            ..Default::default()
+        };
+        let report = reloader.apply(loaded).await.unwrap();
+        assert_eq!(
+            report.applied,
+            vec!["rest_cors_allowed_origin", "rest_request_limit_per_second"]
+        );
+        assert_eq!(report.restart_required, vec!["docker_port_rest"]);
+        assert!(updates.has_changed().unwrap());
+
+        let config = updates.borrow_and_update().clone();
+        assert_eq!(config.rest_request_limit_per_second, 10);
+        assert_eq!(
+            config.rest_cors_allowed_origin,
+            String::from("https://allowed.origin.host")
+        );
+        assert_eq!(config.docker_port_rest, 8000);
+
+        // Options requiring a restart are reported until the service restarts
+        let loaded = Config {
+            docker_port_rest: 9000,
+            ..config.clone()
+        };
+        let report = reloader.apply(loaded).await.unwrap();
+        assert!(report.applied.is_empty());
+        assert_eq!(report.restart_required, vec!["docker_port_rest"]);
+        assert!(!updates.has_changed().unwrap());
+
+        // Log configurations which do not load are reported as failed
+        let loaded = Config {
+            log_config: String::from("missing_log_config.yaml"),
+            ..config.clone()
+        };
+        let report = reloader.apply(loaded).await.unwrap();
+        assert!(report.applied.is_empty());
+        assert!(report.restart_required.is_empty());
+        assert_eq!(report.failed, vec!["log_config"]);
+        assert_eq!(reloader.current().log_config, String::from("log4rs.yaml"));
+        assert!(!updates.has_changed().unwrap());
+
+        // Invalid configurations are not applied
+        let loaded = Config {
+            rest_concurrency_limit_per_service: 0,
+            ..config
+        };
+        assert!(reloader.apply(loaded).await.is_err());
+        assert_eq!(reloader.current().rest_concurrency_limit_per_service, 5);
+        assert!(!updates.has_changed().unwrap());
+
+        ut_info!("(test_apply) Success.");
+    }
+}
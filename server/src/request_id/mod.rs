@@ -0,0 +1,180 @@
+//! Request Ids
+//! ties log lines, errors and audit entries to the request they belong to
+//!
+//! Every REST request gets an id, taken from its `X-Request-Id` header if
+//! valid or generated otherwise. The id is kept in a task local while the
+//! request is handled, and in the log4rs MDC (mapped diagnostic context)
+//! while the request's future is polled, so log lines can include it with
+//! the `{X(request_id)}` pattern.
+
+use axum::http::HeaderMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Request header carrying the request id.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Key of the request id in the log4rs MDC.
+pub const MDC_KEY: &str = "request_id";
+
+/// Maximum length of a request id accepted from a client.
+const MAX_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static CURRENT: RequestId;
+}
+
+/// Returns the id of the request the current task handles, if any.
+pub fn current() -> Option<RequestId> {
+    CURRENT.try_with(|request_id| request_id.clone()).ok()
+}
+
+/// The id of a request.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RequestId(String);
+
+impl Default for RequestId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RequestId {
+    /// Generate a new, random request id.
+    pub fn new() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Use the id provided by a client, if valid.
+    ///
+    /// Ids must be non-empty, at most 128 characters and only consist of
+    /// visible ASCII characters, so they can't break up log lines.
+    pub fn parse(id: &str) -> Option<Self> {
+        let id = id.trim();
+        if id.is_empty() || id.len() > MAX_LENGTH || !id.chars().all(|c| c.is_ascii_graphic()) {
+            return None;
+        }
+
+        Some(Self(id.to_string()))
+    }
+
+    /// Extract the request id from the `X-Request-Id` header of a REST
+    /// request, if valid.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(Self::parse)
+    }
+
+    /// Returns the id as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Run `future` as the handling of this request, making the id
+    /// available through [`current`] and the log4rs MDC.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        let future = WithMdc {
+            request_id: self.0.clone(),
+            future: Box::pin(future),
+        };
+        CURRENT.scope(self, future).await
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Sets the request id in the log4rs MDC while polling a future.
+///
+/// The MDC is thread local, so it's set on each poll as the future may
+/// move between worker threads, and restored afterwards.
+struct WithMdc<F> {
+    request_id: String,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for WithMdc<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let previous = log_mdc::insert(MDC_KEY, self.request_id.as_str());
+        let poll = self.future.as_mut().poll(cx);
+        match previous {
+            Some(previous) => {
+                log_mdc::insert(MDC_KEY, previous);
+            }
+            None => {
+                log_mdc::remove(MDC_KEY);
+            }
+        }
+        poll
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_parse() {
+        crate::get_log_handle().await;
+        ut_info!("(test_parse) Start.");
+
+        assert_eq!(
+            RequestId::parse(" abc-123 ").map(|id| id.to_string()),
+            Some("abc-123".to_string())
+        );
+        assert_eq!(RequestId::parse(""), None);
+        assert_eq!(RequestId::parse("a b"), None);
+        assert_eq!(RequestId::parse("a\nb"), None);
+        assert_eq!(RequestId::parse(&"a".repeat(MAX_LENGTH + 1)), None);
+        assert!(RequestId::parse(&"a".repeat(MAX_LENGTH)).is_some());
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(RequestId::from_headers(&headers), None);
+        headers.insert(REQUEST_ID_HEADER, "abc-123".parse().unwrap());
+        assert_eq!(
+            RequestId::from_headers(&headers),
+            RequestId::parse("abc-123")
+        );
+
+        // Generated ids are unique
+        assert_ne!(RequestId::new(), RequestId::new());
+
+        ut_info!("(test_parse) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_scope() {
+        crate::get_log_handle().await;
+        ut_info!("(test_scope) Start.");
+
+        let request_id = RequestId::new();
+        assert_eq!(current(), None);
+        let (current_id, mdc_id) = request_id
+            .clone()
+            .scope(async {
+                tokio::task::yield_now().await;
+                (
+                    current(),
+                    log_mdc::get(MDC_KEY, |id| id.map(|id| id.to_string())),
+                )
+            })
+            .await;
+        assert_eq!(current_id, Some(request_id.clone()));
+        assert_eq!(mdc_id, Some(request_id.to_string()));
+        // The MDC is cleared after each poll
+        assert_eq!(
+            log_mdc::get(MDC_KEY, |id| id.map(|id| id.to_string())),
+            None
+        );
+
+        ut_info!("(test_scope) Success.");
+    }
+}
@@ -0,0 +1,4 @@
+//! log macro's for vertipad reservation logging
+
+use lib_common::log_macros;
+log_macros!("reservations");
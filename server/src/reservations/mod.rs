@@ -0,0 +1,483 @@
+//! Vertipad Reservations
+//! keeps track of the time windows for which vertipads are reserved
+//!
+//! Reservations are kept in a JSON file, so they survive a restart.
+//! Reservations which have ended are dropped with the next change.
+
+#[macro_use]
+pub mod macros;
+pub mod schedule;
+
+use crate::json_file::{JsonFileStore, JsonRecord};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::OnceCell;
+use utoipa::ToSchema;
+
+pub(crate) static RESERVATIONS: OnceCell<ReservationStore> = OnceCell::const_new();
+
+/// Returns RESERVATIONS, a [`ReservationStore`] configured using a Config
+/// object generated from environment variables.
+/// Initializes RESERVATIONS if it hasn't been initialized yet, in memory if
+/// the reservations file can not be used.
+pub async fn get_reservations() -> &'static ReservationStore {
+    RESERVATIONS
+        .get_or_init(|| async move {
+            let config = crate::Config::try_from_env().unwrap_or_default();
+            match ReservationStore::open(&config.reservations_file) {
+                Ok(store) => store,
+                Err(e) => {
+                    reservations_error!(
+                        "(get_reservations) {}, reservations will be kept in memory.",
+                        e
+                    );
+                    ReservationStore::default()
+                }
+            }
+        })
+        .await
+}
+
+/// Initializes RESERVATIONS with the reservations file configured in
+/// `config`, failing if the file can not be used.
+pub async fn init_reservations(
+    config: &crate::Config,
+) -> Result<&'static ReservationStore, std::io::Error> {
+    RESERVATIONS
+        .get_or_try_init(|| async move { ReservationStore::open(&config.reservations_file) })
+        .await
+}
+
+/// Reservation of a vertipad for a landing or takeoff window.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Reservation {
+    /// The id of the reservation.
+    pub id: String,
+    /// The id of the reserved [`Vertipad`](crate::rest::structs::Vertipad).
+    pub vertipad_id: String,
+    /// The id of the [`Aircraft`](crate::rest::structs::Aircraft)
+    /// the vertipad is reserved for.
+    pub aircraft_id: String,
+    /// The id of the flight the vertipad is reserved for, if any.
+    pub flight_id: Option<String>,
+    /// Start of the reserved window.
+    pub start: DateTime<Utc>,
+    /// End of the reserved window.
+    pub end: DateTime<Utc>,
+    /// Time at which the reservation has been created.
+    pub created_at: DateTime<Utc>,
+}
+
+impl JsonRecord for Reservation {
+    fn key(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Reservation {
+    /// Check if the reserved window overlaps the window from `start` to `end`.
+    ///
+    /// Windows touching each other do not overlap.
+    pub fn overlaps(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        self.start < end && start < self.end
+    }
+}
+
+/// Reason for a reservation to be rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum ConflictReason {
+    /// The vertipad is not in business.
+    VertipadDisabled,
+    /// The window is not within the schedule of the vertipad.
+    OutsideSchedule,
+    /// The window overlaps one or more existing reservations.
+    Overlap,
+}
+
+/// Conflict returned when a reservation is rejected.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ReservationConflict {
+    /// The reasons for the reservation to be rejected.
+    pub reasons: Vec<ConflictReason>,
+    /// The ids of the existing reservations overlapping the window.
+    pub blocking_reservation_ids: Vec<String>,
+}
+
+/// Errors returned when changing a reservation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReservationError {
+    /// The reservation does not exist.
+    NotFound,
+    /// The reservation conflicts with the vertipad or other reservations.
+    Conflict(ReservationConflict),
+    /// The change could not be written to the reservations file.
+    File(String),
+}
+
+impl std::fmt::Display for ReservationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ReservationError::NotFound => write!(f, "reservation not found"),
+            ReservationError::Conflict(conflict) => write!(
+                f,
+                "reservation conflicts: {:?}, blocked by {:?}",
+                conflict.reasons, conflict.blocking_reservation_ids
+            ),
+            ReservationError::File(e) => {
+                write!(f, "could not write the reservations file: {}", e)
+            }
+        }
+    }
+}
+
+/// Keeps the reservations of all vertipads.
+///
+/// If no file path is provided, the reservations are kept in memory only.
+// TODO(R5): svc-storage does not store reservations yet, so each instance
+// of the service only checks the reservations made through it
+#[derive(Clone, Debug, Default)]
+pub struct ReservationStore {
+    reservations: JsonFileStore<Reservation>,
+}
+
+/// Returns the reservations of a vertipad, ordered by start time.
+fn of_vertipad(all: &HashMap<String, Reservation>, vertipad_id: &str) -> Vec<Reservation> {
+    let mut reservations: Vec<Reservation> = all
+        .values()
+        .filter(|r| r.vertipad_id == vertipad_id)
+        .cloned()
+        .collect();
+    reservations.sort_by_key(|r| r.start);
+    reservations
+}
+
+impl ReservationStore {
+    /// Open the reservations stored at `path`.
+    ///
+    /// Fails if the file can not be used.
+    pub fn open(path: &str) -> Result<Self, std::io::Error> {
+        if path.is_empty() {
+            reservations_warn!(
+                "(ReservationStore::open) no reservations file configured, reservations will be kept in memory."
+            );
+        }
+
+        let reservations = JsonFileStore::open(path)
+            .map_err(|e| std::io::Error::new(e.kind(), format!("reservations {}", e)))?;
+        reservations_info!(
+            "(ReservationStore::open) opened reservations file [{}] with {} reservation(s).",
+            path,
+            reservations.len()
+        );
+        Ok(Self { reservations })
+    }
+
+    /// Apply a change to the reservations, after dropping the reservations
+    /// which have ended.
+    fn modify<R>(
+        &self,
+        change: impl FnOnce(&mut HashMap<String, Reservation>) -> Result<R, ReservationError>,
+    ) -> Result<R, ReservationError> {
+        let now = Utc::now();
+        self.reservations
+            .modify(|all| {
+                all.retain(|_, r| r.end > now);
+                change(all)
+            })
+            .map_err(|e| ReservationError::File(e.to_string()))?
+    }
+
+    /// Returns a conflict if `reasons` is not empty or if the window from
+    /// `start` to `end` overlaps any of the `reservations` other than
+    /// `exclude_id`.
+    fn check(
+        reservations: &[Reservation],
+        exclude_id: Option<&str>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        mut reasons: Vec<ConflictReason>,
+    ) -> Result<(), ReservationConflict> {
+        let blocking_reservation_ids: Vec<String> = reservations
+            .iter()
+            .filter(|r| Some(r.id.as_str()) != exclude_id && r.overlaps(start, end))
+            .map(|r| r.id.clone())
+            .collect();
+        if !blocking_reservation_ids.is_empty() {
+            reasons.push(ConflictReason::Overlap);
+        }
+
+        if reasons.is_empty() {
+            Ok(())
+        } else {
+            Err(ReservationConflict {
+                reasons,
+                blocking_reservation_ids,
+            })
+        }
+    }
+
+    /// Add a reservation, unless it overlaps an existing reservation of the
+    /// same vertipad.
+    ///
+    /// Conflicts found by the caller, such as the vertipad being disabled,
+    /// are passed in as `reasons` so all conflicts are reported together.
+    pub fn create(
+        &self,
+        reservation: Reservation,
+        reasons: Vec<ConflictReason>,
+    ) -> Result<Reservation, ReservationError> {
+        let reservation = self.modify(|all| {
+            Self::check(
+                &of_vertipad(all, &reservation.vertipad_id),
+                None,
+                reservation.start,
+                reservation.end,
+                reasons,
+            )
+            .map_err(ReservationError::Conflict)?;
+
+            all.insert(reservation.id.clone(), reservation.clone());
+            Ok(reservation)
+        })?;
+        reservations_debug!("(create) {:?}", reservation);
+
+        Ok(reservation)
+    }
+
+    /// Move the end of a reservation to `end`, unless the new window
+    /// overlaps another reservation of the same vertipad.
+    pub fn extend(
+        &self,
+        vertipad_id: &str,
+        reservation_id: &str,
+        end: DateTime<Utc>,
+        reasons: Vec<ConflictReason>,
+    ) -> Result<Reservation, ReservationError> {
+        let reservation = self.modify(|all| {
+            let Some(mut reservation) = all
+                .get(reservation_id)
+                .filter(|r| r.vertipad_id == vertipad_id)
+                .cloned()
+            else {
+                return Err(ReservationError::NotFound);
+            };
+            Self::check(
+                &of_vertipad(all, vertipad_id),
+                Some(reservation_id),
+                reservation.start,
+                end,
+                reasons,
+            )
+            .map_err(ReservationError::Conflict)?;
+
+            reservation.end = end;
+            all.insert(reservation.id.clone(), reservation.clone());
+            Ok(reservation)
+        })?;
+        reservations_debug!("(extend) {:?}", reservation);
+
+        Ok(reservation)
+    }
+
+    /// Cancel a reservation, returning the cancelled reservation.
+    pub fn cancel(
+        &self,
+        vertipad_id: &str,
+        reservation_id: &str,
+    ) -> Result<Reservation, ReservationError> {
+        let reservation = self.modify(|all| match all.get(reservation_id) {
+            Some(r) if r.vertipad_id == vertipad_id => {
+                all.remove(reservation_id).ok_or(ReservationError::NotFound)
+            }
+            _ => Err(ReservationError::NotFound),
+        })?;
+        reservations_debug!("(cancel) {:?}", reservation);
+
+        Ok(reservation)
+    }
+
+    /// Returns a reservation of a vertipad.
+    pub fn get(&self, vertipad_id: &str, reservation_id: &str) -> Option<Reservation> {
+        self.reservations
+            .get(reservation_id)
+            .filter(|r| r.vertipad_id == vertipad_id)
+    }
+
+    /// Returns the reservation of a vertipad active at `at`, if any.
+    pub fn active(&self, vertipad_id: &str, at: DateTime<Utc>) -> Option<Reservation> {
+        self.reservations
+            .values()
+            .into_iter()
+            .find(|r| r.vertipad_id == vertipad_id && r.start <= at && at < r.end)
+    }
+
+    /// Returns the reservations of a vertipad which have not ended yet,
+    /// ordered by start time.
+    pub fn list(&self, vertipad_id: &str) -> Vec<Reservation> {
+        let now = Utc::now();
+        let mut reservations: Vec<Reservation> = self
+            .reservations
+            .values()
+            .into_iter()
+            .filter(|r| r.vertipad_id == vertipad_id && r.end > now)
+            .collect();
+        reservations.sort_by_key(|r| r.start);
+        reservations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn reservation(id: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Reservation {
+        Reservation {
+            id: id.to_string(),
+            vertipad_id: "pad".to_string(),
+            aircraft_id: "aircraft".to_string(),
+            flight_id: None,
+            start,
+            end,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_overlap() {
+        crate::get_log_handle().await;
+        ut_info!("(test_create_overlap) Start.");
+
+        let store = ReservationStore::default();
+        let now = Utc::now();
+        let hour = Duration::hours(1);
+
+        let first = store
+            .create(reservation("1", now + hour, now + hour * 2), vec![])
+            .unwrap();
+        // Touching windows don't overlap
+        store
+            .create(reservation("2", now + hour * 2, now + hour * 3), vec![])
+            .unwrap();
+
+        assert_eq!(
+            store.create(reservation("3", now + hour / 2, now + hour * 5 / 2), vec![]),
+            Err(ReservationError::Conflict(ReservationConflict {
+                reasons: vec![ConflictReason::Overlap],
+                blocking_reservation_ids: vec!["1".to_string(), "2".to_string()],
+            }))
+        );
+
+        // Reasons of the caller are reported together with overlaps
+        assert_eq!(
+            store.create(
+                reservation("3", now, now + hour * 3 / 2),
+                vec![ConflictReason::OutsideSchedule]
+            ),
+            Err(ReservationError::Conflict(ReservationConflict {
+                reasons: vec![ConflictReason::OutsideSchedule, ConflictReason::Overlap],
+                blocking_reservation_ids: vec!["1".to_string()],
+            }))
+        );
+        assert_eq!(
+            store.create(
+                reservation("3", now, now + hour),
+                vec![ConflictReason::VertipadDisabled]
+            ),
+            Err(ReservationError::Conflict(ReservationConflict {
+                reasons: vec![ConflictReason::VertipadDisabled],
+                blocking_reservation_ids: vec![],
+            }))
+        );
+
+        let list = store.list("pad");
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0], first);
+        assert!(store.list("other").is_empty());
+
+        ut_info!("(test_create_overlap) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_extend_cancel() {
+        crate::get_log_handle().await;
+        ut_info!("(test_extend_cancel) Start.");
+
+        let store = ReservationStore::default();
+        let now = Utc::now();
+        let hour = Duration::hours(1);
+
+        store
+            .create(reservation("1", now, now + hour), vec![])
+            .unwrap();
+        store
+            .create(reservation("2", now + hour * 2, now + hour * 3), vec![])
+            .unwrap();
+
+        // Extending doesn't conflict with the reservation itself
+        let extended = store.extend("pad", "1", now + hour * 2, vec![]).unwrap();
+        assert_eq!(extended.end, now + hour * 2);
+        assert_eq!(store.get("pad", "1"), Some(extended));
+
+        assert_eq!(
+            store.extend("pad", "1", now + hour * 3, vec![]),
+            Err(ReservationError::Conflict(ReservationConflict {
+                reasons: vec![ConflictReason::Overlap],
+                blocking_reservation_ids: vec!["2".to_string()],
+            }))
+        );
+        assert_eq!(
+            store.extend("pad", "3", now + hour, vec![]),
+            Err(ReservationError::NotFound)
+        );
+
+        let cancelled = store.cancel("pad", "2").unwrap();
+        assert_eq!(cancelled.id, "2");
+        assert_eq!(store.cancel("pad", "2"), Err(ReservationError::NotFound));
+        assert!(store.get("pad", "2").is_none());
+        assert_eq!(
+            store.active("pad", now).map(|r| r.id),
+            Some("1".to_string())
+        );
+        assert!(store.active("pad", now - hour).is_none());
+        store.extend("pad", "1", now + hour * 3, vec![]).unwrap();
+
+        ut_info!("(test_extend_cancel) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_reservations_file() {
+        crate::get_log_handle().await;
+        ut_info!("(test_reservations_file) Start.");
+
+        let path = std::env::temp_dir().join(format!(
+            "svc-assets-reservations-{}.json",
+            uuid::Uuid::new_v4()
+        ));
+        let path_str = path.to_string_lossy().to_string();
+        let now = Utc::now();
+        let hour = Duration::hours(1);
+
+        let store = ReservationStore::open(&path_str).unwrap();
+        store
+            .create(reservation("ended", now - hour * 2, now - hour), vec![])
+            .unwrap();
+        assert!(store.list("pad").is_empty());
+        store
+            .create(reservation("1", now + hour, now + hour * 2), vec![])
+            .unwrap();
+
+        // Reservations are restored from the file, without the ended ones
+        let store = ReservationStore::open(&path_str).unwrap();
+        assert_eq!(store.reservations.len(), 1);
+        assert!(store.get("pad", "1").is_some());
+        assert!(matches!(
+            store.create(reservation("2", now, now + hour * 3 / 2), vec![]),
+            Err(ReservationError::Conflict(_))
+        ));
+
+        std::fs::remove_file(path).ok();
+        ut_info!("(test_reservations_file) Success.");
+    }
+}
@@ -0,0 +1,181 @@
+//! Asset schedules
+//!
+//! Schedules are stored as RRULE strings, with the duration of each
+//! occurrence added to the `DTSTART` line, for example:
+//!
+//! ```text
+//! DTSTART:20221020T180000Z;DURATION:PT14H
+//! RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+use rrule::{RRuleSet, Tz};
+use std::str::FromStr;
+
+/// Maximum number of occurrences checked for a single window.
+const MAX_OCCURRENCES: u16 = 100;
+
+/// Error returned when parsing a schedule fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleError(String);
+
+impl std::fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "invalid schedule: {}", self.0)
+    }
+}
+
+/// A recurring availability schedule of an asset.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    rrule_set: RRuleSet,
+    duration: Duration,
+}
+
+impl FromStr for Schedule {
+    type Err = ScheduleError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut duration = None;
+        let mut lines = vec![];
+        for line in value.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            match line.split_once(";DURATION:") {
+                Some((dtstart, value)) if line.starts_with("DTSTART") => {
+                    duration =
+                        Some(parse_duration(value).ok_or_else(|| {
+                            ScheduleError(format!("invalid duration [{}]", value))
+                        })?);
+                    lines.push(dtstart);
+                }
+                _ => lines.push(line),
+            }
+        }
+
+        let duration = duration.ok_or_else(|| ScheduleError("missing duration".to_string()))?;
+        let rrule_set = lines
+            .join("\n")
+            .parse::<RRuleSet>()
+            .map_err(|e| ScheduleError(e.to_string()))?;
+
+        Ok(Self {
+            rrule_set,
+            duration,
+        })
+    }
+}
+
+impl Schedule {
+    /// Check if the window from `start` to `end` falls entirely within a
+    /// single occurrence of the schedule.
+    pub fn contains(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        // Only occurrences starting within `duration` before the window
+        // can contain it. The range is widened by a second since the
+        // bounds are not guaranteed to be inclusive.
+        let margin = Duration::seconds(1);
+        let after = (start - self.duration - margin).with_timezone(&Tz::UTC);
+        let before = (start + margin).with_timezone(&Tz::UTC);
+
+        self.rrule_set
+            .clone()
+            .after(after)
+            .before(before)
+            .all(MAX_OCCURRENCES)
+            .dates
+            .into_iter()
+            .map(|occurrence| occurrence.with_timezone(&Utc))
+            .any(|occurrence| occurrence <= start && occurrence + self.duration >= end)
+    }
+}
+
+/// Parse an ISO 8601 duration, for example `PT14H` or `P1DT30M`.
+///
+/// Years and months are not supported, as their length varies.
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.strip_prefix('P')?;
+    let (date, time) = match value.split_once('T') {
+        Some((date, time)) => (date, time),
+        None => (value, ""),
+    };
+
+    let mut duration = Duration::zero();
+    let mut found = false;
+    for (part, units) in [(date, "WD"), (time, "HMS")] {
+        let mut number = String::new();
+        for c in part.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            if !units.contains(c) || number.is_empty() {
+                return None;
+            }
+
+            let amount = number.parse::<i64>().ok()?;
+            duration = duration
+                + match c {
+                    'W' => Duration::weeks(amount),
+                    'D' => Duration::days(amount),
+                    'H' => Duration::hours(amount),
+                    'M' => Duration::minutes(amount),
+                    _ => Duration::seconds(amount),
+                };
+            number.clear();
+            found = true;
+        }
+        if !number.is_empty() {
+            return None;
+        }
+    }
+
+    found.then_some(duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("PT14H"), Some(Duration::hours(14)));
+        assert_eq!(
+            parse_duration("P1DT1H30M"),
+            Some(Duration::days(1) + Duration::hours(1) + Duration::minutes(30))
+        );
+        assert_eq!(parse_duration("P2W"), Some(Duration::weeks(2)));
+        assert_eq!(parse_duration("PT45S"), Some(Duration::seconds(45)));
+        assert_eq!(parse_duration("P"), None);
+        assert_eq!(parse_duration("PT"), None);
+        assert_eq!(parse_duration("14H"), None);
+        assert_eq!(parse_duration("P1Y"), None);
+        assert_eq!(parse_duration("PT1H30"), None);
+    }
+
+    #[test]
+    fn test_schedule_contains() {
+        // Weekdays from 08:00 until 20:00 UTC
+        let schedule: Schedule =
+            "DTSTART:20231002T080000Z;DURATION:PT12H\nRRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR"
+                .parse()
+                .unwrap();
+
+        // Wednesday
+        let start = Utc.with_ymd_and_hms(2023, 10, 11, 9, 0, 0).unwrap();
+        assert!(schedule.contains(start, start + Duration::minutes(30)));
+        assert!(!schedule.contains(start, start + Duration::hours(12)));
+
+        // Wednesday night and Saturday
+        let start = Utc.with_ymd_and_hms(2023, 10, 11, 21, 0, 0).unwrap();
+        assert!(!schedule.contains(start, start + Duration::minutes(30)));
+        let start = Utc.with_ymd_and_hms(2023, 10, 14, 9, 0, 0).unwrap();
+        assert!(!schedule.contains(start, start + Duration::minutes(30)));
+    }
+
+    #[test]
+    fn test_schedule_invalid() {
+        assert!("RRULE:FREQ=DAILY".parse::<Schedule>().is_err());
+        assert!("DTSTART:20231002T080000Z;DURATION:12H\nRRULE:FREQ=DAILY"
+            .parse::<Schedule>()
+            .is_err());
+    }
+}
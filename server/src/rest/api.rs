@@ -4,16 +4,52 @@
 pub mod rest_types {
     include!("../../../openapi/types.rs");
 }
+pub mod audit;
+pub mod catalog;
+pub mod degraded;
+pub mod deletions;
+pub mod dependents;
+pub mod events;
+pub mod health;
+pub mod matching;
+pub mod metrics;
+pub mod occupancy;
+pub mod registration;
+pub mod reload;
+pub mod request_id;
+pub mod reservations;
+pub mod summary;
+pub mod telemetry;
+pub mod trace;
 use std::str::FromStr;
 
 pub use rest_types::*;
 
-use axum::{extract::Path, Extension, Json};
+use axum::{
+    extract::{Path, Query},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
 use hyper::StatusCode;
 use svc_storage_client_grpc::prelude::*;
 
-use super::structs::{Aircraft, AssetGroup, Operator, Vertipad, Vertiport};
-use crate::grpc::client::GrpcClients;
+use super::structs::{Aircraft, AssetGroup, AssetStatus, Operator, Vertipad, Vertiport};
+use crate::audit::{
+    changed_fields, created_changes, masked_changes, AuditAction, AuditContext, AuditLog,
+};
+use crate::cache::AssetCache;
+use crate::catalog::VehicleCatalog;
+use crate::deletions::DeletionStore;
+use crate::events::{AssetAction, AssetKind, ChangeNotifier};
+use crate::registration::RegistrationValidators;
+use crate::store::{AssetFilter, AssetStore, SharedStore};
+use crate::telemetry::TelemetryStore;
+use audit::data_as_of;
+use deletions::{check_not_removed, is_removed, load_not_removed, restore, soft_delete};
+use dependents::{search_hangared_vehicles, search_vertiport_vertipads, VertiportRemoval};
+use registration::check_identifiers;
+use tonic::Code;
 use uuid::Uuid;
 
 //===========================================================
@@ -25,72 +61,37 @@ fn is_uuid(s: &str) -> bool {
     uuid::Uuid::try_parse(s).is_ok()
 }
 
-//===========================================================
-// REST API Implementations
-//===========================================================
-/// Health check for load balancing
-#[utoipa::path(
-    get,
-    path = "/health",
-    tag = "svc-assets",
-    responses(
-        (status = 200, description = "Service is healthy, all dependencies running."),
-        (status = 503, description = "Service is unhealthy, one or more dependencies unavailable.")
-    )
-)]
-pub async fn health_check(
-    Extension(grpc_clients): Extension<GrpcClients>,
-) -> Result<(), StatusCode> {
-    rest_debug!("(health_check) entry.");
-
-    let mut ok = true;
-
-    if grpc_clients
-        .storage
-        .vertiport
-        .is_ready(ReadyRequest {})
-        .await
-        .is_err()
-    {
-        let error_msg = "svc-storage vertiport unavailable.".to_string();
-        rest_error!("(health_check) {}.", &error_msg);
-        ok = false;
-    }
-    if grpc_clients
-        .storage
-        .vertipad
-        .is_ready(ReadyRequest {})
-        .await
-        .is_err()
-    {
-        let error_msg = "svc-storage vertipad unavailable.".to_string();
-        rest_error!("(health_check) {}.", &error_msg);
-        ok = false;
-    }
-    if grpc_clients
-        .storage
-        .vehicle
-        .is_ready(ReadyRequest {})
-        .await
-        .is_err()
-    {
-        let error_msg = "svc-storage vehicle unavailable.".to_string();
-        rest_error!("(health_check) {}.", &error_msg);
-        ok = false;
+/// Use the manufacturer and performance data of the aircraft's vehicle
+/// model, if the model is in the catalog.
+fn apply_vehicle_model(catalog: &VehicleCatalog, aircraft: &mut Aircraft) {
+    match catalog.get(&aircraft.vehicle_model_id) {
+        Some(model) => aircraft.apply_vehicle_model(&model),
+        None => rest_debug!(
+            "(apply_vehicle_model) vehicle model [{}] of aircraft [{}] not in catalog.",
+            aircraft.vehicle_model_id,
+            aircraft.basics.id
+        ),
     }
+}
 
-    match ok {
-        true => {
-            rest_debug!("(health_check) healthy, all dependencies running.");
-            Ok(())
-        }
-        false => {
-            rest_error!("(health_check) unhealthy, 1+ dependencies down.");
-            Err(StatusCode::SERVICE_UNAVAILABLE)
-        }
+/// Downgrade an available aircraft to the status derived from its
+/// telemetry, if any, so aircraft grounded by their telemetry are never
+/// reported available.
+///
+/// The stored status is kept if the aircraft is not available, e.g. while
+/// grounded for maintenance, whatever its telemetry reports.
+fn apply_telemetry_status(telemetry: &TelemetryStore, aircraft: &mut Aircraft) {
+    if aircraft.basics.status != AssetStatus::Available {
+        return;
+    }
+    if let Some(status) = telemetry.status(&aircraft.basics.id) {
+        aircraft.basics.status = status;
     }
 }
 
+//===========================================================
+// REST API Implementations
+//===========================================================
 /// Get info about an operator by id.
 #[utoipa::path(
     get,
@@ -107,7 +108,7 @@ pub async fn health_check(
     )
 )]
 pub async fn get_operator(
-    Extension(mut _grpc_clients): Extension<GrpcClients>,
+    Extension(_store): Extension<SharedStore>,
     Path(operator_id): Path<String>,
 ) -> Result<Json<Operator>, (StatusCode, String)> {
     rest_info!("(get_operator) {}", operator_id);
@@ -136,14 +137,16 @@ pub async fn get_operator(
 )]
 /// Get all aircraft from the database.
 pub async fn get_all_aircraft(
-    Extension(grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
+    Extension(telemetry): Extension<TelemetryStore>,
+    Extension(catalog): Extension<VehicleCatalog>,
 ) -> Result<Json<Vec<Aircraft>>, (StatusCode, String)> {
     rest_info!("(get_all_aircraft) entry.");
-    let filter = AdvancedSearchFilter::search_is_not_null(String::from("deleted_at"));
+    // Removed assets are hidden until they are restored or purged
+    let filter = AssetFilter::is_null("deleted_at");
 
-    let vehicle_client = grpc_clients.storage.vehicle;
-    let mut vehicles = match vehicle_client.search(filter.clone()).await {
-        Ok(response) => response.into_inner().list,
+    let mut vehicles = match store.vehicles().search(&filter).await {
+        Ok(vehicles) => vehicles,
         Err(e) => {
             let error_msg = "could not retrieve vehicles.".to_string();
             rest_error!("(get_all_aircraft) {}: {}.", error_msg, e);
@@ -154,7 +157,7 @@ pub async fn get_all_aircraft(
     let mut assets = Vec::new();
 
     for vehicle in vehicles.drain(..) {
-        let aircraft: Aircraft = match vehicle.try_into() {
+        let mut aircraft: Aircraft = match vehicle.try_into() {
             Ok(object) => object,
             Err(_) => {
                 let error_msg = "could not convert VehicleObject to Aircraft.".to_string();
@@ -162,6 +165,8 @@ pub async fn get_all_aircraft(
                 return Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg));
             }
         };
+        apply_vehicle_model(&catalog, &mut aircraft);
+        apply_telemetry_status(&telemetry, &mut aircraft);
         assets.push(aircraft);
     }
 
@@ -179,14 +184,14 @@ pub async fn get_all_aircraft(
 )]
 /// Get all vertiports from the database.
 pub async fn get_all_vertiports(
-    Extension(grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
 ) -> Result<Json<Vec<Vertiport>>, (StatusCode, String)> {
     rest_info!("(get_all_vertiports) entry.");
-    let filter = AdvancedSearchFilter::search_is_not_null(String::from("deleted_at"));
+    // Removed assets are hidden until they are restored or purged
+    let filter = AssetFilter::is_null("deleted_at");
 
-    let vertiport_client = grpc_clients.storage.vertiport;
-    let mut vertiports = match vertiport_client.search(filter.clone()).await {
-        Ok(response) => response.into_inner().list,
+    let mut vertiports = match store.vertiports().search(&filter).await {
+        Ok(vertiports) => vertiports,
         Err(e) => {
             let error_msg = "could not retrieve vertiports.".to_string();
             rest_error!("(get_all_vertiports) {}: {}.", error_msg, e);
@@ -222,14 +227,14 @@ pub async fn get_all_vertiports(
 )]
 /// Get all vertipads from the database.
 pub async fn get_all_vertipads(
-    Extension(grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
 ) -> Result<Json<Vec<Vertipad>>, (StatusCode, String)> {
     rest_info!("(get_all_vertipads) entry.");
-    let filter = AdvancedSearchFilter::search_is_not_null(String::from("deleted_at"));
+    // Removed assets are hidden until they are restored or purged
+    let filter = AssetFilter::is_null("deleted_at");
 
-    let vertipad_client = grpc_clients.storage.vertipad;
-    let mut vertipads = match vertipad_client.search(filter.clone()).await {
-        Ok(response) => response.into_inner().list,
+    let mut vertipads = match store.vertipads().search(&filter).await {
+        Ok(vertipads) => vertipads,
         Err(e) => {
             let error_msg = "could not retrieve vertipads.".to_string();
             rest_error!("(get_all_vertipads) {}: {}.", error_msg, e);
@@ -273,7 +278,7 @@ pub async fn get_all_vertipads(
 )]
 /// Get all assets belonging to an operator.
 pub async fn get_all_assets_by_operator(
-    Extension(mut _grpc_clients): Extension<GrpcClients>,
+    Extension(_store): Extension<SharedStore>,
     Path(operator_id): Path<String>,
 ) -> Result<Json<Vec<Uuid>>, (StatusCode, String)> {
     rest_info!("(get_all_assets_by_operator) {}", operator_id);
@@ -336,7 +341,7 @@ pub async fn get_all_assets_by_operator(
     )
 )]
 pub async fn get_all_grouped_assets(
-    Extension(mut _grpc_clients): Extension<GrpcClients>,
+    Extension(_store): Extension<SharedStore>,
     Path(operator_id): Path<String>,
 ) -> Result<Json<Vec<Uuid>>, (StatusCode, String)> {
     rest_info!("(get_all_grouped_assets) {}", operator_id);
@@ -371,7 +376,7 @@ pub async fn get_all_grouped_assets(
     )
 )]
 pub async fn get_all_grouped_assets_delegated_to(
-    Extension(mut _grpc_clients): Extension<GrpcClients>,
+    Extension(_store): Extension<SharedStore>,
     Path(operator_id): Path<String>,
 ) -> Result<Json<Vec<Uuid>>, (StatusCode, String)> {
     rest_info!("(get_all_grouped_assets_delegated_to) {}", operator_id);
@@ -406,7 +411,7 @@ pub async fn get_all_grouped_assets_delegated_to(
     )
 )]
 pub async fn get_all_grouped_assets_delegated_from(
-    Extension(mut _grpc_clients): Extension<GrpcClients>,
+    Extension(_store): Extension<SharedStore>,
     Path(operator_id): Path<String>,
 ) -> Result<Json<Vec<Uuid>>, (StatusCode, String)> {
     rest_info!("(get_all_grouped_assets_delegated_from) {}", operator_id);
@@ -430,6 +435,10 @@ pub async fn get_all_grouped_assets_delegated_from(
 //-----------------------------------------------------------
 
 /// Get an [`Aircraft`] by its id.
+///
+/// With `as_of`, the aircraft is returned as it was at that time,
+/// reconstructed from its change history. Removed aircraft can only be
+/// requested this way.
 #[utoipa::path(
     get,
     path = "/assets/aircraft/{id}",
@@ -437,32 +446,57 @@ pub async fn get_all_grouped_assets_delegated_from(
     responses(
         (status = 200, description = "Aircraft {id} found from database", body = Aircraft),
         (status = 404, description = "Aircraft not found in database"),
-        (status = 400, description = "Invalid aircraft id"),
+        (status = 400, description = "Invalid aircraft id or as_of time"),
+        (status = 409, description = "History of aircraft {id} not available at as_of time"),
         (status = 503, description = "Could not connect to other microservice dependencies")
     ),
     params(
         ("id" = String, Path, description = "Aircraft id"),
+        GetAssetParams
     )
 )]
 pub async fn get_aircraft_by_id(
-    Extension(grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
+    Extension(audit): Extension<AuditLog>,
+    Extension(cache): Extension<AssetCache>,
+    Extension(telemetry): Extension<TelemetryStore>,
+    Extension(catalog): Extension<VehicleCatalog>,
     Path(aircraft_id): Path<String>,
+    Query(params): Query<GetAssetParams>,
 ) -> Result<Json<Aircraft>, (StatusCode, String)> {
-    rest_info!("(get_aircraft_by_id) entry [{}].", aircraft_id);
+    rest_info!("(get_aircraft_by_id) entry [{}] {:?}.", aircraft_id, params);
     if !is_uuid(&aircraft_id) {
         return Err((StatusCode::BAD_REQUEST, "Invalid aircraft id".to_string()));
     }
-
-    let client = grpc_clients.storage.vehicle;
-    match client
-        .get_by_id(Id {
-            id: aircraft_id.clone(),
-        })
-        .await
-    {
-        Ok(response) => {
-            let vehicle = response.into_inner();
-            let aircraft: Aircraft = match vehicle.try_into() {
+    // Removed assets are only requested with as_of, which bypasses the
+    // cache so they are never cached
+    let (removed, result) = match params.as_of {
+        None => {
+            let load = store.vehicles().get_by_id(&aircraft_id);
+            let load = load_not_removed(store.as_ref(), AssetKind::Aircraft, &aircraft_id, load);
+            (
+                false,
+                cache.vehicles().get_or_load(&aircraft_id, load).await,
+            )
+        }
+        Some(_) => {
+            let removed = is_removed(store.as_ref(), AssetKind::Aircraft, &aircraft_id).await?;
+            (removed, store.vehicles().get_by_id(&aircraft_id).await)
+        }
+    };
+    match result {
+        Ok(mut vehicle) => {
+            if let Some(as_of) = &params.as_of {
+                vehicle.data = data_as_of(
+                    &audit,
+                    removed,
+                    AssetKind::Aircraft,
+                    &aircraft_id,
+                    vehicle.data,
+                    as_of,
+                )?;
+            }
+            let mut aircraft: Aircraft = match vehicle.try_into() {
                 Ok(aircraft) => {
                     rest_info!("(get_aircraft_by_id) Aircraft found: {}", aircraft_id);
                     aircraft
@@ -473,8 +507,16 @@ pub async fn get_aircraft_by_id(
                     return Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg));
                 }
             };
+            apply_vehicle_model(&catalog, &mut aircraft);
+            // Telemetry only describes the current state of the aircraft
+            if params.as_of.is_none() {
+                apply_telemetry_status(&telemetry, &mut aircraft);
+            }
             Ok(Json(aircraft))
         }
+        Err(e) if e.code() == Code::NotFound => {
+            Err((StatusCode::NOT_FOUND, "Aircraft not found".to_string()))
+        }
         Err(e) => {
             let error_msg = format!("Error getting aircraft from storage: {}", e);
             rest_error!("(get_aircraft_by_id) {}", &error_msg);
@@ -484,6 +526,10 @@ pub async fn get_aircraft_by_id(
 }
 
 /// Get an [`Vertipad`] by its id.
+///
+/// With `as_of`, the vertipad is returned as it was at that time,
+/// reconstructed from its change history. Removed vertipads can only be
+/// requested this way.
 #[utoipa::path(
     get,
     path = "/assets/vertipads/{id}",
@@ -491,31 +537,54 @@ pub async fn get_aircraft_by_id(
     responses(
         (status = 200, description = "Vertipad {id} found from database", body = Vertipad),
         (status = 404, description = "Vertipad not found in database"),
-        (status = 400, description = "Invalid vertipad id"),
+        (status = 400, description = "Invalid vertipad id or as_of time"),
+        (status = 409, description = "History of vertipad {id} not available at as_of time"),
         (status = 503, description = "Could not connect to other microservice dependencies")
     ),
     params(
         ("id" = String, Path, description = "Vertipad id"),
+        GetAssetParams
     )
 )]
 pub async fn get_vertipad_by_id(
-    Extension(grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
+    Extension(audit): Extension<AuditLog>,
+    Extension(cache): Extension<AssetCache>,
     Path(vertipad_id): Path<String>,
+    Query(params): Query<GetAssetParams>,
 ) -> Result<Json<Vertipad>, (StatusCode, String)> {
-    rest_info!("(get_vertipad_by_id) entry [{}].", vertipad_id);
+    rest_info!("(get_vertipad_by_id) entry [{}] {:?}.", vertipad_id, params);
     if !is_uuid(&vertipad_id) {
         return Err((StatusCode::BAD_REQUEST, "Invalid vertipad id".to_string()));
     }
-
-    let client = grpc_clients.storage.vertipad;
-    match client
-        .get_by_id(Id {
-            id: vertipad_id.clone(),
-        })
-        .await
-    {
-        Ok(response) => {
-            let vertipad = response.into_inner();
+    // Removed assets are only requested with as_of, which bypasses the
+    // cache so they are never cached
+    let (removed, result) = match params.as_of {
+        None => {
+            let load = store.vertipads().get_by_id(&vertipad_id);
+            let load = load_not_removed(store.as_ref(), AssetKind::Vertipad, &vertipad_id, load);
+            (
+                false,
+                cache.vertipads().get_or_load(&vertipad_id, load).await,
+            )
+        }
+        Some(_) => {
+            let removed = is_removed(store.as_ref(), AssetKind::Vertipad, &vertipad_id).await?;
+            (removed, store.vertipads().get_by_id(&vertipad_id).await)
+        }
+    };
+    match result {
+        Ok(mut vertipad) => {
+            if let Some(as_of) = &params.as_of {
+                vertipad.data = data_as_of(
+                    &audit,
+                    removed,
+                    AssetKind::Vertipad,
+                    &vertipad_id,
+                    vertipad.data,
+                    as_of,
+                )?;
+            }
             let vertipad: Vertipad = match vertipad.try_into() {
                 Ok(vertipad) => {
                     rest_info!("(get_vertipad_by_id) Vertipad found: {}", vertipad_id);
@@ -529,6 +598,9 @@ pub async fn get_vertipad_by_id(
             };
             Ok(Json(vertipad))
         }
+        Err(e) if e.code() == Code::NotFound => {
+            Err((StatusCode::NOT_FOUND, "Vertipad not found".to_string()))
+        }
         Err(e) => {
             let error_msg = format!("Error getting vertipad from storage: {}", e);
             rest_error!("(get_vertipad_by_id) {}", &error_msg);
@@ -538,6 +610,10 @@ pub async fn get_vertipad_by_id(
 }
 
 /// Get an [`Vertiport`] by its id.
+///
+/// With `as_of`, the vertiport is returned as it was at that time,
+/// reconstructed from its change history. Removed vertiports can only be
+/// requested this way.
 #[utoipa::path(
     get,
     path = "/assets/vertiports/{id}",
@@ -545,31 +621,58 @@ pub async fn get_vertipad_by_id(
     responses(
         (status = 200, description = "Vertiport {id} found from database", body = Vertiport),
         (status = 404, description = "Vertiport not found in database"),
-        (status = 400, description = "Invalid vertiport id"),
+        (status = 400, description = "Invalid vertiport id or as_of time"),
+        (status = 409, description = "History of vertiport {id} not available at as_of time"),
         (status = 503, description = "Could not connect to other microservice dependencies")
     ),
     params(
         ("id" = String, Path, description = "Vertiport id"),
+        GetAssetParams
     )
 )]
 pub async fn get_vertiport_by_id(
-    Extension(grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
+    Extension(audit): Extension<AuditLog>,
+    Extension(cache): Extension<AssetCache>,
     Path(vertiport_id): Path<String>,
+    Query(params): Query<GetAssetParams>,
 ) -> Result<Json<Vertiport>, (StatusCode, String)> {
-    rest_info!("(get_vertiport_by_id) entry [{}].", vertiport_id);
+    rest_info!(
+        "(get_vertiport_by_id) entry [{}] {:?}.",
+        vertiport_id,
+        params
+    );
     if !is_uuid(&vertiport_id) {
         return Err((StatusCode::BAD_REQUEST, "Invalid vertiport id".to_string()));
     }
-
-    let client = grpc_clients.storage.vertiport;
-    match client
-        .get_by_id(Id {
-            id: vertiport_id.clone(),
-        })
-        .await
-    {
-        Ok(response) => {
-            let vertiport = response.into_inner();
+    // Removed assets are only requested with as_of, which bypasses the
+    // cache so they are never cached
+    let (removed, result) = match params.as_of {
+        None => {
+            let load = store.vertiports().get_by_id(&vertiport_id);
+            let load = load_not_removed(store.as_ref(), AssetKind::Vertiport, &vertiport_id, load);
+            (
+                false,
+                cache.vertiports().get_or_load(&vertiport_id, load).await,
+            )
+        }
+        Some(_) => {
+            let removed = is_removed(store.as_ref(), AssetKind::Vertiport, &vertiport_id).await?;
+            (removed, store.vertiports().get_by_id(&vertiport_id).await)
+        }
+    };
+    match result {
+        Ok(mut vertiport) => {
+            if let Some(as_of) = &params.as_of {
+                vertiport.data = data_as_of(
+                    &audit,
+                    removed,
+                    AssetKind::Vertiport,
+                    &vertiport_id,
+                    vertiport.data,
+                    as_of,
+                )?;
+            }
             let vertiport: Vertiport = match vertiport.try_into() {
                 Ok(vertiport) => {
                     rest_info!("(get_vertiport_by_id) Vertiport found: {}", vertiport_id);
@@ -583,6 +686,9 @@ pub async fn get_vertiport_by_id(
             };
             Ok(Json(vertiport))
         }
+        Err(e) if e.code() == Code::NotFound => {
+            Err((StatusCode::NOT_FOUND, "Vertiport not found".to_string()))
+        }
         Err(e) => {
             let error_msg = format!("Error getting vertiport from storage: {}", e);
             rest_error!("(get_vertiport_by_id) {}", &error_msg);
@@ -607,7 +713,7 @@ pub async fn get_vertiport_by_id(
     )
 )]
 pub async fn get_asset_group_by_id(
-    Extension(mut _grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
     Path(asset_group_id): Path<String>,
 ) -> Result<Json<AssetGroup>, (StatusCode, String)> {
     rest_info!("(get_asset_group_by_id) {}", asset_group_id);
@@ -617,17 +723,18 @@ pub async fn get_asset_group_by_id(
             "Invalid asset group id".to_string(),
         ));
     }
-    // Get Client
-    // let _client_option = grpc_clients.storage.get_client().await;
-    // if client_option.is_none() {
-    //     let error_msg = "svc-storage unavailable.".to_string();
-    //     rest_error!("(get_asset_group_by_id) {}", &error_msg);
-    //     return Err((StatusCode::SERVICE_UNAVAILABLE, error_msg));
-    // }
-    // let mut client = client_option.unwrap();
 
-    // TODO(R4)
-    Ok(Json(AssetGroup::random()))
+    match store.get_group(&asset_group_id).await {
+        Ok(asset_group) => Ok(Json(asset_group)),
+        Err(e) if e.code() == Code::NotFound => {
+            Err((StatusCode::NOT_FOUND, "Asset group not found".to_string()))
+        }
+        Err(e) => {
+            let error_msg = format!("Error getting asset group from storage: {}", e);
+            rest_error!("(get_asset_group_by_id) {}", &error_msg);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg))
+        }
+    }
 }
 
 //-----------------------------------------------------------
@@ -635,6 +742,10 @@ pub async fn get_asset_group_by_id(
 //-----------------------------------------------------------
 
 /// Register an [`Aircraft`] in the database.
+///
+/// The registration number must be a valid registration mark of a known
+/// aviation authority. The registration and serial number must not be used
+/// by any other aircraft.
 #[utoipa::path(
     post,
     path = "/assets/aircraft",
@@ -642,36 +753,45 @@ pub async fn get_asset_group_by_id(
     request_body=vehicle::Data,
     responses(
         (status = 200, description = "Aircraft registered in database; a UUID is returned", body = String),
+        (status = 400, description = "Invalid registration number"),
+        (status = 409, description = "Registration or serial number used by another aircraft"),
         (status = 422, description = "Request body is invalid format"),
         (status = 503, description = "Could not connect to other microservice dependencies")
     )
 )]
 pub async fn register_aircraft(
-    Extension(grpc_clients): Extension<GrpcClients>,
-    Json(payload): Json<vehicle::Data>,
+    Extension(store): Extension<SharedStore>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(audit): Extension<AuditLog>,
+    Extension(registration): Extension<RegistrationValidators>,
+    headers: HeaderMap,
+    Json(mut payload): Json<vehicle::Data>,
 ) -> Result<String, (StatusCode, String)> {
     rest_info!("(register_aircraft) entry.");
     rest_debug!("(register_aircraft) Payload: {:?}", &payload);
+    check_identifiers(store.as_ref(), &registration, None, &mut payload, None).await?;
 
-    let client = grpc_clients.storage.vehicle;
-
-    match client.insert(payload).await {
-        Ok(res) => {
+    match store.vehicles().insert(payload.clone()).await {
+        Ok(vehicle_obj) => {
             rest_info!("(register_aircraft) registration success.");
-            rest_debug!("(register_aircraft) {:?}", res);
-            let vehicle_obj = res.into_inner().object;
-            if let Some(vehicle_obj) = vehicle_obj {
-                rest_debug!(
-                    "(register_aircraft) got new aircraft id: {}",
-                    vehicle_obj.id
-                );
-                Ok(vehicle_obj.id)
-            } else {
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "(register_aircraft) could not insert vehicle.".to_string(),
-                ))
-            }
+            rest_debug!(
+                "(register_aircraft) got new aircraft id: {}",
+                vehicle_obj.id
+            );
+            notifier.emit(
+                AssetKind::Aircraft,
+                AssetAction::Created,
+                &vehicle_obj.id,
+                serde_json::to_value(&payload).ok(),
+            );
+            audit.record(
+                AssetKind::Aircraft,
+                &vehicle_obj.id,
+                AuditAction::Created,
+                &AuditContext::from_headers(&headers),
+                created_changes(&payload),
+            );
+            Ok(vehicle_obj.id)
         }
         Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
     }
@@ -690,31 +810,36 @@ pub async fn register_aircraft(
     )
 )]
 pub async fn register_vertiport(
-    Extension(grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(audit): Extension<AuditLog>,
+    headers: HeaderMap,
     Json(payload): Json<vertiport::Data>,
 ) -> Result<String, (StatusCode, String)> {
     rest_info!("(register_vertiport) entry.");
     rest_debug!("(register_vertiport) Payload: {:?}", &payload);
 
-    let client = grpc_clients.storage.vertiport;
-
-    match client.insert(payload).await {
-        Ok(res) => {
+    match store.vertiports().insert(payload.clone()).await {
+        Ok(vertiport_obj) => {
             rest_info!("(register_vertiport) registration success.");
-            rest_debug!("(register_vertiport) {:?}", res);
-            let vertiport_obj = res.into_inner().object;
-            if let Some(vertiport_obj) = vertiport_obj {
-                rest_debug!(
-                    "(register_vertiport) got new vertiport id: {}",
-                    vertiport_obj.id
-                );
-                Ok(vertiport_obj.id)
-            } else {
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "(register_vertiport) could not insert vertiport.".to_string(),
-                ))
-            }
+            rest_debug!(
+                "(register_vertiport) got new vertiport id: {}",
+                vertiport_obj.id
+            );
+            notifier.emit(
+                AssetKind::Vertiport,
+                AssetAction::Created,
+                &vertiport_obj.id,
+                serde_json::to_value(&payload).ok(),
+            );
+            audit.record(
+                AssetKind::Vertiport,
+                &vertiport_obj.id,
+                AuditAction::Created,
+                &AuditContext::from_headers(&headers),
+                created_changes(&payload),
+            );
+            Ok(vertiport_obj.id)
         }
         Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
     }
@@ -730,36 +855,45 @@ pub async fn register_vertiport(
     request_body=vertipad::Data,
     responses(
         (status = 200, description = "Vertipad registered in database; a UUID is returned", body = String),
+        (status = 404, description = "Vertiport of the vertipad has been removed"),
         (status = 422, description = "Request body is invalid format"),
         (status = 503, description = "Could not connect to other microservice dependencies")
     )
 )]
 pub async fn register_vertipad(
-    Extension(grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(audit): Extension<AuditLog>,
+    headers: HeaderMap,
     Json(payload): Json<vertipad::Data>,
 ) -> Result<String, (StatusCode, String)> {
     rest_info!("(register_vertipad) entry.");
     rest_debug!("(register_vertipad) Payload: {:?}", &payload);
+    if is_uuid(&payload.vertiport_id) {
+        check_not_removed(store.as_ref(), AssetKind::Vertiport, &payload.vertiport_id).await?;
+    }
 
-    let client = grpc_clients.storage.vertipad;
-
-    match client.insert(payload).await {
-        Ok(res) => {
+    match store.vertipads().insert(payload.clone()).await {
+        Ok(vertipad_obj) => {
             rest_info!("(register_vertipad) registration success.");
-            rest_debug!("(register_vertipad) {:?}", res);
-            let vertipad_obj = res.into_inner().object;
-            if let Some(vertipad_obj) = vertipad_obj {
-                rest_debug!(
-                    "(register_vertipad) got new vertipad id: {}",
-                    vertipad_obj.id
-                );
-                Ok(vertipad_obj.id)
-            } else {
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "(register_vertipad) could not insert vertipad.".to_string(),
-                ))
-            }
+            rest_debug!(
+                "(register_vertipad) got new vertipad id: {}",
+                vertipad_obj.id
+            );
+            notifier.emit(
+                AssetKind::Vertipad,
+                AssetAction::Created,
+                &vertipad_obj.id,
+                serde_json::to_value(&payload).ok(),
+            );
+            audit.record(
+                AssetKind::Vertipad,
+                &vertipad_obj.id,
+                AuditAction::Created,
+                &AuditContext::from_headers(&headers),
+                created_changes(&payload),
+            );
+            Ok(vertipad_obj.id)
         }
         Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
     }
@@ -782,12 +916,15 @@ pub async fn register_vertipad(
     )
 )]
 pub async fn register_asset_group(
-    Extension(mut _grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(audit): Extension<AuditLog>,
+    headers: HeaderMap,
     Json(payload): Json<RegisterAssetGroupPayload>,
 ) -> Result<String, (StatusCode, String)> {
     rest_info!("(register_asset_group) with payload: {:?}", &payload);
 
-    let _asset_group = AssetGroup {
+    let asset_group = AssetGroup {
         id: Uuid::new_v4().to_string(),
         name: payload.name,
         owner: payload.owner,
@@ -796,18 +933,28 @@ pub async fn register_asset_group(
         delegatee: None,
         assets: payload.assets,
     };
-
-    // Get Client
-    // let _client_option = grpc_clients.storage.get_client().await;
-    // if client_option.is_none() {
-    //     let error_msg = "svc-storage unavailable.".to_string();
-    //     rest_error!("(get_asset_group_by_id) {}", &error_msg);
-    //     return Err((StatusCode::SERVICE_UNAVAILABLE, error_msg));
-    // }
-    // let mut client = client_option.unwrap();
-
-    // TODO(R4)
-    Ok(_asset_group.id)
+    let id = asset_group.id.clone();
+    let changes = created_changes(&asset_group);
+    let event_data = serde_json::to_value(&asset_group).ok();
+
+    match store.insert_group(asset_group).await {
+        Ok(()) => {
+            notifier.emit(AssetKind::AssetGroup, AssetAction::Created, &id, event_data);
+            audit.record(
+                AssetKind::AssetGroup,
+                &id,
+                AuditAction::Created,
+                &AuditContext::from_headers(&headers),
+                changes,
+            );
+            Ok(id)
+        }
+        Err(e) => {
+            let error_msg = format!("could not insert asset group: {}", e);
+            rest_error!("(register_asset_group) {}", &error_msg);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg))
+        }
+    }
 }
 
 //-----------------------------------------------------------
@@ -816,7 +963,10 @@ pub async fn register_asset_group(
 
 /// Update/modify an [`Aircraft`] in the database.
 ///
-/// This will update the aircraft's information.
+/// This will update the aircraft's information. An updated registration
+/// number must be a valid registration mark of a known aviation authority.
+/// Updated registration and serial numbers must not be used by any other
+/// aircraft.
 #[utoipa::path(
     put,
     path = "/assets/aircraft",
@@ -824,96 +974,128 @@ pub async fn register_asset_group(
     request_body=UpdateAircraftPayload,
     responses(
         (status = 200, description = "Aircraft updated in database; a UUID is returned", body = String),
+        (status = 400, description = "Invalid registration number"),
+        (status = 404, description = "Aircraft not found in database or removed"),
+        (status = 409, description = "Registration or serial number used by another aircraft"),
         (status = 422, description = "Request body is invalid format"),
         (status = 503, description = "Could not connect to other microservice dependencies")
     )
 )]
 pub async fn update_aircraft(
-    Extension(grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(audit): Extension<AuditLog>,
+    Extension(cache): Extension<AssetCache>,
+    Extension(registration): Extension<RegistrationValidators>,
+    headers: HeaderMap,
     Json(payload): Json<UpdateAircraftPayload>,
 ) -> Result<String, (StatusCode, String)> {
     rest_info!("(update_aircraft) entry [{}].", payload.id);
     rest_debug!("(update_aircraft) Payload: {:?}", &payload);
+    let event_data = payload.clone();
 
     let vehicle_id = payload.id.clone();
-    let client = grpc_clients.storage.vehicle;
+    check_not_removed(store.as_ref(), AssetKind::Aircraft, &vehicle_id).await?;
 
-    let response = match client
-        .get_by_id(Id {
-            id: vehicle_id.clone(),
-        })
-        .await
-    {
-        Ok(res) => {
-            rest_debug!("(update_aircraft) successfully got vehicle {:?}", res);
-            res
+    let object = match store.vehicles().get_by_id(&vehicle_id).await {
+        Ok(object) => {
+            rest_debug!("(update_aircraft) successfully got vehicle {:?}", object);
+            object
+        }
+        Err(e) if e.code() == Code::NotFound => {
+            return Err((StatusCode::NOT_FOUND, "Vehicle not found".to_string()));
         }
         Err(e) => {
             return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
         }
     };
 
-    let vehicle = match response.into_inner().data {
+    let vehicle = match object.data {
         Some(data) => data,
         None => {
             return Err((StatusCode::NOT_FOUND, "Vehicle not found".to_string()));
         }
     };
 
-    match client
-        .update(vehicle::UpdateObject {
-            id: vehicle_id.clone(),
-            data: Some(vehicle::Data {
-                hangar_id: payload.hangar_id,
-                hangar_bay_id: payload.hangar_bay_id,
-                vehicle_model_id: payload.vehicle_model_id.unwrap_or(vehicle.vehicle_model_id),
-                serial_number: payload.serial_number.unwrap_or(vehicle.serial_number),
-                registration_number: payload
-                    .registration_number
-                    .unwrap_or(vehicle.registration_number),
-                description: payload.description.unwrap_or(vehicle.description),
-                asset_group_id: payload.asset_group_id.unwrap_or(vehicle.asset_group_id),
-                schedule: payload.schedule.unwrap_or(vehicle.schedule),
-                last_maintenance: if let Some(last_maintenance) = payload.last_maintenance {
-                    match last_maintenance {
-                        Some(last_maintenance) => match Timestamp::from_str(&last_maintenance) {
-                            Ok(time_stamp) => Some(time_stamp),
-                            Err(e) => {
-                                rest_error!("(update_aircraft) {}", &e.to_string());
-                                return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
-                            }
-                        },
-                        None => None,
+    let previous = vehicle.clone();
+    let mut data = vehicle::Data {
+        hangar_id: payload.hangar_id,
+        hangar_bay_id: payload.hangar_bay_id,
+        vehicle_model_id: payload.vehicle_model_id.unwrap_or(vehicle.vehicle_model_id),
+        serial_number: payload.serial_number.unwrap_or(vehicle.serial_number),
+        registration_number: payload
+            .registration_number
+            .unwrap_or(vehicle.registration_number),
+        description: payload.description.unwrap_or(vehicle.description),
+        asset_group_id: payload.asset_group_id.unwrap_or(vehicle.asset_group_id),
+        schedule: payload.schedule.unwrap_or(vehicle.schedule),
+        last_maintenance: if let Some(last_maintenance) = payload.last_maintenance {
+            match last_maintenance {
+                Some(last_maintenance) => match Timestamp::from_str(&last_maintenance) {
+                    Ok(time_stamp) => Some(time_stamp),
+                    Err(e) => {
+                        rest_error!("(update_aircraft) {}", &e.to_string());
+                        return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
                     }
-                } else {
-                    vehicle.last_maintenance
                 },
-
-                next_maintenance: if let Some(next_maintenance) = payload.next_maintenance {
-                    match next_maintenance {
-                        Some(next_maintenance) => match Timestamp::from_str(&next_maintenance) {
-                            Ok(time_stamp) => Some(time_stamp),
-                            Err(e) => {
-                                rest_error!("(update_aircraft) {}", &e.to_string());
-                                return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
-                            }
-                        },
-                        None => None,
+                None => None,
+            }
+        } else {
+            vehicle.last_maintenance
+        },
+
+        next_maintenance: if let Some(next_maintenance) = payload.next_maintenance {
+            match next_maintenance {
+                Some(next_maintenance) => match Timestamp::from_str(&next_maintenance) {
+                    Ok(time_stamp) => Some(time_stamp),
+                    Err(e) => {
+                        rest_error!("(update_aircraft) {}", &e.to_string());
+                        return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
                     }
-                } else {
-                    vehicle.next_maintenance
                 },
-                created_at: None,
-                updated_at: None,
-            }),
-            mask: Some(FieldMask {
-                paths: payload.mask,
-            }),
-        })
-        .await
-    {
-        Ok(res) => {
-            rest_info!("(update_aircraft) successfully updated vehicle {:?}", res);
+                None => None,
+            }
+        } else {
+            vehicle.next_maintenance
+        },
+        created_at: None,
+        updated_at: None,
+    };
+    check_identifiers(
+        store.as_ref(),
+        &registration,
+        Some(&vehicle_id),
+        &mut data,
+        Some(&payload.mask),
+    )
+    .await?;
+    let changes = masked_changes(&previous, &data, &payload.mask);
+
+    let result = store
+        .vehicles()
+        .update(&vehicle_id, data, payload.mask)
+        .await;
+    // The update may have been applied even if the call failed
+    cache.invalidate(AssetKind::Aircraft, &vehicle_id);
+    match result {
+        Ok(()) => {
+            rest_info!(
+                "(update_aircraft) successfully updated vehicle {}",
+                vehicle_id
+            );
+            notifier.emit(
+                AssetKind::Aircraft,
+                AssetAction::Updated,
+                &vehicle_id,
+                serde_json::to_value(&event_data).ok(),
+            );
+            audit.record(
+                AssetKind::Aircraft,
+                &vehicle_id,
+                AuditAction::Updated,
+                &AuditContext::from_headers(&headers),
+                changes,
+            );
             Ok(vehicle_id.clone())
         }
         Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
@@ -931,62 +1113,78 @@ pub async fn update_aircraft(
     request_body=UpdateVertiportPayload,
     responses(
         (status = 200, description = "Vertiport updated in database; a UUID is returned", body = String),
+        (status = 404, description = "Vertiport not found in database or removed"),
         (status = 422, description = "Request body is invalid format"),
         (status = 503, description = "Could not connect to other microservice dependencies")
     )
 )]
 pub async fn update_vertiport(
-    Extension(grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(audit): Extension<AuditLog>,
+    Extension(cache): Extension<AssetCache>,
+    headers: HeaderMap,
     Json(payload): Json<UpdateVertiportPayload>,
 ) -> Result<String, (StatusCode, String)> {
     rest_info!("(update_vertiport) entry [{}].", payload.id);
     rest_debug!("(update_vertiport) Payload: {:?}", &payload);
+    let event_data = payload.clone();
+    check_not_removed(store.as_ref(), AssetKind::Vertiport, &payload.id).await?;
 
-    let client = grpc_clients.storage.vertiport;
-
-    let response = match client
-        .get_by_id(Id {
-            id: payload.id.clone(),
-        })
-        .await
-    {
-        Ok(res) => {
-            rest_info!("(update_vertiport) successfully got vertiport {:?}", res);
-            res
+    let object = match store.vertiports().get_by_id(&payload.id).await {
+        Ok(object) => {
+            rest_info!("(update_vertiport) successfully got vertiport {:?}", object);
+            object
+        }
+        Err(e) if e.code() == Code::NotFound => {
+            return Err((StatusCode::NOT_FOUND, "Vertiport not found".to_string()));
         }
         Err(e) => {
             return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
         }
     };
 
-    let vertiport = match response.into_inner().data {
+    let vertiport = match object.data {
         Some(data) => data,
         None => {
             return Err((StatusCode::NOT_FOUND, "Vertiport not found".to_string()));
         }
     };
 
-    match client
-        .update(vertiport::UpdateObject {
-            id: payload.id.clone(),
-            data: Some(vertiport::Data {
-                name: payload.name.unwrap_or(vertiport.name),
-                description: payload.description.unwrap_or(vertiport.description),
-                geo_location: payload.geo_location.or(vertiport.geo_location),
-                schedule: payload.schedule.unwrap_or(vertiport.schedule),
-                created_at: None,
-                updated_at: None,
-            }),
-            mask: Some(FieldMask {
-                paths: payload.mask,
-            }),
-        })
-        .await
-    {
-        Ok(res) => {
+    let previous = vertiport.clone();
+    let data = vertiport::Data {
+        name: payload.name.unwrap_or(vertiport.name),
+        description: payload.description.unwrap_or(vertiport.description),
+        geo_location: payload.geo_location.or(vertiport.geo_location),
+        schedule: payload.schedule.unwrap_or(vertiport.schedule),
+        created_at: None,
+        updated_at: None,
+    };
+    let changes = masked_changes(&previous, &data, &payload.mask);
+
+    let result = store
+        .vertiports()
+        .update(&payload.id, data, payload.mask)
+        .await;
+    cache.invalidate(AssetKind::Vertiport, &payload.id);
+    match result {
+        Ok(()) => {
             rest_info!(
-                "(update_vertiport) successfully updated vertiport {:?}",
-                res
+                "(update_vertiport) successfully updated vertiport {}",
+                payload.id
+            );
+            notifier.emit(
+                AssetKind::Vertiport,
+                AssetAction::Updated,
+                &payload.id,
+                serde_json::to_value(&event_data).ok(),
+            );
+            audit.record(
+                AssetKind::Vertiport,
+                &payload.id,
+                AuditAction::Updated,
+                &AuditContext::from_headers(&headers),
+                changes,
             );
             Ok(payload.id.clone())
         }
@@ -1002,62 +1200,87 @@ pub async fn update_vertiport(
     request_body=UpdateVertipadPayload,
     responses(
         (status = 200, description = "Vertipad updated in database; a UUID is returned", body = String),
+        (status = 400, description = "Occupied field can not be updated"),
+        (status = 404, description = "Vertipad not found in database or removed"),
         (status = 422, description = "Request body is invalid format"),
         (status = 503, description = "Could not connect to other microservice dependencies")
     )
 )]
 pub async fn update_vertipad(
-    Extension(grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(audit): Extension<AuditLog>,
+    Extension(cache): Extension<AssetCache>,
+    headers: HeaderMap,
     Json(payload): Json<UpdateVertipadPayload>,
 ) -> Result<String, (StatusCode, String)> {
     rest_info!("(update_vertipad) entry [{}].", payload.id);
     rest_debug!("(update_vertipad) Payload: {:?}", &payload);
+    if payload.mask.iter().any(|field| field == "occupied") {
+        let error_msg = "occupied can only be changed by occupying or releasing the vertipad.";
+        rest_warn!("(update_vertipad) {}", error_msg);
+        return Err((StatusCode::BAD_REQUEST, error_msg.to_string()));
+    }
+    let event_data = payload.clone();
+    check_not_removed(store.as_ref(), AssetKind::Vertipad, &payload.id).await?;
 
-    let client = grpc_clients.storage.vertipad;
-
-    let response = match client
-        .get_by_id(Id {
-            id: payload.id.clone(),
-        })
-        .await
-    {
-        Ok(res) => {
-            rest_info!("(update_vertipad) successfully got vertipad {:?}", res);
-            res
+    let object = match store.vertipads().get_by_id(&payload.id).await {
+        Ok(object) => {
+            rest_info!("(update_vertipad) successfully got vertipad {:?}", object);
+            object
+        }
+        Err(e) if e.code() == Code::NotFound => {
+            return Err((StatusCode::NOT_FOUND, "Vertipad not found".to_string()));
         }
         Err(e) => {
             return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
         }
     };
 
-    let vertipad = match response.into_inner().data {
+    let vertipad = match object.data {
         Some(data) => data,
         None => {
             return Err((StatusCode::NOT_FOUND, "Vertipad not found".to_string()));
         }
     };
 
-    match client
-        .update(vertipad::UpdateObject {
-            id: payload.id.clone(),
-            data: Some(vertipad::Data {
-                name: payload.name.unwrap_or(vertipad.name),
-                geo_location: payload.geo_location.or(vertipad.geo_location),
-                enabled: payload.enabled.unwrap_or(vertipad.enabled),
-                occupied: payload.occupied.unwrap_or(vertipad.occupied),
-                schedule: payload.schedule.unwrap_or(vertipad.schedule),
-                vertiport_id: payload.vertiport_id.unwrap_or(vertipad.vertiport_id),
-                created_at: None,
-                updated_at: None,
-            }),
-            mask: Some(FieldMask {
-                paths: payload.mask,
-            }),
-        })
-        .await
-    {
-        Ok(res) => {
-            rest_info!("(update_vertipad) successfully updated vertipad {:?}", res);
+    let previous = vertipad.clone();
+    let data = vertipad::Data {
+        name: payload.name.unwrap_or(vertipad.name),
+        geo_location: payload.geo_location.or(vertipad.geo_location),
+        enabled: payload.enabled.unwrap_or(vertipad.enabled),
+        occupied: payload.occupied.unwrap_or(vertipad.occupied),
+        schedule: payload.schedule.unwrap_or(vertipad.schedule),
+        vertiport_id: payload.vertiport_id.unwrap_or(vertipad.vertiport_id),
+        created_at: None,
+        updated_at: None,
+    };
+    let changes = masked_changes(&previous, &data, &payload.mask);
+
+    let result = store
+        .vertipads()
+        .update(&payload.id, data, payload.mask)
+        .await;
+    cache.invalidate(AssetKind::Vertipad, &payload.id);
+    match result {
+        Ok(()) => {
+            rest_info!(
+                "(update_vertipad) successfully updated vertipad {}",
+                payload.id
+            );
+            notifier.emit(
+                AssetKind::Vertipad,
+                AssetAction::Updated,
+                &payload.id,
+                serde_json::to_value(&event_data).ok(),
+            );
+            audit.record(
+                AssetKind::Vertipad,
+                &payload.id,
+                AuditAction::Updated,
+                &AuditContext::from_headers(&headers),
+                changes,
+            );
             Ok(payload.id.clone())
         }
         Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
@@ -1072,6 +1295,7 @@ pub async fn update_vertipad(
     request_body=AssetGroup,
     responses(
         (status = 200, description = "AssetGroup updated in database; a UUID is returned", body = String),
+        (status = 404, description = "AssetGroup not found in database"),
         (status = 422, description = "Request body is invalid format"),
         (status = 503, description = "Could not connect to other microservice dependencies")
     ),
@@ -1080,23 +1304,70 @@ pub async fn update_vertipad(
     )
 )]
 pub async fn update_asset_group(
-    Extension(mut _grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(audit): Extension<AuditLog>,
+    headers: HeaderMap,
     Json(payload): Json<AssetGroup>,
     Path(_id): Path<String>,
 ) -> Result<String, (StatusCode, String)> {
     rest_info!("(update_asset_group) with payload: {:?}", &payload);
 
-    // Get Client
-    // let _client_option = grpc_clients.storage.get_client().await;
-    // if client_option.is_none() {
-    //     let error_msg = "svc-storage unavailable.".to_string();
-    //     rest_error!("(get_asset_group_by_id) {}", &error_msg);
-    //     return Err((StatusCode::SERVICE_UNAVAILABLE, error_msg));
-    // }
-    // let mut client = client_option.unwrap();
-
-    // TODO(R4)
-    Ok(payload.id)
+    let id = payload.id.clone();
+    let before = match store.get_group(&id).await {
+        Ok(group) => group,
+        Err(e) if e.code() == Code::NotFound => {
+            return Err((StatusCode::NOT_FOUND, "Asset group not found".to_string()))
+        }
+        Err(e) => {
+            let error_msg = format!("could not get asset group: {}", e);
+            rest_error!("(update_asset_group) {}", &error_msg);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg));
+        }
+    };
+    // The timestamps are kept by the store
+    let after = AssetGroup {
+        created_at: None,
+        updated_at: None,
+        ..payload.clone()
+    };
+    // The stored asset group is made up if groups are not stored, so the
+    // values before the update are not known
+    let changes = if store.stores_groups() {
+        changed_fields(
+            &AssetGroup {
+                created_at: None,
+                updated_at: None,
+                ..before
+            },
+            &after,
+        )
+    } else {
+        created_changes(&after)
+    };
+    let event_data = serde_json::to_value(&payload).ok();
+
+    match store.update_group(payload).await {
+        Ok(()) => {
+            notifier.emit(AssetKind::AssetGroup, AssetAction::Updated, &id, event_data);
+            audit.record(
+                AssetKind::AssetGroup,
+                &id,
+                AuditAction::Updated,
+                &AuditContext::from_headers(&headers),
+                changes,
+            );
+            Ok(id)
+        }
+        Err(e) if e.code() == Code::NotFound => {
+            Err((StatusCode::NOT_FOUND, "Asset group not found".to_string()))
+        }
+        Err(e) => {
+            let error_msg = format!("could not update asset group: {}", e);
+            rest_error!("(update_asset_group) {}", &error_msg);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg))
+        }
+    }
 }
 
 //-----------------------------------------------------------
@@ -1104,96 +1375,436 @@ pub async fn update_asset_group(
 //-----------------------------------------------------------
 
 /// Remove a [`Aircraft`] from the database.
+///
+/// The aircraft is hidden from all listings until it is restored, and
+/// permanently deleted once the retention period has passed.
 #[utoipa::path(
     delete,
     path = "/assets/aircraft/{id}",
     tag = "svc-assets",
     responses(
         (status = 200, description = "Aircraft removed from database; a UUID is returned", body = String),
+        (status = 400, description = "Invalid aircraft id"),
+        (status = 404, description = "Aircraft not found in database or already removed"),
         (status = 503, description = "Could not connect to other microservice dependencies")
     ),
     params(
         ("id" = String, Path, description = "Aircraft id"),
+        RemoveAssetParams
     )
 )]
 pub async fn remove_aircraft(
-    Extension(grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(deletions): Extension<DeletionStore>,
+    Extension(audit): Extension<AuditLog>,
+    Extension(cache): Extension<AssetCache>,
+    headers: HeaderMap,
     Path(id): Path<String>,
+    Query(params): Query<RemoveAssetParams>,
 ) -> Result<String, (StatusCode, String)> {
-    rest_info!("(remove_aircraft) entry [{}].", &id);
-
-    let client = grpc_clients.storage.vehicle;
-
-    match client.delete(Id { id: id.clone() }).await {
-        Ok(res) => {
-            rest_info!("(remove_aircraft) successfully removed aircraft {:?}", res);
-            Ok(id)
-        }
-        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
-    }
+    rest_info!("(remove_aircraft) entry [{}] {:?}.", &id, &params);
+
+    let context =
+        AuditContext::from_headers(&headers).with_removal(params.deleted_by, params.reason);
+    let record = soft_delete(
+        store.as_ref(),
+        &deletions,
+        &notifier,
+        &audit,
+        AssetKind::Aircraft,
+        &id,
+        &context,
+    )
+    .await?;
+    cache.invalidate(AssetKind::Aircraft, &id);
+    rest_info!(
+        "(remove_aircraft) successfully removed aircraft {:?}",
+        record
+    );
+    Ok(id)
 }
 
 /// Remove a [`Vertipad`] from the database.
+///
+/// The vertipad is hidden from all listings until it is restored, and
+/// permanently deleted once the retention period has passed.
 #[utoipa::path(
     delete,
     path = "/assets/vertipads/{id}",
     tag = "svc-assets",
     responses(
         (status = 200, description = "Vertipad removed from database; a UUID is returned", body = String),
+        (status = 400, description = "Invalid vertipad id"),
+        (status = 404, description = "Vertipad not found in database or already removed"),
         (status = 503, description = "Could not connect to other microservice dependencies")
     ),
     params(
         ("id" = String, Path, description = "Vertipad id"),
+        RemoveAssetParams
     )
 )]
 pub async fn remove_vertipad(
-    Extension(grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(deletions): Extension<DeletionStore>,
+    Extension(audit): Extension<AuditLog>,
+    Extension(cache): Extension<AssetCache>,
+    headers: HeaderMap,
     Path(id): Path<String>,
+    Query(params): Query<RemoveAssetParams>,
 ) -> Result<String, (StatusCode, String)> {
-    rest_info!("(remove_vertipad) entry [{}].", &id);
+    rest_info!("(remove_vertipad) entry [{}] {:?}.", &id, &params);
+
+    let context =
+        AuditContext::from_headers(&headers).with_removal(params.deleted_by, params.reason);
+    let record = soft_delete(
+        store.as_ref(),
+        &deletions,
+        &notifier,
+        &audit,
+        AssetKind::Vertipad,
+        &id,
+        &context,
+    )
+    .await?;
+    cache.invalidate(AssetKind::Vertipad, &id);
+    rest_info!(
+        "(remove_vertipad) successfully removed vertipad {:?}",
+        record
+    );
+    Ok(id)
+}
 
-    let client = grpc_clients.storage.vertipad;
+/// Returns the hangar fields of an aircraft referencing one of the
+/// `removed` assets.
+fn hangar_paths(data: &vehicle::Data, removed: &[&String]) -> Vec<String> {
+    let mut paths = vec![];
+    if let Some(hangar_id) = &data.hangar_id {
+        if removed.contains(&hangar_id) {
+            paths.push("hangar_id".to_string());
+        }
+    }
+    if let Some(hangar_bay_id) = &data.hangar_bay_id {
+        if removed.contains(&hangar_bay_id) {
+            paths.push("hangar_bay_id".to_string());
+        }
+    }
+    paths
+}
 
-    match client.delete(Id { id: id.clone() }).await {
-        Ok(res) => {
-            rest_info!("(remove_vertipad) successfully removed vertipad {:?}", res);
-            Ok(id)
+/// Returns the `data` of an aircraft with the hangar fields of `paths`
+/// cleared.
+fn without_hangar(data: &vehicle::Data, paths: &[String]) -> vehicle::Data {
+    let mut data = data.clone();
+    for path in paths {
+        match path.as_str() {
+            "hangar_id" => data.hangar_id = None,
+            _ => data.hangar_bay_id = None,
         }
-        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
     }
+    data
+}
+
+/// Update the hangar references of a `vehicle` with the `update` data, for
+/// the fields listed in its mask.
+async fn update_hangar(
+    store: &dyn AssetStore,
+    cache: &AssetCache,
+    notifier: &ChangeNotifier,
+    audit: &AuditLog,
+    context: &AuditContext,
+    vehicle: (&str, &vehicle::Data),
+    update: (vehicle::Data, Vec<String>),
+) -> Result<(), String> {
+    let (vehicle_id, previous) = vehicle;
+    let (next, paths) = update;
+    let event_data: serde_json::Map<String, serde_json::Value> = paths
+        .iter()
+        .map(|path| {
+            let value = match path.as_str() {
+                "hangar_id" => next.hangar_id.clone(),
+                _ => next.hangar_bay_id.clone(),
+            };
+            (path.clone(), serde_json::json!(value))
+        })
+        .collect();
+    let changes = masked_changes(previous, &next, &paths);
+    let result = store.vehicles().update(vehicle_id, next, paths).await;
+    cache.invalidate(AssetKind::Aircraft, vehicle_id);
+    if let Err(e) = result {
+        return Err(format!(
+            "could not update hangar of aircraft [{}]: {}",
+            vehicle_id, e
+        ));
+    }
+
+    notifier.emit(
+        AssetKind::Aircraft,
+        AssetAction::Updated,
+        vehicle_id,
+        Some(serde_json::Value::Object(event_data)),
+    );
+    audit.record(
+        AssetKind::Aircraft,
+        vehicle_id,
+        AuditAction::Updated,
+        context,
+        changes,
+    );
+    Ok(())
 }
 
 /// Remove a [`Vertiport`] from the database.
+///
+/// The removal is refused while vertipads or aircraft hangared at the
+/// vertiport exist, unless `cascade` is set. On cascade, the vertipads are
+/// removed first, then the vertiport, then the hangar references of the
+/// aircraft are cleared. If a step fails, the removals are undone as far
+/// as possible, and aircraft are only left without the hangar references
+/// to the assets which could not be restored.
+///
+/// Removed assets are hidden from all listings until they are restored,
+/// and permanently deleted once the retention period has passed.
+///
+/// With `dry_run`, the affected vertipads and aircraft are only listed.
 #[utoipa::path(
     delete,
     path = "/assets/vertiports/{id}",
     tag = "svc-assets",
     responses(
-        (status = 200, description = "Vertiport removed from database; a UUID is returned", body = String),
+        (status = 200, description = "Vertiport removed from database; a UUID is returned. On dry run, the assets affected by the removal are returned instead.", content(
+            ("text/plain" = String),
+            ("application/json" = VertiportRemoval)
+        )),
+        (status = 400, description = "Invalid vertiport id"),
+        (status = 404, description = "Vertiport not found in database or already removed"),
+        (status = 409, description = "Vertipads or aircraft depend on the vertiport", body = VertiportRemoval),
+        (status = 500, description = "Cascade failed, the changes which could not be undone are listed"),
         (status = 503, description = "Could not connect to other microservice dependencies")
     ),
     params(
         ("id" = String, Path, description = "Vertiport id"),
+        RemoveVertiportParams
     )
 )]
 pub async fn remove_vertiport(
-    Extension(grpc_clients): Extension<GrpcClients>,
+    Extension(store): Extension<SharedStore>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(deletions): Extension<DeletionStore>,
+    Extension(audit): Extension<AuditLog>,
+    Extension(cache): Extension<AssetCache>,
+    headers: HeaderMap,
     Path(id): Path<String>,
-) -> Result<String, (StatusCode, String)> {
-    rest_info!("(remove_vertiport) entry [{}].", &id);
+    Query(params): Query<RemoveVertiportParams>,
+) -> Result<Response, Response> {
+    rest_info!("(remove_vertiport) entry [{}] {:?}.", &id, &params);
+    if !is_uuid(&id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid vertiport id").into_response());
+    }
+    let removed = is_removed(store.as_ref(), AssetKind::Vertiport, &id)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    if removed {
+        return Err((StatusCode::NOT_FOUND, "Vertiport already removed").into_response());
+    }
+    match store.vertiports().get_by_id(&id).await {
+        Ok(object) if object.data.is_some() => (),
+        Ok(_) => return Err((StatusCode::NOT_FOUND, "Vertiport not found").into_response()),
+        Err(e) if e.code() == Code::NotFound => {
+            return Err((StatusCode::NOT_FOUND, "Vertiport not found").into_response())
+        }
+        Err(e) => {
+            let error_msg = format!("Error getting vertiport from storage: {}", e);
+            rest_error!("(remove_vertiport) {}", &error_msg);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg).into_response());
+        }
+    }
 
-    let client = grpc_clients.storage.vertiport;
+    // Assets which have been removed already don't depend on the vertiport
+    let vertipad_ids: Vec<String> = search_vertiport_vertipads(store.as_ref(), &id)
+        .await
+        .map_err(IntoResponse::into_response)?
+        .into_iter()
+        .map(|vertipad| vertipad.id)
+        .collect();
+    let vehicles = search_hangared_vehicles(store.as_ref(), &id, &vertipad_ids)
+        .await
+        .map_err(IntoResponse::into_response)?;
 
-    match client.delete(Id { id: id.clone() }).await {
-        Ok(res) => {
-            rest_info!(
-                "(remove_vertiport) successfully removed vertiport {:?}",
-                res
+    let removal = VertiportRemoval {
+        vertiport_id: id.clone(),
+        dry_run: params.dry_run,
+        vertipad_ids,
+        aircraft_ids: vehicles.iter().map(|vehicle| vehicle.id.clone()).collect(),
+    };
+    if params.dry_run {
+        return Ok(Json(removal).into_response());
+    }
+    if removal.has_dependents() && !params.cascade {
+        rest_warn!(
+            "(remove_vertiport) vertiport [{}] has dependents: {:?}",
+            &id,
+            &removal
+        );
+        return Err((StatusCode::CONFLICT, Json(removal)).into_response());
+    }
+
+    // Everything is checked before the first change is made
+    let mut hangars = vec![];
+    for vehicle in vehicles {
+        let Some(data) = vehicle.data else {
+            let error_msg = format!("aircraft [{}] has no data", vehicle.id);
+            rest_error!("(remove_vertiport) {}", &error_msg);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg).into_response());
+        };
+        hangars.push((vehicle.id, data));
+    }
+
+    let context =
+        AuditContext::from_headers(&headers).with_removal(params.deleted_by, params.reason);
+
+    // Remove the assets first, as svc-storage can not restore them, while
+    // the hangar references of the aircraft can always be set again
+    let mut removed = vec![];
+    let mut result = Ok(());
+    let assets = removal
+        .vertipad_ids
+        .iter()
+        .map(|vertipad_id| (AssetKind::Vertipad, vertipad_id))
+        .chain([(AssetKind::Vertiport, &id)]);
+    for (kind, asset_id) in assets {
+        result = soft_delete(
+            store.as_ref(),
+            &deletions,
+            &notifier,
+            &audit,
+            kind,
+            asset_id,
+            &context,
+        )
+        .await
+        .map(|_| cache.invalidate(kind, asset_id))
+        .map_err(|(_, error_msg)| error_msg);
+        if result.is_err() {
+            break;
+        }
+        removed.push((kind, asset_id));
+    }
+
+    let mut cleared = vec![];
+    if result.is_ok() {
+        let removed_ids: Vec<&String> = removed.iter().map(|(_, asset_id)| *asset_id).collect();
+        for (vehicle_id, data) in &hangars {
+            let paths = hangar_paths(data, &removed_ids);
+            let next = vehicle::Data {
+                created_at: None,
+                updated_at: None,
+                ..without_hangar(data, &paths)
+            };
+            result = update_hangar(
+                store.as_ref(),
+                &cache,
+                &notifier,
+                &audit,
+                &context,
+                (vehicle_id, data),
+                (next, paths.clone()),
+            )
+            .await;
+            if result.is_err() {
+                break;
+            }
+            cleared.push((vehicle_id, paths));
+        }
+    }
+
+    let Err(error_msg) = result else {
+        rest_info!(
+            "(remove_vertiport) successfully removed vertiport {:?}",
+            removal
+        );
+        return Ok(id.into_response());
+    };
+
+    // Undo the removals in reverse order, then only clear the hangar
+    // references to the assets which are still removed
+    rest_error!(
+        "(remove_vertiport) could not remove vertiport [{}], undoing changes: {}",
+        &id,
+        &error_msg
+    );
+    let mut not_undone = vec![];
+    let mut still_removed = vec![];
+    for (kind, asset_id) in removed.into_iter().rev() {
+        let restored = restore(
+            store.as_ref(),
+            &deletions,
+            &notifier,
+            &audit,
+            kind,
+            asset_id,
+            &context,
+        )
+        .await;
+        if let Err((_, e)) = restored {
+            rest_error!(
+                "(remove_vertiport) could not restore {:?} [{}]: {}",
+                kind,
+                asset_id,
+                e
             );
-            Ok(id)
+            not_undone.push(asset_id.clone());
+            still_removed.push(asset_id);
+        }
+    }
+    for (vehicle_id, data) in &hangars {
+        let current_paths = cleared
+            .iter()
+            .find(|(cleared_id, _)| *cleared_id == vehicle_id)
+            .map(|(_, paths)| paths.clone())
+            .unwrap_or_default();
+        let target_paths = hangar_paths(data, &still_removed);
+        let paths: Vec<String> = ["hangar_id", "hangar_bay_id"]
+            .into_iter()
+            .map(String::from)
+            .filter(|path| current_paths.contains(path) != target_paths.contains(path))
+            .collect();
+        if paths.is_empty() {
+            continue;
+        }
+
+        let previous = without_hangar(data, &current_paths);
+        let next = vehicle::Data {
+            created_at: None,
+            updated_at: None,
+            ..without_hangar(data, &target_paths)
+        };
+        let updated = update_hangar(
+            store.as_ref(),
+            &cache,
+            &notifier,
+            &audit,
+            &context,
+            (vehicle_id, &previous),
+            (next, paths),
+        )
+        .await;
+        if let Err(e) = updated {
+            rest_error!("(remove_vertiport) {}", e);
+            not_undone.push(vehicle_id.clone());
         }
-        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
     }
+
+    let mut error_msg = format!("could not remove vertiport: {}", error_msg);
+    if !not_undone.is_empty() {
+        error_msg = format!(
+            "{}; changes to [{}] could not be undone",
+            error_msg,
+            not_undone.join(", ")
+        );
+    }
+    Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg).into_response())
 }
 
 /// Remove an [`AssetGroup`](crate::rest::structs::AssetGroup) from the database.
@@ -1203,6 +1814,7 @@ pub async fn remove_vertiport(
     tag = "svc-assets",
     responses(
         (status = 200, description = "AssetGroup removed from database; a UUID is returned", body = String),
+        (status = 404, description = "AssetGroup not found in database"),
         (status = 503, description = "Could not connect to other microservice dependencies")
     ),
     params(
@@ -1210,20 +1822,144 @@ pub async fn remove_vertiport(
     )
 )]
 pub async fn remove_asset_group(
-    Extension(_grpc_clients): Extension<GrpcClients>,
-    Path(_id): Path<String>,
+    Extension(store): Extension<SharedStore>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(audit): Extension<AuditLog>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
 ) -> Result<String, (StatusCode, String)> {
-    rest_info!("(remove_asset_group) with id: {:?}", &_id);
+    rest_info!("(remove_asset_group) with id: {:?}", &id);
+
+    match store.delete_group(&id).await {
+        Ok(()) => {
+            notifier.emit(AssetKind::AssetGroup, AssetAction::Removed, &id, None);
+            audit.record(
+                AssetKind::AssetGroup,
+                &id,
+                AuditAction::Removed,
+                &AuditContext::from_headers(&headers),
+                vec![],
+            );
+            Ok(id)
+        }
+        Err(e) if e.code() == Code::NotFound => {
+            Err((StatusCode::NOT_FOUND, "Asset group not found".to_string()))
+        }
+        Err(e) => {
+            let error_msg = format!("could not remove asset group: {}", e);
+            rest_error!("(remove_asset_group) {}", &error_msg);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg))
+        }
+    }
+}
 
-    // Get Client
-    // let _client_option = grpc_clients.storage.get_client().await;
-    // if client_option.is_none() {
-    //     let error_msg = "svc-storage unavailable.".to_string();
-    //     rest_error!("(remove_asset_group) {}", &error_msg);
-    //     return Err((StatusCode::SERVICE_UNAVAILABLE, error_msg));
-    // }
-    // let mut client = client_option.unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::Metrics;
+    use crate::store::MemoryStore;
+    use std::sync::Arc;
+
+    /// Store a vertiport with two vertipads, and an aircraft hangared at the
+    /// first vertipad, returning their ids.
+    async fn hangared_aircraft(store: &MemoryStore) -> (String, Vec<String>, String) {
+        let vertiport = store
+            .vertiports()
+            .insert(vertiport::Data::default())
+            .await
+            .unwrap();
+        let mut vertipad_ids = vec![];
+        for _ in 0..2 {
+            let data = vertipad::Data {
+                vertiport_id: vertiport.id.clone(),
+                ..Default::default()
+            };
+            vertipad_ids.push(store.vertipads().insert(data).await.unwrap().id);
+        }
+        let data = vehicle::Data {
+            hangar_id: Some(vertiport.id.clone()),
+            hangar_bay_id: Some(vertipad_ids[0].clone()),
+            ..Default::default()
+        };
+        let vehicle = store.vehicles().insert(data).await.unwrap();
+        (vertiport.id, vertipad_ids, vehicle.id)
+    }
 
-    // TODO(R4)
-    Ok(_id)
+    /// Remove a vertiport with its dependents, returning the error message.
+    async fn remove_cascade(store: &MemoryStore, vertiport_id: &str) -> String {
+        let config = crate::Config::default();
+        let response = remove_vertiport(
+            Extension(Arc::new(store.clone()) as SharedStore),
+            Extension(ChangeNotifier::new(&config).unwrap()),
+            Extension(DeletionStore::default()),
+            Extension(AuditLog::in_memory(Metrics::new())),
+            Extension(AssetCache::new(&config, Metrics::new())),
+            HeaderMap::new(),
+            Path(vertiport_id.to_string()),
+            Query(RemoveVertiportParams {
+                cascade: true,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_remove_vertiport_undo() {
+        crate::get_log_handle().await;
+        ut_info!("(test_remove_vertiport_undo) Start.");
+
+        // The removals are undone if clearing a hangar reference fails
+        let store = MemoryStore::default();
+        let (vertiport_id, vertipad_ids, vehicle_id) = hangared_aircraft(&store).await;
+        store.fail("vehicle", "update");
+        let error_msg = remove_cascade(&store, &vertiport_id).await;
+        assert!(!error_msg.contains("could not be undone"));
+        assert!(!store.vertiports().is_deleted(&vertiport_id).await.unwrap());
+        for vertipad_id in &vertipad_ids {
+            assert!(!store.vertipads().is_deleted(vertipad_id).await.unwrap());
+        }
+        let data = store
+            .vehicles()
+            .get_by_id(&vehicle_id)
+            .await
+            .unwrap()
+            .data
+            .unwrap();
+        assert_eq!(data.hangar_bay_id, Some(vertipad_ids[0].clone()));
+
+        // Aircraft are not left hangared at vertipads which can not be
+        // restored
+        let store = MemoryStore::default();
+        let (vertiport_id, vertipad_ids, vehicle_id) = hangared_aircraft(&store).await;
+        store.fail("vertiport", "soft_delete");
+        store.fail("vertipad", "restore");
+        let error_msg = remove_cascade(&store, &vertiport_id).await;
+        // Vertipads are found in no particular order
+        let (_, not_undone) = error_msg.split_once("changes to [").unwrap();
+        let (not_undone, _) = not_undone.split_once("] could not be undone").unwrap();
+        let mut not_undone: Vec<&str> = not_undone.split(", ").collect();
+        not_undone.sort();
+        let mut expected: Vec<&str> = vertipad_ids.iter().map(String::as_str).collect();
+        expected.sort();
+        assert_eq!(not_undone, expected);
+        for vertipad_id in &vertipad_ids {
+            assert!(store.vertipads().is_deleted(vertipad_id).await.unwrap());
+        }
+        let data = store
+            .vehicles()
+            .get_by_id(&vehicle_id)
+            .await
+            .unwrap()
+            .data
+            .unwrap();
+        assert_eq!(data.hangar_id, Some(vertiport_id));
+        assert_eq!(data.hangar_bay_id, None);
+
+        ut_info!("(test_remove_vertiport_undo) Success.");
+    }
 }
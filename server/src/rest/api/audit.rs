@@ -0,0 +1,199 @@
+//! REST API implementations for the audit log
+
+use super::is_uuid;
+use crate::audit::{AuditEntry, AuditFilter, AuditLog, PastState};
+use crate::events::AssetKind;
+use axum::{
+    extract::{Path, Query},
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use hyper::StatusCode;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Reconstruct the `data` of an asset as it was at `as_of`, by rolling back
+/// the changes recorded since. The asset is currently `removed` or not.
+pub(super) fn data_as_of<T: Serialize + DeserializeOwned>(
+    audit: &AuditLog,
+    removed: bool,
+    kind: AssetKind,
+    asset_id: &str,
+    data: Option<T>,
+    as_of: &str,
+) -> Result<Option<T>, (StatusCode, String)> {
+    let as_of = DateTime::parse_from_rfc3339(as_of)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| {
+            let error_msg = format!("Invalid as_of time [{}]: {}", as_of, e);
+            rest_warn!("(data_as_of) {}", &error_msg);
+            (StatusCode::BAD_REQUEST, error_msg)
+        })?;
+    let Some(data) = data else {
+        return Ok(None);
+    };
+
+    let to_error = |e: serde_json::Error| {
+        let error_msg = format!("Could not reconstruct {:?} [{}]: {}", kind, asset_id, e);
+        rest_error!("(data_as_of) {}", &error_msg);
+        (StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+    };
+    let current = serde_json::to_value(data).map_err(to_error)?;
+    let state = audit
+        .state_at(kind, asset_id, current, removed, as_of)
+        .map_err(audit_error)?;
+    match state {
+        PastState::Existing(state) => serde_json::from_value(state).map(Some).map_err(to_error),
+        PastState::Missing => Err((
+            StatusCode::NOT_FOUND,
+            format!("{:?} did not exist at {}", kind, as_of),
+        )),
+        PastState::Unknown(Some(first)) => Err((
+            StatusCode::CONFLICT,
+            format!("{:?} history not available before {}", kind, first),
+        )),
+        PastState::Unknown(None) => Err((
+            StatusCode::CONFLICT,
+            format!("{:?} history not available", kind),
+        )),
+    }
+}
+
+/// Map an error reading the audit log to a REST error.
+fn audit_error(e: std::io::Error) -> (StatusCode, String) {
+    let error_msg = format!("Could not read the audit log: {}", e);
+    rest_error!("(audit_error) {}", &error_msg);
+    (StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+}
+
+/// Returns all changes of an asset, oldest first.
+fn history(
+    audit: &AuditLog,
+    kind: AssetKind,
+    asset_id: &str,
+) -> Result<Json<Vec<AuditEntry>>, (StatusCode, String)> {
+    if !is_uuid(asset_id) {
+        return Err((StatusCode::BAD_REQUEST, format!("Invalid {:?} id", kind)));
+    }
+
+    audit.history(kind, asset_id).map(Json).map_err(audit_error)
+}
+
+/// Get all changes of an [`Aircraft`](crate::rest::structs::Aircraft),
+/// oldest first.
+///
+/// Changes of removed aircraft are kept.
+#[utoipa::path(
+    get,
+    path = "/assets/aircraft/{id}/history",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Changes of aircraft {id}", body = [AuditEntry]),
+        (status = 400, description = "Invalid aircraft id"),
+        (status = 500, description = "Could not read the audit log")
+    ),
+    params(
+        ("id" = String, Path, description = "Aircraft id"),
+    )
+)]
+pub async fn get_aircraft_history(
+    Extension(audit): Extension<AuditLog>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<AuditEntry>>, (StatusCode, String)> {
+    rest_info!("(get_aircraft_history) entry [{}].", &id);
+    history(&audit, AssetKind::Aircraft, &id)
+}
+
+/// Get all changes of a [`Vertiport`](crate::rest::structs::Vertiport),
+/// oldest first.
+///
+/// Changes of removed vertiports are kept.
+#[utoipa::path(
+    get,
+    path = "/assets/vertiports/{id}/history",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Changes of vertiport {id}", body = [AuditEntry]),
+        (status = 400, description = "Invalid vertiport id"),
+        (status = 500, description = "Could not read the audit log")
+    ),
+    params(
+        ("id" = String, Path, description = "Vertiport id"),
+    )
+)]
+pub async fn get_vertiport_history(
+    Extension(audit): Extension<AuditLog>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<AuditEntry>>, (StatusCode, String)> {
+    rest_info!("(get_vertiport_history) entry [{}].", &id);
+    history(&audit, AssetKind::Vertiport, &id)
+}
+
+/// Get all changes of a [`Vertipad`](crate::rest::structs::Vertipad),
+/// oldest first.
+///
+/// Changes of removed vertipads are kept.
+#[utoipa::path(
+    get,
+    path = "/assets/vertipads/{id}/history",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Changes of vertipad {id}", body = [AuditEntry]),
+        (status = 400, description = "Invalid vertipad id"),
+        (status = 500, description = "Could not read the audit log")
+    ),
+    params(
+        ("id" = String, Path, description = "Vertipad id"),
+    )
+)]
+pub async fn get_vertipad_history(
+    Extension(audit): Extension<AuditLog>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<AuditEntry>>, (StatusCode, String)> {
+    rest_info!("(get_vertipad_history) entry [{}].", &id);
+    history(&audit, AssetKind::Vertipad, &id)
+}
+
+/// Number of changes returned by an audit log query without a `limit`.
+const DEFAULT_QUERY_LIMIT: usize = 100;
+/// Largest number of changes returned by an audit log query.
+const MAX_QUERY_LIMIT: usize = 1000;
+
+/// Get the changes of all assets passing the filter, oldest first.
+///
+/// Only the most recent 100 matching changes are returned if no `limit` is
+/// provided, and at most 1000 changes.
+#[utoipa::path(
+    get,
+    path = "/assets/audit",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Changes passing the filter", body = [AuditEntry]),
+        (status = 500, description = "Could not read the audit log")
+    ),
+    params(
+        AuditFilter
+    )
+)]
+pub async fn get_audit_entries(
+    Extension(audit): Extension<AuditLog>,
+    Query(filter): Query<AuditFilter>,
+) -> Result<Json<Vec<AuditEntry>>, (StatusCode, String)> {
+    rest_info!("(get_audit_entries) entry {:?}.", filter);
+    let filter = AuditFilter {
+        limit: Some(
+            filter
+                .limit
+                .unwrap_or(DEFAULT_QUERY_LIMIT)
+                .min(MAX_QUERY_LIMIT),
+        ),
+        ..filter
+    };
+
+    // Queries not limited to an asset read the whole audit file
+    let audit = audit.clone();
+    tokio::task::spawn_blocking(move || audit.query(&filter))
+        .await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e)))
+        .map(Json)
+        .map_err(audit_error)
+}
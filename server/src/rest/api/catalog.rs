@@ -0,0 +1,243 @@
+//! REST API implementations for the vehicle model catalog
+
+use super::is_uuid;
+use super::rest_types::VehicleModelPayload;
+use crate::catalog::{VehicleCatalog, VehicleDimensions, VehicleModel};
+use crate::store::{AssetFilter, SharedStore};
+use axum::{extract::Path, Extension, Json};
+use chrono::Utc;
+use hyper::StatusCode;
+use uuid::Uuid;
+
+/// Check the payload and build the [`VehicleModel`] with the given id.
+fn to_vehicle_model(
+    id: String,
+    payload: VehicleModelPayload,
+) -> Result<VehicleModel, (StatusCode, String)> {
+    if payload.manufacturer.trim().is_empty() || payload.model_name.trim().is_empty() {
+        let error_msg = "manufacturer and model_name can not be empty.".to_string();
+        rest_warn!("(to_vehicle_model) {}", &error_msg);
+        return Err((StatusCode::BAD_REQUEST, error_msg));
+    }
+
+    let values = [
+        ("max_payload_kg", payload.max_payload_kg),
+        ("max_range_km", payload.max_range_km),
+        ("cruise_speed_kph", payload.cruise_speed_kph),
+        ("charge_time_minutes", payload.charge_time_minutes),
+        ("length_m", payload.length_m),
+        ("width_m", payload.width_m),
+        ("height_m", payload.height_m),
+    ];
+    if let Some((field, value)) = values
+        .iter()
+        .find(|(_, value)| !value.is_finite() || *value < 0.0)
+    {
+        let error_msg = format!("{} must be a non-negative number, got {}.", field, value);
+        rest_warn!("(to_vehicle_model) {}", &error_msg);
+        return Err((StatusCode::BAD_REQUEST, error_msg));
+    }
+
+    let now = Utc::now();
+    Ok(VehicleModel {
+        id,
+        manufacturer: payload.manufacturer,
+        model_name: payload.model_name,
+        max_payload_kg: payload.max_payload_kg,
+        max_range_km: payload.max_range_km,
+        cruise_speed_kph: payload.cruise_speed_kph,
+        charge_time_minutes: payload.charge_time_minutes,
+        dimensions: VehicleDimensions {
+            length_m: payload.length_m,
+            width_m: payload.width_m,
+            height_m: payload.height_m,
+        },
+        created_at: now,
+        updated_at: now,
+    })
+}
+
+/// Store a model in the catalog.
+fn upsert(
+    catalog: &VehicleCatalog,
+    model: VehicleModel,
+) -> Result<VehicleModel, (StatusCode, String)> {
+    catalog.upsert(model).map_err(|e| {
+        let error_msg = format!("could not store vehicle model: {}", e);
+        rest_error!("(upsert) {}", &error_msg);
+        (StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+    })
+}
+
+/// Get all [`VehicleModel`]s of the catalog.
+#[utoipa::path(
+    get,
+    path = "/assets/models",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Vehicle models of the catalog", body = [VehicleModel])
+    )
+)]
+pub async fn get_vehicle_models(
+    Extension(catalog): Extension<VehicleCatalog>,
+) -> Json<Vec<VehicleModel>> {
+    rest_info!("(get_vehicle_models) entry.");
+    Json(catalog.list())
+}
+
+/// Get a [`VehicleModel`] by its id.
+#[utoipa::path(
+    get,
+    path = "/assets/models/{id}",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Vehicle model {id} found in the catalog", body = VehicleModel),
+        (status = 400, description = "Invalid vehicle model id"),
+        (status = 404, description = "Vehicle model not found in the catalog")
+    ),
+    params(
+        ("id" = String, Path, description = "Vehicle model id"),
+    )
+)]
+pub async fn get_vehicle_model(
+    Extension(catalog): Extension<VehicleCatalog>,
+    Path(id): Path<String>,
+) -> Result<Json<VehicleModel>, (StatusCode, String)> {
+    rest_info!("(get_vehicle_model) entry [{}].", &id);
+    if !is_uuid(&id) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Invalid vehicle model id".to_string(),
+        ));
+    }
+
+    catalog
+        .get(&id)
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Vehicle model not found".to_string()))
+}
+
+/// Add a [`VehicleModel`] to the catalog.
+#[utoipa::path(
+    post,
+    path = "/assets/models",
+    tag = "svc-assets",
+    request_body = VehicleModelPayload,
+    responses(
+        (status = 200, description = "Vehicle model added to the catalog; a UUID is returned", body = String),
+        (status = 400, description = "Empty names or negative values"),
+        (status = 422, description = "Request body is invalid format")
+    )
+)]
+pub async fn register_vehicle_model(
+    Extension(catalog): Extension<VehicleCatalog>,
+    Json(payload): Json<VehicleModelPayload>,
+) -> Result<String, (StatusCode, String)> {
+    rest_info!("(register_vehicle_model) entry.");
+    rest_debug!("(register_vehicle_model) Payload: {:?}", &payload);
+
+    let model = to_vehicle_model(Uuid::new_v4().to_string(), payload)?;
+    let model = upsert(&catalog, model)?;
+    rest_info!("(register_vehicle_model) added vehicle model {:?}", model);
+    Ok(model.id)
+}
+
+/// Replace a [`VehicleModel`] of the catalog.
+#[utoipa::path(
+    put,
+    path = "/assets/models/{id}",
+    tag = "svc-assets",
+    request_body = VehicleModelPayload,
+    responses(
+        (status = 200, description = "Vehicle model updated in the catalog; a UUID is returned", body = String),
+        (status = 400, description = "Invalid vehicle model id, empty names or negative values"),
+        (status = 404, description = "Vehicle model not found in the catalog"),
+        (status = 422, description = "Request body is invalid format")
+    ),
+    params(
+        ("id" = String, Path, description = "Vehicle model id"),
+    )
+)]
+pub async fn update_vehicle_model(
+    Extension(catalog): Extension<VehicleCatalog>,
+    Path(id): Path<String>,
+    Json(payload): Json<VehicleModelPayload>,
+) -> Result<String, (StatusCode, String)> {
+    rest_info!("(update_vehicle_model) entry [{}].", &id);
+    rest_debug!("(update_vehicle_model) Payload: {:?}", &payload);
+    if !is_uuid(&id) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Invalid vehicle model id".to_string(),
+        ));
+    }
+    if catalog.get(&id).is_none() {
+        return Err((StatusCode::NOT_FOUND, "Vehicle model not found".to_string()));
+    }
+
+    let model = to_vehicle_model(id, payload)?;
+    let model = upsert(&catalog, model)?;
+    rest_info!("(update_vehicle_model) updated vehicle model {:?}", model);
+    Ok(model.id)
+}
+
+/// Remove a [`VehicleModel`] from the catalog.
+///
+/// The removal is refused while aircraft of the model exist.
+#[utoipa::path(
+    delete,
+    path = "/assets/models/{id}",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Vehicle model removed from the catalog; a UUID is returned", body = String),
+        (status = 400, description = "Invalid vehicle model id"),
+        (status = 404, description = "Vehicle model not found in the catalog"),
+        (status = 409, description = "Aircraft of the vehicle model exist"),
+        (status = 503, description = "Could not connect to other microservice dependencies")
+    ),
+    params(
+        ("id" = String, Path, description = "Vehicle model id"),
+    )
+)]
+pub async fn remove_vehicle_model(
+    Extension(store): Extension<SharedStore>,
+    Extension(catalog): Extension<VehicleCatalog>,
+    Path(id): Path<String>,
+) -> Result<String, (StatusCode, String)> {
+    rest_info!("(remove_vehicle_model) entry [{}].", &id);
+    if !is_uuid(&id) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Invalid vehicle model id".to_string(),
+        ));
+    }
+    if catalog.get(&id).is_none() {
+        return Err((StatusCode::NOT_FOUND, "Vehicle model not found".to_string()));
+    }
+
+    let filter = AssetFilter::equals("vehicle_model_id", &id).and_is_null("deleted_at");
+    let aircraft_ids: Vec<String> = match store.vehicles().search(&filter).await {
+        Ok(vehicles) => vehicles.into_iter().map(|vehicle| vehicle.id).collect(),
+        Err(e) => {
+            let error_msg = "could not retrieve vehicles.".to_string();
+            rest_error!("(remove_vehicle_model) {}: {}.", error_msg, e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg));
+        }
+    };
+    if !aircraft_ids.is_empty() {
+        let error_msg = format!(
+            "vehicle model is used by aircraft [{}].",
+            aircraft_ids.join(", ")
+        );
+        rest_warn!("(remove_vehicle_model) {}", &error_msg);
+        return Err((StatusCode::CONFLICT, error_msg));
+    }
+
+    catalog.remove(&id).map_err(|e| {
+        let error_msg = format!("could not remove vehicle model: {}", e);
+        rest_error!("(remove_vehicle_model) {}", &error_msg);
+        (StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+    })?;
+    rest_info!("(remove_vehicle_model) removed vehicle model [{}].", &id);
+    Ok(id)
+}
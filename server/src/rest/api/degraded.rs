@@ -0,0 +1,92 @@
+//! REST API implementations for the degraded mode
+
+use crate::degraded::{serves_stale, storage_dependencies, DegradedMode, StaleReads};
+use axum::{
+    body::Body,
+    extract::MatchedPath,
+    http::{header, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+/// Warning attached to responses including stale assets.
+const STALE_WARNING: &str = "110 svc-assets \"Response is Stale\"";
+
+/// Reject writes and listings while the svc-storage resources a route
+/// depends on are down, and flag responses to reads which were served stale
+/// assets.
+///
+/// Reads of an asset by id are served the expired assets of the asset cache
+/// if looking them up in svc-storage fails with a transient error, as a
+/// resource may go down between two health checks. Listings are never
+/// served from the cache. Must be added with `route_layer`, so the matched
+/// route is known.
+pub async fn degraded_mode(request: Request<Body>, next: Next<Body>) -> Response {
+    let Some(degraded) = request.extensions().get::<DegradedMode>().cloned() else {
+        return next.run(request).await;
+    };
+    let (dependencies, stale) = match request.extensions().get::<MatchedPath>() {
+        Some(route) => (
+            storage_dependencies(request.method(), route.as_str()),
+            serves_stale(route.as_str()),
+        ),
+        None => (&[][..], false),
+    };
+    if dependencies.is_empty() {
+        return next.run(request).await;
+    }
+
+    // Writes and reads not served from the asset cache need the resources
+    if request.method() != Method::GET || !stale {
+        let down = degraded.down(dependencies);
+        if down.is_empty() {
+            return next.run(request).await;
+        }
+        let rejected = match request.method() == Method::GET {
+            true => "only assets looked up by id are served",
+            false => "changes are rejected",
+        };
+        return reject(&degraded, &down, rejected);
+    }
+
+    let uri = request.uri().to_string();
+    let reads = StaleReads::default();
+    let mut response = reads.clone().scope(next.run(request)).await;
+    if let (StatusCode::OK, Some(age)) = (response.status(), reads.age()) {
+        rest_warn!(
+            "(degraded_mode) svc-storage unavailable, served stale assets to [{}].",
+            uri
+        );
+        flag_stale(&mut response, age.as_secs());
+    }
+    response
+}
+
+/// Reject a request while svc-storage resources it depends on are down.
+fn reject(degraded: &DegradedMode, down: &[&str], rejected: &str) -> Response {
+    let error_msg = format!(
+        "svc-storage {} unavailable, {} until it recovers.",
+        down.join(", "),
+        rejected
+    );
+    rest_warn!("(reject) {}", &error_msg);
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(
+            header::RETRY_AFTER,
+            degraded.retry_after().as_secs().to_string(),
+        )],
+        error_msg,
+    )
+        .into_response()
+}
+
+/// Flag a response including stale assets with a `Warning` header and the
+/// `Age` of the oldest asset in seconds.
+fn flag_stale(response: &mut Response, age: u64) {
+    let headers = response.headers_mut();
+    headers.insert(header::WARNING, HeaderValue::from_static(STALE_WARNING));
+    if let Ok(age) = HeaderValue::from_str(&age.to_string()) {
+        headers.insert(header::AGE, age);
+    }
+}
@@ -0,0 +1,466 @@
+//! REST API implementations for removed assets
+
+use super::is_uuid;
+use crate::audit::{AuditAction, AuditContext, AuditLog};
+use crate::degraded::stale_reads_allowed;
+use crate::deletions::{DeletionFilter, DeletionRecord, DeletionStore};
+use crate::events::{AssetAction, AssetKind, ChangeNotifier};
+use crate::grpc::policy::is_transient;
+use crate::store::{AssetFilter, AssetStore, SharedStore};
+use axum::{
+    extract::{Path, Query},
+    http::HeaderMap,
+    Extension, Json,
+};
+use chrono::Utc;
+use hyper::StatusCode;
+use std::future::Future;
+use tonic::{Code, Status};
+
+/// Check if an asset exists in storage.
+async fn asset_exists(
+    store: &dyn AssetStore,
+    kind: AssetKind,
+    asset_id: &str,
+) -> Result<bool, (StatusCode, String)> {
+    let result = match kind {
+        AssetKind::Aircraft => store
+            .vehicles()
+            .get_by_id(asset_id)
+            .await
+            .map(|object| object.data.is_some()),
+        AssetKind::Vertiport => store
+            .vertiports()
+            .get_by_id(asset_id)
+            .await
+            .map(|object| object.data.is_some()),
+        AssetKind::Vertipad => store
+            .vertipads()
+            .get_by_id(asset_id)
+            .await
+            .map(|object| object.data.is_some()),
+        AssetKind::AssetGroup => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Asset groups can not be removed".to_string(),
+            ))
+        }
+    };
+
+    match result {
+        Ok(exists) => Ok(exists),
+        Err(e) if e.code() == Code::NotFound => Ok(false),
+        Err(e) => {
+            let error_msg = format!("Error getting {:?} from storage: {}", kind, e);
+            rest_error!("(asset_exists) {}", &error_msg);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg))
+        }
+    }
+}
+
+/// Map a storage error to a REST error.
+fn storage_error(function: &str, kind: AssetKind, error: tonic::Status) -> (StatusCode, String) {
+    let error_msg = format!("Error updating {:?} in storage: {}", kind, error);
+    rest_error!("({}) {}", function, &error_msg);
+    match error.code() {
+        Code::NotFound => (StatusCode::NOT_FOUND, format!("{:?} not found", kind)),
+        Code::Unimplemented => (StatusCode::NOT_IMPLEMENTED, error_msg),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, error_msg),
+    }
+}
+
+/// Check if an asset has been removed.
+pub(super) async fn is_removed(
+    store: &dyn AssetStore,
+    kind: AssetKind,
+    asset_id: &str,
+) -> Result<bool, (StatusCode, String)> {
+    let result = match kind {
+        AssetKind::Aircraft => store.vehicles().is_deleted(asset_id).await,
+        AssetKind::Vertiport => store.vertiports().is_deleted(asset_id).await,
+        AssetKind::Vertipad => store.vertipads().is_deleted(asset_id).await,
+        AssetKind::AssetGroup => return Ok(false),
+    };
+
+    match result {
+        Ok(removed) => Ok(removed),
+        // Removed assets are not kept in the asset cache, so the stale
+        // assets served while svc-storage is down have not been removed
+        Err(e) if is_transient(&e) && stale_reads_allowed() => {
+            rest_warn!(
+                "(is_removed) could not check if {:?} [{}] has been removed, serving stale assets: {}",
+                kind,
+                asset_id,
+                e
+            );
+            Ok(false)
+        }
+        Err(e) => {
+            let error_msg = format!("Error getting {:?} from storage: {}", kind, e);
+            rest_error!("(is_removed) {}", &error_msg);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg))
+        }
+    }
+}
+
+/// Load an asset from storage, failing with `NOT_FOUND` if it has been
+/// removed.
+///
+/// Meant to be loaded through the asset cache: removing an asset
+/// invalidates its cache entry, so its removal only needs to be checked
+/// when the asset is not cached.
+pub(super) async fn load_not_removed<T>(
+    store: &dyn AssetStore,
+    kind: AssetKind,
+    asset_id: &str,
+    load: impl Future<Output = Result<T, Status>>,
+) -> Result<T, Status> {
+    let removed = match kind {
+        AssetKind::Aircraft => store.vehicles().is_deleted(asset_id).await?,
+        AssetKind::Vertiport => store.vertiports().is_deleted(asset_id).await?,
+        AssetKind::Vertipad => store.vertipads().is_deleted(asset_id).await?,
+        AssetKind::AssetGroup => false,
+    };
+    match removed {
+        true => Err(Status::not_found(format!(
+            "{:?} [{}] has been removed",
+            kind, asset_id
+        ))),
+        false => load.await,
+    }
+}
+
+/// Fail with 404 if an asset has been removed.
+pub(super) async fn check_not_removed(
+    store: &dyn AssetStore,
+    kind: AssetKind,
+    asset_id: &str,
+) -> Result<(), (StatusCode, String)> {
+    if is_removed(store, kind, asset_id).await? {
+        rest_warn!(
+            "(check_not_removed) {:?} [{}] has been removed.",
+            kind,
+            asset_id
+        );
+        return Err((StatusCode::NOT_FOUND, format!("{:?} not found", kind)));
+    }
+    Ok(())
+}
+
+/// Remove an asset, hiding it from all listings until it is restored or
+/// purged.
+///
+/// The asset is marked as deleted in the store. The actor and reason of
+/// the `context` are recorded with the removal.
+pub(super) async fn soft_delete(
+    store: &dyn AssetStore,
+    deletions: &DeletionStore,
+    notifier: &ChangeNotifier,
+    audit: &AuditLog,
+    kind: AssetKind,
+    asset_id: &str,
+    context: &AuditContext,
+) -> Result<DeletionRecord, (StatusCode, String)> {
+    if !is_uuid(asset_id) {
+        return Err((StatusCode::BAD_REQUEST, format!("Invalid {:?} id", kind)));
+    }
+    if is_removed(store, kind, asset_id).await? {
+        let error_msg = match deletions.get(asset_id).and_then(|record| record.deleted_at) {
+            Some(deleted_at) => format!("{:?} has already been removed at {}", kind, deleted_at),
+            None => format!("{:?} has already been removed", kind),
+        };
+        rest_warn!("(soft_delete) {}", &error_msg);
+        return Err((StatusCode::NOT_FOUND, error_msg));
+    }
+    if !asset_exists(store, kind, asset_id).await? {
+        return Err((StatusCode::NOT_FOUND, format!("{:?} not found", kind)));
+    }
+
+    let result = match kind {
+        AssetKind::Aircraft => store.vehicles().soft_delete(asset_id).await,
+        AssetKind::Vertiport => store.vertiports().soft_delete(asset_id).await,
+        AssetKind::Vertipad => store.vertipads().soft_delete(asset_id).await,
+        AssetKind::AssetGroup => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Asset groups can not be removed".to_string(),
+            ))
+        }
+    };
+    result.map_err(|e| storage_error("soft_delete", kind, e))?;
+
+    let record = DeletionRecord {
+        kind,
+        asset_id: asset_id.to_string(),
+        deleted_at: Some(Utc::now()),
+        deleted_by: context.actor.clone(),
+        reason: context.reason.clone(),
+    };
+    // The asset is removed, even if the details of the removal are lost
+    if let Err(e) = deletions.record(record.clone()) {
+        rest_error!(
+            "(soft_delete) {:?} [{}] removed, but the removal could not be recorded: {}",
+            kind,
+            asset_id,
+            e
+        );
+    }
+    audit.record(kind, asset_id, AuditAction::Removed, context, vec![]);
+
+    notifier.emit(
+        kind,
+        AssetAction::Removed,
+        asset_id,
+        serde_json::to_value(&record).ok(),
+    );
+    Ok(record)
+}
+
+/// Restore a removed asset, returning its id.
+pub(super) async fn restore(
+    store: &dyn AssetStore,
+    deletions: &DeletionStore,
+    notifier: &ChangeNotifier,
+    audit: &AuditLog,
+    kind: AssetKind,
+    asset_id: &str,
+    context: &AuditContext,
+) -> Result<String, (StatusCode, String)> {
+    if !is_uuid(asset_id) {
+        return Err((StatusCode::BAD_REQUEST, format!("Invalid {:?} id", kind)));
+    }
+    if !is_removed(store, kind, asset_id).await? {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("{:?} has not been removed", kind),
+        ));
+    }
+
+    let result = match kind {
+        AssetKind::Aircraft => store.vehicles().restore(asset_id).await,
+        AssetKind::Vertiport => store.vertiports().restore(asset_id).await,
+        AssetKind::Vertipad => store.vertipads().restore(asset_id).await,
+        AssetKind::AssetGroup => {
+            return Err((StatusCode::NOT_FOUND, "Asset group not found".to_string()))
+        }
+    };
+    result.map_err(|e| storage_error("restore", kind, e))?;
+
+    let record = match deletions.forget(asset_id) {
+        Ok(record) => record,
+        Err(e) => {
+            rest_error!(
+                "(restore) {:?} [{}] restored, but its removal could not be forgotten: {}",
+                kind,
+                asset_id,
+                e
+            );
+            None
+        }
+    };
+    audit.record(kind, asset_id, AuditAction::Restored, context, vec![]);
+    notifier.emit(
+        kind,
+        AssetAction::Created,
+        asset_id,
+        Some(serde_json::json!({ "restored": &record })),
+    );
+    Ok(asset_id.to_string())
+}
+
+/// Restore a removed [`Aircraft`](crate::rest::structs::Aircraft).
+///
+/// Not served with the svc-storage backend, which can not restore assets.
+#[utoipa::path(
+    post,
+    path = "/assets/aircraft/{id}/restore",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Aircraft restored; a UUID is returned", body = String),
+        (status = 400, description = "Invalid aircraft id"),
+        (status = 404, description = "Aircraft has not been removed or has been purged")
+    ),
+    params(
+        ("id" = String, Path, description = "Aircraft id"),
+    )
+)]
+pub async fn restore_aircraft(
+    Extension(store): Extension<SharedStore>,
+    Extension(deletions): Extension<DeletionStore>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(audit): Extension<AuditLog>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<String, (StatusCode, String)> {
+    rest_info!("(restore_aircraft) entry [{}].", &id);
+    let context = AuditContext::from_headers(&headers);
+    restore(
+        store.as_ref(),
+        &deletions,
+        &notifier,
+        &audit,
+        AssetKind::Aircraft,
+        &id,
+        &context,
+    )
+    .await
+}
+
+/// Restore a removed [`Vertiport`](crate::rest::structs::Vertiport).
+///
+/// Vertipads removed together with the vertiport have to be restored
+/// separately. Not served with the svc-storage backend, which can not
+/// restore assets.
+#[utoipa::path(
+    post,
+    path = "/assets/vertiports/{id}/restore",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Vertiport restored; a UUID is returned", body = String),
+        (status = 400, description = "Invalid vertiport id"),
+        (status = 404, description = "Vertiport has not been removed or has been purged")
+    ),
+    params(
+        ("id" = String, Path, description = "Vertiport id"),
+    )
+)]
+pub async fn restore_vertiport(
+    Extension(store): Extension<SharedStore>,
+    Extension(deletions): Extension<DeletionStore>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(audit): Extension<AuditLog>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<String, (StatusCode, String)> {
+    rest_info!("(restore_vertiport) entry [{}].", &id);
+    let context = AuditContext::from_headers(&headers);
+    restore(
+        store.as_ref(),
+        &deletions,
+        &notifier,
+        &audit,
+        AssetKind::Vertiport,
+        &id,
+        &context,
+    )
+    .await
+}
+
+/// Restore a removed [`Vertipad`](crate::rest::structs::Vertipad).
+///
+/// Not served with the svc-storage backend, which can not restore assets.
+#[utoipa::path(
+    post,
+    path = "/assets/vertipads/{id}/restore",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Vertipad restored; a UUID is returned", body = String),
+        (status = 400, description = "Invalid vertipad id"),
+        (status = 404, description = "Vertipad has not been removed or has been purged")
+    ),
+    params(
+        ("id" = String, Path, description = "Vertipad id"),
+    )
+)]
+pub async fn restore_vertipad(
+    Extension(store): Extension<SharedStore>,
+    Extension(deletions): Extension<DeletionStore>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(audit): Extension<AuditLog>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<String, (StatusCode, String)> {
+    rest_info!("(restore_vertipad) entry [{}].", &id);
+    let context = AuditContext::from_headers(&headers);
+    restore(
+        store.as_ref(),
+        &deletions,
+        &notifier,
+        &audit,
+        AssetKind::Vertipad,
+        &id,
+        &context,
+    )
+    .await
+}
+
+/// Returns the ids of the removed assets of a `kind`.
+async fn search_removed(
+    store: &dyn AssetStore,
+    kind: AssetKind,
+) -> Result<Vec<String>, (StatusCode, String)> {
+    let filter = AssetFilter::is_not_null("deleted_at");
+    let result = match kind {
+        AssetKind::Aircraft => store
+            .vehicles()
+            .search(&filter)
+            .await
+            .map(|list| list.into_iter().map(|object| object.id).collect()),
+        AssetKind::Vertiport => store
+            .vertiports()
+            .search(&filter)
+            .await
+            .map(|list| list.into_iter().map(|object| object.id).collect()),
+        AssetKind::Vertipad => store
+            .vertipads()
+            .search(&filter)
+            .await
+            .map(|list| list.into_iter().map(|object| object.id).collect()),
+        AssetKind::AssetGroup => Ok(vec![]),
+    };
+
+    result.map_err(|e| {
+        let error_msg = format!("could not retrieve removed {:?} assets.", kind);
+        rest_error!("(search_removed) {}: {}.", error_msg, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+    })
+}
+
+/// Get all removed assets which have not been purged yet, oldest removal
+/// first.
+///
+/// The time, actor and reason are only known for removals made by this
+/// instance, other removals are listed last.
+#[utoipa::path(
+    get,
+    path = "/assets/deleted",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Removed assets", body = [DeletionRecord]),
+        (status = 503, description = "Could not connect to other microservice dependencies")
+    ),
+    params(
+        DeletionFilter
+    )
+)]
+pub async fn get_deleted_assets(
+    Extension(store): Extension<SharedStore>,
+    Extension(deletions): Extension<DeletionStore>,
+    Query(filter): Query<DeletionFilter>,
+) -> Result<Json<Vec<DeletionRecord>>, (StatusCode, String)> {
+    rest_info!("(get_deleted_assets) entry {:?}.", filter);
+    let kinds = match filter.kind {
+        Some(kind) => vec![kind],
+        None => vec![
+            AssetKind::Aircraft,
+            AssetKind::Vertiport,
+            AssetKind::Vertipad,
+        ],
+    };
+
+    let mut list = vec![];
+    for kind in kinds {
+        for asset_id in search_removed(store.as_ref(), kind).await? {
+            let record = deletions.get(&asset_id).unwrap_or(DeletionRecord {
+                kind,
+                asset_id,
+                deleted_at: None,
+                deleted_by: None,
+                reason: None,
+            });
+            list.push(record);
+        }
+    }
+    list.sort_by_key(|record| (record.deleted_at.is_none(), record.deleted_at));
+    Ok(Json(list))
+}
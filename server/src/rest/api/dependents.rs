@@ -0,0 +1,85 @@
+//! Assets depending on a vertiport
+//!
+//! Vertipads reference their vertiport, and aircraft reference the
+//! vertiport or vertipad they are hangared at.
+
+use crate::store::{AssetFilter, AssetStore};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use svc_storage_client_grpc::prelude::*;
+use utoipa::ToSchema;
+
+/// Assets depending on a vertiport, affected by its removal.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VertiportRemoval {
+    /// The id of the vertiport.
+    pub vertiport_id: String,
+    /// Indicates if nothing has been removed or updated, only listed.
+    pub dry_run: bool,
+    /// The ids of the vertipads of the vertiport, removed on cascade.
+    pub vertipad_ids: Vec<String>,
+    /// The ids of the aircraft hangared at the vertiport, of which the
+    /// hangar references are cleared on cascade.
+    pub aircraft_ids: Vec<String>,
+}
+
+impl VertiportRemoval {
+    /// Check if any assets depend on the vertiport.
+    pub fn has_dependents(&self) -> bool {
+        !self.vertipad_ids.is_empty() || !self.aircraft_ids.is_empty()
+    }
+}
+
+/// Search the vehicles in storage matching `filter`.
+async fn search_vehicles(
+    store: &dyn AssetStore,
+    filter: AssetFilter,
+) -> Result<Vec<vehicle::Object>, (StatusCode, String)> {
+    match store.vehicles().search(&filter).await {
+        Ok(vehicles) => Ok(vehicles),
+        Err(e) => {
+            let error_msg = "could not retrieve vehicles.".to_string();
+            rest_error!("(search_vehicles) {}: {}.", error_msg, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg))
+        }
+    }
+}
+
+/// Search the vertipads of a vertiport which have not been deleted.
+pub(super) async fn search_vertiport_vertipads(
+    store: &dyn AssetStore,
+    vertiport_id: &str,
+) -> Result<Vec<vertipad::Object>, (StatusCode, String)> {
+    let filter = AssetFilter::equals("vertiport_id", vertiport_id).and_is_null("deleted_at");
+    match store.vertipads().search(&filter).await {
+        Ok(vertipads) => Ok(vertipads),
+        Err(e) => {
+            let error_msg = "could not retrieve vertipads.".to_string();
+            rest_error!("(search_vertiport_vertipads) {}: {}.", error_msg, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg))
+        }
+    }
+}
+
+/// Search the vehicles hangared at a vertiport, which have not been deleted.
+///
+/// Vehicles are hangared at the vertiport if their `hangar_id` is the
+/// vertiport, or their `hangar_bay_id` is one of its `vertipad_ids`.
+pub(super) async fn search_hangared_vehicles(
+    store: &dyn AssetStore,
+    vertiport_id: &str,
+    vertipad_ids: &[String],
+) -> Result<Vec<vehicle::Object>, (StatusCode, String)> {
+    let filter = AssetFilter::equals("hangar_id", vertiport_id).and_is_null("deleted_at");
+    let mut vehicles = search_vehicles(store, filter).await?;
+    if !vertipad_ids.is_empty() {
+        let filter =
+            AssetFilter::is_in("hangar_bay_id", vertipad_ids.to_vec()).and_is_null("deleted_at");
+        vehicles.extend(search_vehicles(store, filter).await?);
+    }
+
+    let mut seen = HashSet::new();
+    vehicles.retain(|vehicle| seen.insert(vehicle.id.clone()));
+    Ok(vehicles)
+}
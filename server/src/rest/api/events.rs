@@ -0,0 +1,81 @@
+//! REST API implementations for asset change events
+
+use crate::events::outbox::OutboxStats;
+use crate::events::{AssetEventFilter, ChangeNotifier};
+use axum::extract::Query;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::{Extension, Json};
+use futures::stream::{Stream, StreamExt};
+use hyper::StatusCode;
+
+/// Stream asset change events as Server-Sent Events.
+///
+/// Each event has the event's sequence number as `id`, the action as
+/// `event` name and the [`AssetEvent`](crate::events::AssetEvent) as
+/// JSON `data`. If the subscriber can not keep up and misses events, the
+/// stream ends with a `lagged` event, having the sequence number of the
+/// last event received as `id`.
+///
+/// Filtering on `operator_id` is rejected, as the owners of the assets are
+/// not known yet.
+#[utoipa::path(
+    get,
+    path = "/assets/events",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Stream of asset change events", content_type = "text/event-stream", body = AssetEvent),
+        (status = 400, description = "Filtering on operator_id is not supported yet")
+    ),
+    params(
+        AssetEventFilter
+    )
+)]
+pub async fn get_asset_events(
+    Extension(notifier): Extension<ChangeNotifier>,
+    Query(filter): Query<AssetEventFilter>,
+) -> Result<Sse<impl Stream<Item = Result<Event, serde_json::Error>>>, (StatusCode, String)> {
+    rest_info!("(get_asset_events) new subscriber with filter {:?}.", filter);
+
+    // TODO(R5): svc-storage does not store asset owners yet, so events
+    // never have an operator_id and this filter would never match
+    if filter.operator_id.is_some() {
+        rest_warn!("(get_asset_events) operator_id filter not supported.");
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "filtering on operator_id is not supported yet".to_string(),
+        ));
+    }
+
+    let stream = notifier.subscribe(filter).map(|result| match result {
+        Ok(event) => Event::default()
+            .id(event.sequence.to_string())
+            .event(event.action.as_str())
+            .json_data(&event),
+        Err(lagged) => Ok(Event::default()
+            .id(lagged.last.to_string())
+            .event("lagged")
+            .data(lagged.to_string())),
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Get statistics of the asset event outbox.
+#[utoipa::path(
+    get,
+    path = "/assets/events/outbox",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Outbox statistics", body = OutboxStats),
+        (status = 404, description = "Outbox not enabled")
+    )
+)]
+pub async fn get_outbox_stats(
+    Extension(notifier): Extension<ChangeNotifier>,
+) -> Result<Json<OutboxStats>, (StatusCode, String)> {
+    rest_debug!("(get_outbox_stats) entry.");
+    match notifier.outbox() {
+        Some(outbox) => Ok(Json(outbox.stats())),
+        None => Err((StatusCode::NOT_FOUND, "Outbox not enabled".to_string())),
+    }
+}
@@ -0,0 +1,76 @@
+//! REST API implementations for health checks
+
+use crate::health::{HealthMonitor, HealthReport};
+use axum::{Extension, Json};
+use hyper::StatusCode;
+
+/// Health check for load balancing
+///
+/// Checks the dependencies of the service, returning 503 if a required
+/// dependency is unavailable.
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Service is healthy, all dependencies running."),
+        (status = 503, description = "Service is unhealthy, one or more dependencies unavailable.")
+    )
+)]
+pub async fn health_check(Extension(health): Extension<HealthMonitor>) -> Result<(), StatusCode> {
+    rest_debug!("(health_check) entry.");
+    match health.refresh().await.ready {
+        true => {
+            rest_debug!("(health_check) healthy, all dependencies running.");
+            Ok(())
+        }
+        false => {
+            rest_error!("(health_check) unhealthy, 1+ dependencies down.");
+            Err(StatusCode::SERVICE_UNAVAILABLE)
+        }
+    }
+}
+
+/// Liveness check, succeeds as long as the service is running.
+///
+/// Dependencies are not checked, an unavailable dependency should not
+/// cause the service to be restarted.
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Service is running.")
+    )
+)]
+pub async fn health_live() -> StatusCode {
+    rest_debug!("(health_live) entry.");
+    StatusCode::OK
+}
+
+/// Readiness check, reporting the status and latency of each dependency.
+///
+/// The dependencies are checked again for each request. The service is
+/// ready if all required dependencies are available.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Service is ready, all required dependencies available.", body = HealthReport),
+        (status = 503, description = "Service is not ready, one or more required dependencies unavailable.", body = HealthReport)
+    )
+)]
+pub async fn health_ready(
+    Extension(health): Extension<HealthMonitor>,
+) -> (StatusCode, Json<HealthReport>) {
+    rest_debug!("(health_ready) entry.");
+    let report = health.refresh().await;
+    match report.ready {
+        true => (StatusCode::OK, Json(report)),
+        false => {
+            rest_warn!("(health_ready) not ready: {:?}", report.dependencies);
+            (StatusCode::SERVICE_UNAVAILABLE, Json(report))
+        }
+    }
+}
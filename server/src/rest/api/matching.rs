@@ -0,0 +1,225 @@
+//! REST API implementations for capability matching
+
+use super::deletions::check_not_removed;
+use super::dependents::{search_hangared_vehicles, search_vertiport_vertipads};
+use super::rest_types::CapableAircraftParams;
+use super::{apply_telemetry_status, is_uuid};
+use crate::catalog::VehicleCatalog;
+use crate::events::AssetKind;
+use crate::matching::{center, distance_km, is_capable, Mission};
+use crate::rest::structs::Aircraft;
+use crate::store::{AssetFilter, AssetStore, SharedStore};
+use crate::telemetry::TelemetryStore;
+use axum::{extract::Query, Extension, Json};
+use chrono::{DateTime, Utc};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use svc_storage_client_grpc::prelude::*;
+use tonic::Code;
+use utoipa::ToSchema;
+
+/// An aircraft able to fly a mission.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CapableAircraft {
+    /// The aircraft, with the data of its vehicle model.
+    pub aircraft: Aircraft,
+    /// The id of the vertiport the aircraft is hangared at.
+    pub vertiport_id: String,
+    /// Distance in kilometers between the vertiport the aircraft is
+    /// hangared at and the departure vertiport.
+    pub vertiport_distance_km: f64,
+}
+
+/// Search the vertiports within `radius_km` of `departure`, including
+/// `departure` itself, with their distance to `departure`.
+async fn search_nearby_vertiports(
+    store: &dyn AssetStore,
+    departure: (&str, &vertiport::Data),
+    radius_km: f64,
+) -> Result<Vec<(String, f64)>, (StatusCode, String)> {
+    let (departure_id, departure) = departure;
+    let mut nearby = vec![(departure_id.to_string(), 0.0)];
+    if radius_km <= 0.0 {
+        return Ok(nearby);
+    }
+    let Some(origin) = departure.geo_location.as_ref().and_then(center) else {
+        rest_warn!(
+            "(search_nearby_vertiports) vertiport [{}] has no location, only its own aircraft are searched.",
+            departure_id
+        );
+        return Ok(nearby);
+    };
+
+    let filter = AssetFilter::is_null("deleted_at");
+    let vertiports = match store.vertiports().search(&filter).await {
+        Ok(vertiports) => vertiports,
+        Err(e) => {
+            let error_msg = "could not retrieve vertiports.".to_string();
+            rest_error!("(search_nearby_vertiports) {}: {}.", error_msg, e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg));
+        }
+    };
+
+    for vertiport in vertiports {
+        if vertiport.id == departure_id {
+            continue;
+        }
+        let Some(location) = vertiport
+            .data
+            .as_ref()
+            .and_then(|data| data.geo_location.as_ref())
+            .and_then(center)
+        else {
+            continue;
+        };
+        let distance = distance_km(&origin, &location);
+        if distance <= radius_km {
+            nearby.push((vertiport.id, distance));
+        }
+    }
+
+    nearby.sort_by(|a, b| a.1.total_cmp(&b.1));
+    Ok(nearby)
+}
+
+/// Get the [`Aircraft`] able to fly a mission, nearest first.
+///
+/// Aircraft are capable if their vehicle model can carry the payload over
+/// the distance, they are available, they are not due for maintenance
+/// before the flight ends and they are hangared at the departure vertiport
+/// or a vertiport within `radius_km` of it. The flight of aircraft
+/// hangared at another vertiport includes the leg to the departure
+/// vertiport. Aircraft of which the vehicle
+/// model is not in the catalog are never capable.
+#[utoipa::path(
+    get,
+    path = "/assets/capable-aircraft",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Aircraft able to fly the mission", body = [CapableAircraft]),
+        (status = 400, description = "Invalid vertiport id, negative values or invalid departure time"),
+        (status = 404, description = "Departure vertiport not found in database"),
+        (status = 503, description = "Could not connect to other microservice dependencies")
+    ),
+    params(
+        CapableAircraftParams
+    )
+)]
+pub async fn get_capable_aircraft(
+    Extension(store): Extension<SharedStore>,
+    Extension(telemetry): Extension<TelemetryStore>,
+    Extension(catalog): Extension<VehicleCatalog>,
+    Query(params): Query<CapableAircraftParams>,
+) -> Result<Json<Vec<CapableAircraft>>, (StatusCode, String)> {
+    rest_info!("(get_capable_aircraft) entry {:?}.", params);
+    if !is_uuid(&params.vertiport_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid vertiport id".to_string()));
+    }
+
+    let radius_km = params.radius_km.unwrap_or_default();
+    let values = [
+        ("payload_kg", params.payload_kg),
+        ("distance_km", params.distance_km),
+        ("radius_km", radius_km),
+    ];
+    if let Some((field, value)) = values
+        .iter()
+        .find(|(_, value)| !value.is_finite() || *value < 0.0)
+    {
+        let error_msg = format!("{} must be a non-negative number, got {}.", field, value);
+        rest_warn!("(get_capable_aircraft) {}", &error_msg);
+        return Err((StatusCode::BAD_REQUEST, error_msg));
+    }
+
+    let departure = match &params.departure_time {
+        Some(time) => DateTime::parse_from_rfc3339(time)
+            .map(|time| time.with_timezone(&Utc))
+            .map_err(|e| {
+                let error_msg = format!("Invalid departure time [{}]: {}", time, e);
+                rest_warn!("(get_capable_aircraft) {}", &error_msg);
+                (StatusCode::BAD_REQUEST, error_msg)
+            })?,
+        None => Utc::now(),
+    };
+    let mission = Mission {
+        payload_kg: params.payload_kg,
+        distance_km: params.distance_km,
+        departure,
+    };
+
+    check_not_removed(store.as_ref(), AssetKind::Vertiport, &params.vertiport_id).await?;
+    let vertiport = match store.vertiports().get_by_id(&params.vertiport_id).await {
+        Ok(object) => object
+            .data
+            .ok_or((StatusCode::NOT_FOUND, "Vertiport not found".to_string()))?,
+        Err(e) if e.code() == Code::NotFound => {
+            return Err((StatusCode::NOT_FOUND, "Vertiport not found".to_string()))
+        }
+        Err(e) => {
+            let error_msg = format!("Error getting vertiport from storage: {}", e);
+            rest_error!("(get_capable_aircraft) {}", &error_msg);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg));
+        }
+    };
+
+    let vertiports = search_nearby_vertiports(
+        store.as_ref(),
+        (&params.vertiport_id, &vertiport),
+        radius_km,
+    )
+    .await?;
+
+    let mut seen = HashSet::new();
+    let mut capable = vec![];
+    for (vertiport_id, vertiport_distance_km) in vertiports {
+        let vertipad_ids: Vec<String> = search_vertiport_vertipads(store.as_ref(), &vertiport_id)
+            .await?
+            .into_iter()
+            .map(|vertipad| vertipad.id)
+            .collect();
+        let vehicles =
+            search_hangared_vehicles(store.as_ref(), &vertiport_id, &vertipad_ids).await?;
+
+        for vehicle in vehicles {
+            // An aircraft is listed at the nearest vertiport it is hangared at
+            if !seen.insert(vehicle.id.clone()) {
+                continue;
+            }
+            let mut aircraft: Aircraft = vehicle.try_into().map_err(|e| {
+                let error_msg = format!("could not convert VehicleObject to Aircraft: {}", e);
+                rest_error!("(get_capable_aircraft) {}", &error_msg);
+                (StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+            })?;
+            let Some(model) = catalog.get(&aircraft.vehicle_model_id) else {
+                rest_debug!(
+                    "(get_capable_aircraft) vehicle model [{}] of aircraft [{}] not in catalog.",
+                    aircraft.vehicle_model_id,
+                    aircraft.basics.id
+                );
+                continue;
+            };
+            aircraft.apply_vehicle_model(&model);
+            apply_telemetry_status(&telemetry, &mut aircraft);
+
+            // Aircraft hangared elsewhere first fly to the departure vertiport
+            let flight = Mission {
+                distance_km: mission.distance_km + vertiport_distance_km,
+                ..mission
+            };
+            if is_capable(&aircraft, &model, &flight) {
+                capable.push(CapableAircraft {
+                    aircraft,
+                    vertiport_id: vertiport_id.clone(),
+                    vertiport_distance_km,
+                });
+            }
+        }
+    }
+
+    rest_info!(
+        "(get_capable_aircraft) found {} capable aircraft.",
+        capable.len()
+    );
+    Ok(Json(capable))
+}
@@ -0,0 +1,54 @@
+//! REST API implementations for metrics
+
+use crate::events::ChangeNotifier;
+use crate::metrics::Metrics;
+use axum::{
+    body::Body,
+    extract::MatchedPath,
+    http::{header, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use std::time::Instant;
+
+/// Record the method, route, status code and duration of a request.
+///
+/// Must be added with `route_layer`, so the matched route is known.
+pub async fn track_requests(request: Request<Body>, next: Next<Body>) -> Response {
+    let start = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let metrics = request.extensions().get::<Metrics>().cloned();
+
+    let response = next.run(request).await;
+    if let Some(metrics) = metrics {
+        metrics.record_rest_request(&method, &route, response.status().as_u16(), start.elapsed());
+    }
+    response
+}
+
+/// Get the metrics of the service in the Prometheus text exposition format.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text exposition format", body = String)
+    )
+)]
+pub async fn get_metrics(
+    Extension(metrics): Extension<Metrics>,
+    Extension(notifier): Extension<ChangeNotifier>,
+) -> impl IntoResponse {
+    rest_debug!("(get_metrics) entry.");
+    let outbox = notifier.outbox().map(|outbox| outbox.stats());
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics.render(outbox.as_ref()),
+    )
+}
@@ -0,0 +1,351 @@
+//! REST API implementations for vertipad occupancy
+
+use super::deletions::check_not_removed;
+use super::is_uuid;
+use super::rest_types::{OccupyVertipadPayload, ReleaseVertipadPayload};
+use crate::audit::{AuditAction, AuditContext, AuditLog, FieldChange};
+use crate::cache::AssetCache;
+use crate::events::{AssetAction, AssetKind, ChangeNotifier};
+use crate::occupancy::{OccupancyError, OccupancyRecord, OccupancyStore};
+use crate::store::{AssetFilter, AssetStore, SharedStore};
+use axum::{extract::Path, Extension, Json};
+use hyper::{HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize};
+use svc_storage_client_grpc::prelude::*;
+use tonic::Code;
+use utoipa::ToSchema;
+
+/// Occupancy of a single vertipad of a vertiport.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VertipadOccupancy {
+    /// The id of the vertipad.
+    pub vertipad_id: String,
+    /// The name of the vertipad.
+    pub name: String,
+    /// Indicates if the vertipad is in business.
+    pub enabled: bool,
+    /// Indicates if the vertipad is currently occupied.
+    pub occupied: bool,
+    /// The current occupancy, if occupied through an occupy request.
+    pub occupancy: Option<OccupancyRecord>,
+}
+
+/// Current occupancy of all vertipads of a vertiport.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VertiportOccupancy {
+    /// The id of the vertiport.
+    pub vertiport_id: String,
+    /// Number of vertipads of the vertiport.
+    pub total: usize,
+    /// Number of occupied vertipads.
+    pub occupied: usize,
+    /// The occupancy of each vertipad.
+    pub vertipads: Vec<VertipadOccupancy>,
+}
+
+/// Get the stored data of a vertipad which has not been removed.
+pub(super) async fn get_vertipad_data(
+    store: &dyn AssetStore,
+    vertipad_id: &str,
+) -> Result<vertipad::Data, (StatusCode, String)> {
+    check_not_removed(store, AssetKind::Vertipad, vertipad_id).await?;
+    let object = match store.vertipads().get_by_id(vertipad_id).await {
+        Ok(object) => object,
+        Err(e) if e.code() == Code::NotFound => {
+            return Err((StatusCode::NOT_FOUND, "Vertipad not found".to_string()))
+        }
+        Err(e) => {
+            let error_msg = format!("Error getting vertipad from storage: {}", e);
+            rest_error!("(get_vertipad_data) {}", &error_msg);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg));
+        }
+    };
+
+    object
+        .data
+        .ok_or((StatusCode::NOT_FOUND, "Vertipad not found".to_string()))
+}
+
+/// Store the occupied flag of a vertipad, recording the change in the
+/// audit log.
+async fn set_occupied(
+    store: &dyn AssetStore,
+    cache: &AssetCache,
+    audit: &AuditLog,
+    context: &AuditContext,
+    vertipad_id: &str,
+    data: vertipad::Data,
+    occupied: bool,
+) -> Result<(), (StatusCode, String)> {
+    let change = FieldChange {
+        field: "occupied".to_string(),
+        before: serde_json::json!(data.occupied),
+        after: serde_json::json!(occupied),
+    };
+    let result = store
+        .vertipads()
+        .update(
+            vertipad_id,
+            vertipad::Data {
+                occupied,
+                created_at: None,
+                updated_at: None,
+                ..data
+            },
+            vec!["occupied".to_string()],
+        )
+        .await;
+    cache.invalidate(AssetKind::Vertipad, vertipad_id);
+    if let Err(e) = result {
+        let error_msg = format!("Error updating vertipad in storage: {}", e);
+        rest_error!("(set_occupied) {}", &error_msg);
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg));
+    }
+
+    audit.record(
+        AssetKind::Vertipad,
+        vertipad_id,
+        AuditAction::Updated,
+        context,
+        vec![change],
+    );
+    Ok(())
+}
+
+/// Map an [`OccupancyError`] to a REST error.
+fn occupancy_error(error: OccupancyError) -> (StatusCode, String) {
+    rest_warn!("(occupancy_error) {}", error);
+    (StatusCode::CONFLICT, error.to_string())
+}
+
+/// Occupy a [`Vertipad`](crate::rest::structs::Vertipad) with an aircraft.
+#[utoipa::path(
+    post,
+    path = "/assets/vertipads/{id}/occupy",
+    tag = "svc-assets",
+    request_body = OccupyVertipadPayload,
+    responses(
+        (status = 200, description = "Vertipad occupied", body = OccupancyRecord),
+        (status = 400, description = "Invalid vertipad, aircraft or flight id"),
+        (status = 404, description = "Vertipad not found in database"),
+        (status = 409, description = "Vertipad is already occupied"),
+        (status = 503, description = "Could not connect to other microservice dependencies")
+    ),
+    params(
+        ("id" = String, Path, description = "Vertipad id"),
+    )
+)]
+pub async fn occupy_vertipad(
+    Extension(store): Extension<SharedStore>,
+    Extension(cache): Extension<AssetCache>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(occupancy): Extension<OccupancyStore>,
+    Extension(audit): Extension<AuditLog>,
+    headers: HeaderMap,
+    Path(vertipad_id): Path<String>,
+    Json(payload): Json<OccupyVertipadPayload>,
+) -> Result<Json<OccupancyRecord>, (StatusCode, String)> {
+    rest_info!("(occupy_vertipad) entry [{}].", vertipad_id);
+    rest_debug!("(occupy_vertipad) Payload: {:?}", &payload);
+    if !is_uuid(&vertipad_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid vertipad id".to_string()));
+    }
+    if !is_uuid(&payload.aircraft_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid aircraft id".to_string()));
+    }
+    if let Some(flight_id) = &payload.flight_id {
+        if !is_uuid(flight_id) {
+            return Err((StatusCode::BAD_REQUEST, "Invalid flight id".to_string()));
+        }
+    }
+
+    let data = get_vertipad_data(store.as_ref(), &vertipad_id).await?;
+    // The vertipad may have been occupied before a restart or through
+    // another instance of the service
+    if data.occupied && occupancy.current(&vertipad_id).is_none() {
+        return Err(occupancy_error(OccupancyError::OccupiedInStorage));
+    }
+    let record = occupancy
+        .occupy(&vertipad_id, &payload.aircraft_id, payload.flight_id)
+        .map_err(occupancy_error)?;
+
+    let context = AuditContext::from_headers(&headers);
+    let result = set_occupied(
+        store.as_ref(),
+        &cache,
+        &audit,
+        &context,
+        &vertipad_id,
+        data,
+        true,
+    )
+    .await;
+    if let Err(e) = result {
+        occupancy.undo(&record);
+        return Err(e);
+    }
+
+    notifier.emit(
+        AssetKind::Vertipad,
+        AssetAction::Updated,
+        &vertipad_id,
+        Some(serde_json::json!({ "occupancy": &record })),
+    );
+    Ok(Json(record))
+}
+
+/// Release a [`Vertipad`](crate::rest::structs::Vertipad) occupied by an aircraft.
+#[utoipa::path(
+    post,
+    path = "/assets/vertipads/{id}/release",
+    tag = "svc-assets",
+    request_body = ReleaseVertipadPayload,
+    responses(
+        (status = 200, description = "Vertipad released", body = OccupancyRecord),
+        (status = 400, description = "Invalid vertipad or aircraft id"),
+        (status = 404, description = "Vertipad not found in database"),
+        (status = 409, description = "Vertipad is not occupied by the aircraft"),
+        (status = 503, description = "Could not connect to other microservice dependencies")
+    ),
+    params(
+        ("id" = String, Path, description = "Vertipad id"),
+    )
+)]
+pub async fn release_vertipad(
+    Extension(store): Extension<SharedStore>,
+    Extension(cache): Extension<AssetCache>,
+    Extension(notifier): Extension<ChangeNotifier>,
+    Extension(occupancy): Extension<OccupancyStore>,
+    Extension(audit): Extension<AuditLog>,
+    headers: HeaderMap,
+    Path(vertipad_id): Path<String>,
+    Json(payload): Json<ReleaseVertipadPayload>,
+) -> Result<Json<OccupancyRecord>, (StatusCode, String)> {
+    rest_info!("(release_vertipad) entry [{}].", vertipad_id);
+    rest_debug!("(release_vertipad) Payload: {:?}", &payload);
+    if !is_uuid(&vertipad_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid vertipad id".to_string()));
+    }
+    if !is_uuid(&payload.aircraft_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid aircraft id".to_string()));
+    }
+
+    let data = get_vertipad_data(store.as_ref(), &vertipad_id).await?;
+    // The vertipad may have been occupied before a restart or through
+    // another instance of the service
+    let record = match occupancy.release(&vertipad_id, &payload.aircraft_id) {
+        Err(OccupancyError::NotOccupied) if data.occupied => {
+            occupancy.release_unrecorded(&vertipad_id, &payload.aircraft_id)
+        }
+        result => result,
+    }
+    .map_err(occupancy_error)?;
+
+    let context = AuditContext::from_headers(&headers);
+    let result = set_occupied(
+        store.as_ref(),
+        &cache,
+        &audit,
+        &context,
+        &vertipad_id,
+        data,
+        false,
+    )
+    .await;
+    if let Err(e) = result {
+        occupancy.undo(&record);
+        return Err(e);
+    }
+
+    notifier.emit(
+        AssetKind::Vertipad,
+        AssetAction::Updated,
+        &vertipad_id,
+        Some(serde_json::json!({ "occupancy": &record })),
+    );
+    Ok(Json(record))
+}
+
+/// Get the occupancy history of a [`Vertipad`](crate::rest::structs::Vertipad),
+/// oldest first.
+#[utoipa::path(
+    get,
+    path = "/assets/vertipads/{id}/occupancy",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Occupancy history of vertipad {id}", body = [OccupancyRecord]),
+        (status = 400, description = "Invalid vertipad id")
+    ),
+    params(
+        ("id" = String, Path, description = "Vertipad id"),
+    )
+)]
+pub async fn get_vertipad_occupancy_history(
+    Extension(occupancy): Extension<OccupancyStore>,
+    Path(vertipad_id): Path<String>,
+) -> Result<Json<Vec<OccupancyRecord>>, (StatusCode, String)> {
+    rest_info!("(get_vertipad_occupancy_history) entry [{}].", vertipad_id);
+    if !is_uuid(&vertipad_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid vertipad id".to_string()));
+    }
+
+    Ok(Json(occupancy.history(&vertipad_id)))
+}
+
+/// Get the current occupancy of all vertipads of a
+/// [`Vertiport`](crate::rest::structs::Vertiport).
+#[utoipa::path(
+    get,
+    path = "/assets/vertiports/{id}/occupancy",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Occupancy of vertiport {id}", body = VertiportOccupancy),
+        (status = 400, description = "Invalid vertiport id"),
+        (status = 503, description = "Could not connect to other microservice dependencies")
+    ),
+    params(
+        ("id" = String, Path, description = "Vertiport id"),
+    )
+)]
+pub async fn get_vertiport_occupancy(
+    Extension(store): Extension<SharedStore>,
+    Extension(occupancy): Extension<OccupancyStore>,
+    Path(vertiport_id): Path<String>,
+) -> Result<Json<VertiportOccupancy>, (StatusCode, String)> {
+    rest_info!("(get_vertiport_occupancy) entry [{}].", vertiport_id);
+    if !is_uuid(&vertiport_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid vertiport id".to_string()));
+    }
+
+    let filter = AssetFilter::equals("vertiport_id", &vertiport_id).and_is_null("deleted_at");
+    let vertipads = match store.vertipads().search(&filter).await {
+        Ok(vertipads) => vertipads,
+        Err(e) => {
+            let error_msg = "could not retrieve vertipads.".to_string();
+            rest_error!("(get_vertiport_occupancy) {}: {}.", error_msg, e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg));
+        }
+    };
+
+    let vertipads: Vec<VertipadOccupancy> = vertipads
+        .into_iter()
+        .filter_map(|vertipad| {
+            let data = vertipad.data?;
+            let current = occupancy.current(&vertipad.id);
+            Some(VertipadOccupancy {
+                occupied: data.occupied || current.is_some(),
+                vertipad_id: vertipad.id,
+                name: data.name,
+                enabled: data.enabled,
+                occupancy: current,
+            })
+        })
+        .collect();
+
+    Ok(Json(VertiportOccupancy {
+        vertiport_id,
+        total: vertipads.len(),
+        occupied: vertipads.iter().filter(|pad| pad.occupied).count(),
+        vertipads,
+    }))
+}
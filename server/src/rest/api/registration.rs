@@ -0,0 +1,112 @@
+//! REST API helpers for aircraft registration and serial numbers
+
+use crate::registration::{normalize_mark, RegistrationValidators};
+use crate::store::{AssetFilter, AssetStore};
+use hyper::StatusCode;
+use svc_storage_client_grpc::prelude::*;
+
+/// Returns a normalized identifier of a vehicle.
+type Identifier = fn(&vehicle::Data) -> String;
+
+/// Get all vehicles, including removed ones, as they may still be
+/// restored.
+async fn search_vehicles(
+    store: &dyn AssetStore,
+) -> Result<Vec<vehicle::Object>, (StatusCode, String)> {
+    match store.vehicles().search(&AssetFilter::default()).await {
+        Ok(vehicles) => Ok(vehicles),
+        Err(e) => {
+            let error_msg = "could not retrieve vehicles.".to_string();
+            rest_error!("(search_vehicles) {}: {}.", error_msg, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg))
+        }
+    }
+}
+
+/// Returns the ids of the `vehicles`, other than `aircraft_id`, of which
+/// the normalized identifier returned by `identifier` equals `value`.
+fn duplicates(
+    vehicles: &[vehicle::Object],
+    identifier: Identifier,
+    value: &str,
+    aircraft_id: Option<&str>,
+) -> Vec<String> {
+    vehicles
+        .iter()
+        .filter(|vehicle| Some(vehicle.id.as_str()) != aircraft_id)
+        .filter(|vehicle| vehicle.data.as_ref().map(identifier).as_deref() == Some(value))
+        .map(|vehicle| vehicle.id.clone())
+        .collect()
+}
+
+/// Validate the registration number of `data`, normalizing it, and check
+/// that its registration and serial number are not used by any other
+/// aircraft.
+///
+/// If a `mask` is provided, only the fields in the mask are checked.
+/// Stored numbers may predate their normalization, so they are normalized
+/// as well before comparing them, and all vehicles are compared.
+///
+/// Uniqueness is not enforced by the store, and the check is not atomic
+/// with the following write: concurrent requests using the same number
+/// may all pass the check.
+pub(super) async fn check_identifiers(
+    store: &dyn AssetStore,
+    validators: &RegistrationValidators,
+    aircraft_id: Option<&str>,
+    data: &mut vehicle::Data,
+    mask: Option<&[String]>,
+) -> Result<(), (StatusCode, String)> {
+    let checked = |field: &str| match mask {
+        Some(mask) => mask.iter().any(|path| path == field),
+        None => true,
+    };
+
+    if checked("registration_number") {
+        data.registration_number =
+            validators
+                .validate(&data.registration_number)
+                .map_err(|error_msg| {
+                    rest_warn!("(check_identifiers) {}", &error_msg);
+                    (StatusCode::BAD_REQUEST, error_msg)
+                })?;
+    }
+
+    let fields: [(&str, Identifier); 2] = [
+        ("registration_number", |data| {
+            normalize_mark(&data.registration_number)
+        }),
+        ("serial_number", |data| {
+            data.serial_number.trim().to_string()
+        }),
+    ];
+    let mut vehicles = None;
+    for (field, identifier) in fields {
+        let value = identifier(data);
+        if !checked(field) || value.is_empty() {
+            continue;
+        }
+
+        if vehicles.is_none() {
+            vehicles = Some(search_vehicles(store).await?);
+        }
+        let duplicates = duplicates(
+            vehicles.as_deref().unwrap_or_default(),
+            identifier,
+            &value,
+            aircraft_id,
+        );
+        if !duplicates.is_empty() {
+            let error_msg = format!(
+                "{} [{}] is already used by aircraft [{}].",
+                field,
+                value,
+                duplicates.join(", ")
+            );
+            rest_warn!("(check_identifiers) {}", &error_msg);
+            return Err((StatusCode::CONFLICT, error_msg));
+        }
+    }
+
+    Ok(())
+}
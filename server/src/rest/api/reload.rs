@@ -0,0 +1,29 @@
+//! REST API implementations for configuration reloads
+
+use crate::reload::{ConfigReloader, ReloadReport};
+use axum::{Extension, Json};
+use hyper::StatusCode;
+
+/// Reload the configuration file and environment variables.
+///
+/// Rate limits, the CORS allowed origins and the log configuration are
+/// applied right away. Other changed options are reported as requiring a
+/// restart, and a log configuration which does not load as failed.
+#[utoipa::path(
+    post,
+    path = "/admin/config/reload",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Configuration reloaded", body = ReloadReport),
+        (status = 400, description = "Invalid configuration, nothing applied")
+    )
+)]
+pub async fn reload_config(
+    Extension(reloader): Extension<ConfigReloader>,
+) -> Result<Json<ReloadReport>, (StatusCode, String)> {
+    rest_debug!("(reload_config) entry.");
+    match reloader.reload().await {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => Err((StatusCode::BAD_REQUEST, e.to_string())),
+    }
+}
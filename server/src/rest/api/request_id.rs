@@ -0,0 +1,86 @@
+//! REST API implementations for request ids
+
+use crate::request_id::{RequestId, REQUEST_ID_HEADER};
+use axum::{
+    body::{self, Body},
+    http::{header, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
+
+/// Assign an id to a request, using its `X-Request-Id` header if valid.
+///
+/// The id replaces the request's `X-Request-Id` header and is added to the
+/// request extensions and the response headers. The request is handled in
+/// the scope of the id, so all log lines written while handling it include
+/// the id. Error messages returned to the client end with the id.
+pub async fn assign_request_id(mut request: Request<Body>, next: Next<Body>) -> Response {
+    let start = Instant::now();
+    let request_id = RequestId::from_headers(request.headers()).unwrap_or_default();
+    let header_value = HeaderValue::from_str(request_id.as_str()).ok();
+    if let Some(value) = &header_value {
+        request
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER, value.clone());
+    }
+    request.extensions_mut().insert(request_id.clone());
+
+    let method = request.method().clone();
+    let uri = request.uri().clone();
+    let response = request_id
+        .clone()
+        .scope(async move {
+            rest_debug!("(assign_request_id) {} {} started.", method, uri);
+            let response = next.run(request).await;
+            rest_info!(
+                "(assign_request_id) {} {} returned {} in {:?}.",
+                method,
+                uri,
+                response.status().as_u16(),
+                start.elapsed()
+            );
+            response
+        })
+        .await;
+
+    let mut response = append_to_error(response, &request_id).await;
+    if let Some(value) = header_value {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Append the request id to the message of a plain text error response.
+async fn append_to_error(response: Response, request_id: &RequestId) -> Response {
+    let is_text = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.starts_with("text/plain"))
+        .unwrap_or(true);
+    let status = response.status();
+    if !is_text || !(status.is_client_error() || status.is_server_error()) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let message = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).trim_end().to_string(),
+        Err(e) => {
+            rest_warn!("(append_to_error) could not read error message: {}", e);
+            String::new()
+        }
+    };
+    let message = match message.is_empty() {
+        true => format!("request id: {}", request_id),
+        false => format!("{} (request id: {})", message, request_id),
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    Response::from_parts(parts, body::boxed(Body::from(message)))
+}
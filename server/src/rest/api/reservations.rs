@@ -0,0 +1,261 @@
+//! REST API implementations for vertipad reservations
+
+use super::is_uuid;
+use super::occupancy::get_vertipad_data;
+use super::rest_types::{CreateReservationPayload, ExtendReservationPayload};
+use crate::reservations::schedule::Schedule;
+use crate::reservations::{
+    ConflictReason, Reservation, ReservationConflict, ReservationError, ReservationStore,
+};
+use crate::store::{AssetStore, SharedStore};
+use axum::{
+    extract::Path,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use hyper::StatusCode;
+use uuid::Uuid;
+
+/// Parse an RFC 3339 timestamp of a request.
+fn parse_time(field: &str, value: &str) -> Result<DateTime<Utc>, Response> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| {
+            let error_msg = format!("Invalid {} time [{}]: {}", field, value, e);
+            rest_warn!("(parse_time) {}", &error_msg);
+            (StatusCode::BAD_REQUEST, error_msg).into_response()
+        })
+}
+
+/// Check the window from `start` to `end` against the state and schedule
+/// of a vertipad, returning the reasons the window can not be reserved.
+async fn check_vertipad(
+    store: &dyn AssetStore,
+    vertipad_id: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<ConflictReason>, Response> {
+    let data = get_vertipad_data(store, vertipad_id)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let mut reasons = vec![];
+    if !data.enabled {
+        reasons.push(ConflictReason::VertipadDisabled);
+    }
+
+    // Vertipads without a schedule are always available
+    if let Some(schedule) = data.schedule.filter(|s| !s.trim().is_empty()) {
+        let schedule: Schedule = schedule.parse().map_err(|e| {
+            let error_msg = format!("Vertipad [{}] has an {}", vertipad_id, e);
+            rest_error!("(check_vertipad) {}", &error_msg);
+            (StatusCode::INTERNAL_SERVER_ERROR, error_msg).into_response()
+        })?;
+        if !schedule.contains(start, end) {
+            reasons.push(ConflictReason::OutsideSchedule);
+        }
+    }
+
+    Ok(reasons)
+}
+
+/// Map a [`ReservationError`] to a REST error.
+///
+/// Conflicts are returned as a
+/// [`ReservationConflict`](crate::reservations::ReservationConflict).
+fn reservation_error(error: ReservationError) -> Response {
+    rest_warn!("(reservation_error) {}", error);
+    match error {
+        ReservationError::NotFound => (StatusCode::NOT_FOUND, error.to_string()).into_response(),
+        ReservationError::Conflict(conflict) => {
+            (StatusCode::CONFLICT, Json(conflict)).into_response()
+        }
+        ReservationError::File(_) => {
+            (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response()
+        }
+    }
+}
+
+/// Reserve a [`Vertipad`](crate::rest::structs::Vertipad) for a landing or
+/// takeoff window.
+#[utoipa::path(
+    post,
+    path = "/assets/vertipads/{id}/reservations",
+    tag = "svc-assets",
+    request_body = CreateReservationPayload,
+    responses(
+        (status = 200, description = "Vertipad reserved", body = Reservation),
+        (status = 400, description = "Invalid vertipad, aircraft or flight id, or invalid window"),
+        (status = 404, description = "Vertipad not found in database"),
+        (status = 409, description = "Window can not be reserved", body = ReservationConflict),
+        (status = 503, description = "Could not connect to other microservice dependencies")
+    ),
+    params(
+        ("id" = String, Path, description = "Vertipad id"),
+    )
+)]
+pub async fn create_reservation(
+    Extension(store): Extension<SharedStore>,
+    Extension(reservations): Extension<ReservationStore>,
+    Path(vertipad_id): Path<String>,
+    Json(payload): Json<CreateReservationPayload>,
+) -> Result<Json<Reservation>, Response> {
+    rest_info!("(create_reservation) entry [{}].", vertipad_id);
+    rest_debug!("(create_reservation) Payload: {:?}", &payload);
+    if !is_uuid(&vertipad_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid vertipad id").into_response());
+    }
+    if !is_uuid(&payload.aircraft_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid aircraft id").into_response());
+    }
+    if let Some(flight_id) = &payload.flight_id {
+        if !is_uuid(flight_id) {
+            return Err((StatusCode::BAD_REQUEST, "Invalid flight id").into_response());
+        }
+    }
+
+    let start = parse_time("start", &payload.start)?;
+    let end = parse_time("end", &payload.end)?;
+    if start >= end {
+        return Err((StatusCode::BAD_REQUEST, "Start must be before end").into_response());
+    }
+    if end <= Utc::now() {
+        return Err((StatusCode::BAD_REQUEST, "End must be in the future").into_response());
+    }
+
+    let reasons = check_vertipad(store.as_ref(), &vertipad_id, start, end).await?;
+    let reservation = Reservation {
+        id: Uuid::new_v4().to_string(),
+        vertipad_id,
+        aircraft_id: payload.aircraft_id,
+        flight_id: payload.flight_id,
+        start,
+        end,
+        created_at: Utc::now(),
+    };
+
+    reservations
+        .create(reservation, reasons)
+        .map(Json)
+        .map_err(reservation_error)
+}
+
+/// Extend a reservation of a [`Vertipad`](crate::rest::structs::Vertipad).
+#[utoipa::path(
+    put,
+    path = "/assets/vertipads/{id}/reservations/{reservation_id}",
+    tag = "svc-assets",
+    request_body = ExtendReservationPayload,
+    responses(
+        (status = 200, description = "Reservation extended", body = Reservation),
+        (status = 400, description = "Invalid vertipad or reservation id, or invalid end"),
+        (status = 404, description = "Vertipad or reservation not found"),
+        (status = 409, description = "Window can not be reserved", body = ReservationConflict),
+        (status = 503, description = "Could not connect to other microservice dependencies")
+    ),
+    params(
+        ("id" = String, Path, description = "Vertipad id"),
+        ("reservation_id" = String, Path, description = "Reservation id"),
+    )
+)]
+pub async fn extend_reservation(
+    Extension(store): Extension<SharedStore>,
+    Extension(reservations): Extension<ReservationStore>,
+    Path((vertipad_id, reservation_id)): Path<(String, String)>,
+    Json(payload): Json<ExtendReservationPayload>,
+) -> Result<Json<Reservation>, Response> {
+    rest_info!(
+        "(extend_reservation) entry [{}] [{}].",
+        vertipad_id,
+        reservation_id
+    );
+    rest_debug!("(extend_reservation) Payload: {:?}", &payload);
+    if !is_uuid(&vertipad_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid vertipad id").into_response());
+    }
+    if !is_uuid(&reservation_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid reservation id").into_response());
+    }
+
+    let end = parse_time("end", &payload.end)?;
+    let Some(reservation) = reservations.get(&vertipad_id, &reservation_id) else {
+        return Err(reservation_error(ReservationError::NotFound));
+    };
+    if end <= reservation.end {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "End must be after the current end of the reservation",
+        )
+            .into_response());
+    }
+
+    let reasons = check_vertipad(store.as_ref(), &vertipad_id, reservation.start, end).await?;
+    reservations
+        .extend(&vertipad_id, &reservation_id, end, reasons)
+        .map(Json)
+        .map_err(reservation_error)
+}
+
+/// Cancel a reservation of a [`Vertipad`](crate::rest::structs::Vertipad).
+#[utoipa::path(
+    delete,
+    path = "/assets/vertipads/{id}/reservations/{reservation_id}",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Reservation cancelled", body = Reservation),
+        (status = 400, description = "Invalid vertipad or reservation id"),
+        (status = 404, description = "Reservation not found")
+    ),
+    params(
+        ("id" = String, Path, description = "Vertipad id"),
+        ("reservation_id" = String, Path, description = "Reservation id"),
+    )
+)]
+pub async fn cancel_reservation(
+    Extension(reservations): Extension<ReservationStore>,
+    Path((vertipad_id, reservation_id)): Path<(String, String)>,
+) -> Result<Json<Reservation>, Response> {
+    rest_info!(
+        "(cancel_reservation) entry [{}] [{}].",
+        vertipad_id,
+        reservation_id
+    );
+    if !is_uuid(&vertipad_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid vertipad id").into_response());
+    }
+    if !is_uuid(&reservation_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid reservation id").into_response());
+    }
+
+    reservations
+        .cancel(&vertipad_id, &reservation_id)
+        .map(Json)
+        .map_err(reservation_error)
+}
+
+/// Get the reservations of a [`Vertipad`](crate::rest::structs::Vertipad)
+/// which have not ended yet, ordered by start time.
+#[utoipa::path(
+    get,
+    path = "/assets/vertipads/{id}/reservations",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Reservations of vertipad {id}", body = [Reservation]),
+        (status = 400, description = "Invalid vertipad id")
+    ),
+    params(
+        ("id" = String, Path, description = "Vertipad id"),
+    )
+)]
+pub async fn get_reservations(
+    Extension(reservations): Extension<ReservationStore>,
+    Path(vertipad_id): Path<String>,
+) -> Result<Json<Vec<Reservation>>, (StatusCode, String)> {
+    rest_info!("(get_reservations) entry [{}].", vertipad_id);
+    if !is_uuid(&vertipad_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid vertipad id".to_string()));
+    }
+
+    Ok(Json(reservations.list(&vertipad_id)))
+}
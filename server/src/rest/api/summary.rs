@@ -0,0 +1,191 @@
+//! REST API implementations for vertiport summaries
+
+use super::deletions::check_not_removed;
+use super::dependents::{search_hangared_vehicles, search_vertiport_vertipads};
+use super::{apply_telemetry_status, is_uuid};
+use crate::events::AssetKind;
+use crate::occupancy::OccupancyStore;
+use crate::reservations::ReservationStore;
+use crate::rest::structs::{Aircraft, AssetStatus};
+use crate::store::SharedStore;
+use crate::telemetry::TelemetryStore;
+use axum::{extract::Path, Extension, Json};
+use chrono::Utc;
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use tonic::Code;
+use utoipa::ToSchema;
+
+/// Current state of a single vertipad of a vertiport.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VertipadSummary {
+    /// The id of the vertipad.
+    pub vertipad_id: String,
+    /// The name of the vertipad.
+    pub name: String,
+    /// Indicates if the vertipad is in business.
+    pub enabled: bool,
+    /// Indicates if the vertipad is currently occupied.
+    pub occupied: bool,
+    /// Indicates if the vertipad is currently reserved.
+    pub reserved: bool,
+    /// Indicates if the vertipad is enabled, not occupied and not reserved.
+    pub available: bool,
+}
+
+/// An aircraft hangared at a vertiport.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct HangaredAircraft {
+    /// The id of the aircraft.
+    pub aircraft_id: String,
+    /// The registration number of the aircraft.
+    pub registration_number: String,
+    /// The id of the vertipad the aircraft is hangared at, if any.
+    pub hangar_bay_id: Option<String>,
+    /// The current status of the aircraft.
+    pub status: AssetStatus,
+}
+
+/// Capacity and current availability of a vertiport.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VertiportSummary {
+    /// The id of the vertiport.
+    pub vertiport_id: String,
+    /// The name of the vertiport.
+    pub name: String,
+    /// Number of vertipads of the vertiport.
+    pub vertipads_total: usize,
+    /// Number of enabled vertipads.
+    pub vertipads_enabled: usize,
+    /// Number of occupied vertipads.
+    pub vertipads_occupied: usize,
+    /// Number of currently reserved vertipads.
+    pub vertipads_reserved: usize,
+    /// Number of vertipads which are currently available.
+    pub vertipads_available: usize,
+    /// Number of aircraft hangared at the vertiport.
+    pub aircraft_total: usize,
+    /// Number of hangared aircraft which are currently available.
+    pub aircraft_available: usize,
+    /// The state of each vertipad.
+    pub vertipads: Vec<VertipadSummary>,
+    /// The aircraft hangared at the vertiport.
+    pub aircraft: Vec<HangaredAircraft>,
+}
+
+/// Get the capacity and current availability of a
+/// [`Vertiport`](crate::rest::structs::Vertiport), including its vertipads
+/// and the aircraft hangared at the vertiport.
+///
+/// Aircraft are hangared at the vertiport if their `hangar_id` is the
+/// vertiport, or their `hangar_bay_id` is one of its vertipads.
+#[utoipa::path(
+    get,
+    path = "/assets/vertiports/{id}/summary",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Summary of vertiport {id}", body = VertiportSummary),
+        (status = 400, description = "Invalid vertiport id"),
+        (status = 404, description = "Vertiport not found in database"),
+        (status = 503, description = "Could not connect to other microservice dependencies")
+    ),
+    params(
+        ("id" = String, Path, description = "Vertiport id"),
+    )
+)]
+pub async fn get_vertiport_summary(
+    Extension(store): Extension<SharedStore>,
+    Extension(telemetry): Extension<TelemetryStore>,
+    Extension(occupancy): Extension<OccupancyStore>,
+    Extension(reservations): Extension<ReservationStore>,
+    Path(vertiport_id): Path<String>,
+) -> Result<Json<VertiportSummary>, (StatusCode, String)> {
+    rest_info!("(get_vertiport_summary) entry [{}].", vertiport_id);
+    if !is_uuid(&vertiport_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid vertiport id".to_string()));
+    }
+    check_not_removed(store.as_ref(), AssetKind::Vertiport, &vertiport_id).await?;
+
+    let vertiport = match store.vertiports().get_by_id(&vertiport_id).await {
+        Ok(object) => object
+            .data
+            .ok_or((StatusCode::NOT_FOUND, "Vertiport not found".to_string()))?,
+        Err(e) if e.code() == Code::NotFound => {
+            return Err((StatusCode::NOT_FOUND, "Vertiport not found".to_string()))
+        }
+        Err(e) => {
+            let error_msg = format!("Error getting vertiport from storage: {}", e);
+            rest_error!("(get_vertiport_summary) {}", &error_msg);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, error_msg));
+        }
+    };
+
+    //
+    // Vertipads
+    //
+    let vertipads = search_vertiport_vertipads(store.as_ref(), &vertiport_id).await?;
+
+    let now = Utc::now();
+    let vertipads: Vec<VertipadSummary> = vertipads
+        .into_iter()
+        .filter_map(|vertipad| {
+            let data = vertipad.data?;
+            let occupied = data.occupied || occupancy.current(&vertipad.id).is_some();
+            let reserved = reservations.active(&vertipad.id, now).is_some();
+            Some(VertipadSummary {
+                available: data.enabled && !occupied && !reserved,
+                vertipad_id: vertipad.id,
+                name: data.name,
+                enabled: data.enabled,
+                occupied,
+                reserved,
+            })
+        })
+        .collect();
+
+    //
+    // Aircraft
+    //
+    let vertipad_ids: Vec<String> = vertipads
+        .iter()
+        .map(|vertipad| vertipad.vertipad_id.clone())
+        .collect();
+    let vehicles = search_hangared_vehicles(store.as_ref(), &vertiport_id, &vertipad_ids).await?;
+
+    let mut aircraft = vec![];
+    for vehicle in vehicles {
+        let hangar_bay_id = vehicle
+            .data
+            .as_ref()
+            .and_then(|data| data.hangar_bay_id.clone());
+        let mut object: Aircraft = vehicle.try_into().map_err(|e| {
+            let error_msg = format!("could not convert VehicleObject to Aircraft: {}", e);
+            rest_error!("(get_vertiport_summary) {}", &error_msg);
+            (StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+        })?;
+        apply_telemetry_status(&telemetry, &mut object);
+        aircraft.push(HangaredAircraft {
+            aircraft_id: object.basics.id,
+            registration_number: object.registration_number,
+            hangar_bay_id,
+            status: object.basics.status,
+        });
+    }
+
+    Ok(Json(VertiportSummary {
+        vertiport_id,
+        name: vertiport.name,
+        vertipads_total: vertipads.len(),
+        vertipads_enabled: vertipads.iter().filter(|pad| pad.enabled).count(),
+        vertipads_occupied: vertipads.iter().filter(|pad| pad.occupied).count(),
+        vertipads_reserved: vertipads.iter().filter(|pad| pad.reserved).count(),
+        vertipads_available: vertipads.iter().filter(|pad| pad.available).count(),
+        aircraft_total: aircraft.len(),
+        aircraft_available: aircraft
+            .iter()
+            .filter(|a| a.status == AssetStatus::Available)
+            .count(),
+        vertipads,
+        aircraft,
+    }))
+}
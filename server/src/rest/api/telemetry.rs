@@ -0,0 +1,66 @@
+//! REST API implementations for live aircraft telemetry
+
+use super::is_uuid;
+use crate::telemetry::status::StatusChange;
+use crate::telemetry::{AircraftTelemetry, TelemetryStore};
+use axum::{extract::Path, Extension, Json};
+use hyper::StatusCode;
+
+/// Get the live state of an [`Aircraft`](crate::rest::structs::Aircraft),
+/// derived from the MAVLink telemetry received for it.
+#[utoipa::path(
+    get,
+    path = "/assets/aircraft/{id}/telemetry",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Telemetry of aircraft {id} found", body = AircraftTelemetry),
+        (status = 404, description = "No telemetry received for aircraft"),
+        (status = 400, description = "Invalid aircraft id")
+    ),
+    params(
+        ("id" = String, Path, description = "Aircraft id"),
+    )
+)]
+pub async fn get_aircraft_telemetry(
+    Extension(telemetry): Extension<TelemetryStore>,
+    Path(aircraft_id): Path<String>,
+) -> Result<Json<AircraftTelemetry>, (StatusCode, String)> {
+    rest_info!("(get_aircraft_telemetry) entry [{}].", aircraft_id);
+    if !is_uuid(&aircraft_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid aircraft id".to_string()));
+    }
+
+    match telemetry.get(&aircraft_id) {
+        Some(telemetry) => Ok(Json(telemetry)),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            "No telemetry received for aircraft".to_string(),
+        )),
+    }
+}
+
+/// Get the status changes of an [`Aircraft`](crate::rest::structs::Aircraft)
+/// caused by its telemetry, oldest first.
+#[utoipa::path(
+    get,
+    path = "/assets/aircraft/{id}/status-history",
+    tag = "svc-assets",
+    responses(
+        (status = 200, description = "Status changes of aircraft {id}", body = [StatusChange]),
+        (status = 400, description = "Invalid aircraft id")
+    ),
+    params(
+        ("id" = String, Path, description = "Aircraft id"),
+    )
+)]
+pub async fn get_aircraft_status_history(
+    Extension(telemetry): Extension<TelemetryStore>,
+    Path(aircraft_id): Path<String>,
+) -> Result<Json<Vec<StatusChange>>, (StatusCode, String)> {
+    rest_info!("(get_aircraft_status_history) entry [{}].", aircraft_id);
+    if !is_uuid(&aircraft_id) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid aircraft id".to_string()));
+    }
+
+    Ok(Json(telemetry.status_history(&aircraft_id)))
+}
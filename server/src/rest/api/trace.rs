@@ -0,0 +1,46 @@
+//! REST API implementations for distributed tracing
+
+use crate::request_id::RequestId;
+use crate::trace::continue_from_headers;
+use axum::{body::Body, extract::MatchedPath, http::Request, middleware::Next, response::Response};
+use tracing::field::Empty;
+use tracing::Instrument;
+
+/// Record a server span for a request, continuing the trace of its
+/// `traceparent` header if any.
+///
+/// The request is handled within the span, so the spans of the svc-storage
+/// calls made by the handler become its children. Must be added with
+/// `route_layer`, so the matched route is known.
+pub async fn trace_requests(request: Request<Body>, next: Next<Body>) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let span = tracing::info_span!(
+        "request",
+        otel.name = %format!("{} {}", method, route),
+        otel.kind = "server",
+        http.method = %method,
+        http.route = %route,
+        http.target = %request.uri(),
+        http.request_id = Empty,
+        http.status_code = Empty,
+        otel.status_code = Empty,
+    );
+    continue_from_headers(&span, request.headers());
+    if let Some(request_id) = request.extensions().get::<RequestId>() {
+        span.record("http.request_id", request_id.as_str());
+    }
+
+    let response = next.run(request).instrument(span.clone()).await;
+    let status = response.status();
+    span.record("http.status_code", status.as_u16());
+    if status.is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+    response
+}
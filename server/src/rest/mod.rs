@@ -16,6 +16,16 @@ use svc_storage_client_grpc::prelude::*;
 #[derive(OpenApi)]
 #[openapi(
     paths(
+        api::health::health_live,
+        api::health::health_ready,
+        api::metrics::get_metrics,
+        api::events::get_asset_events,
+        api::events::get_outbox_stats,
+        api::deletions::get_deleted_assets,
+        api::audit::get_audit_entries,
+        api::catalog::get_vehicle_models,
+        api::catalog::get_vehicle_model,
+        api::matching::get_capable_aircraft,
         api::get_operator,
         api::get_all_aircraft,
         api::get_all_vertiports,
@@ -25,21 +35,42 @@ use svc_storage_client_grpc::prelude::*;
         api::get_all_grouped_assets_delegated_to,
         api::get_all_grouped_assets_delegated_from,
         api::get_aircraft_by_id,
+        api::telemetry::get_aircraft_telemetry,
+        api::telemetry::get_aircraft_status_history,
+        api::audit::get_aircraft_history,
         api::get_vertipad_by_id,
         api::get_vertiport_by_id,
+        api::occupancy::get_vertiport_occupancy,
+        api::summary::get_vertiport_summary,
+        api::audit::get_vertiport_history,
+        api::occupancy::get_vertipad_occupancy_history,
+        api::audit::get_vertipad_history,
+        api::reservations::get_reservations,
         api::get_asset_group_by_id,
         api::register_aircraft,
         api::register_vertiport,
         api::register_vertipad,
         api::register_asset_group,
+        api::catalog::register_vehicle_model,
+        api::occupancy::occupy_vertipad,
+        api::occupancy::release_vertipad,
+        api::reservations::create_reservation,
+        api::reservations::extend_reservation,
+        api::reservations::cancel_reservation,
+        api::deletions::restore_aircraft,
+        api::deletions::restore_vertiport,
+        api::deletions::restore_vertipad,
         api::update_aircraft,
         api::update_vertiport,
         api::update_vertipad,
         api::update_asset_group,
+        api::catalog::update_vehicle_model,
         api::remove_aircraft,
         api::remove_vertiport,
         api::remove_vertipad,
         api::remove_asset_group,
+        api::catalog::remove_vehicle_model,
+        api::reload::reload_config,
     ),
     components(
         schemas(
@@ -53,6 +84,15 @@ use svc_storage_client_grpc::prelude::*;
             UpdateAircraftPayload,
             UpdateVertiportPayload,
             UpdateVertipadPayload,
+            OccupyVertipadPayload,
+            ReleaseVertipadPayload,
+            GetAssetParams,
+            RemoveAssetParams,
+            RemoveVertiportParams,
+            CreateReservationPayload,
+            ExtendReservationPayload,
+            VehicleModelPayload,
+            CapableAircraftParams,
             structs::Operator,
             structs::Aircraft,
             structs::Vertiport,
@@ -60,6 +100,38 @@ use svc_storage_client_grpc::prelude::*;
             structs::AssetGroup,
             structs::AssetStatus,
             structs::Basics,
+            crate::events::AssetEvent,
+            crate::events::AssetKind,
+            crate::events::AssetAction,
+            crate::events::AssetEventFilter,
+            crate::events::outbox::OutboxStats,
+            crate::telemetry::AircraftTelemetry,
+            crate::telemetry::TelemetryPosition,
+            crate::telemetry::status::StatusChange,
+            crate::telemetry::status::StatusReason,
+            crate::occupancy::OccupancyRecord,
+            api::occupancy::VertipadOccupancy,
+            api::occupancy::VertiportOccupancy,
+            crate::reservations::Reservation,
+            crate::reservations::ConflictReason,
+            crate::reservations::ReservationConflict,
+            api::summary::VertiportSummary,
+            api::summary::VertipadSummary,
+            api::summary::HangaredAircraft,
+            api::dependents::VertiportRemoval,
+            crate::deletions::DeletionRecord,
+            crate::deletions::DeletionFilter,
+            crate::audit::AuditEntry,
+            crate::audit::AuditAction,
+            crate::audit::AuditFilter,
+            crate::audit::FieldChange,
+            crate::catalog::VehicleModel,
+            crate::catalog::VehicleDimensions,
+            api::matching::CapableAircraft,
+            crate::health::HealthReport,
+            crate::health::DependencyHealth,
+            crate::health::DependencyStatus,
+            crate::reload::ReloadReport,
         )
     ),
     tags(
@@ -1,20 +1,28 @@
 //! Rest server implementation
 
 use super::api;
-use crate::grpc::client::GrpcClients;
 use crate::shutdown_signal;
+use crate::store::SVC_STORAGE_BACKEND;
 use crate::Config;
 use axum::{
+    body::Body,
     error_handling::HandleErrorLayer,
     extract::Extension,
-    http::{HeaderValue, StatusCode},
-    routing, BoxError, Router,
+    http::{HeaderValue, Request, StatusCode},
+    middleware,
+    response::Response,
+    routing::{self, future::RouteFuture},
+    BoxError, Router,
 };
+use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use tokio::sync::watch;
 use tower::{
     buffer::BufferLayer,
     limit::{ConcurrencyLimitLayer, RateLimitLayer},
-    ServiceBuilder,
+    Service, ServiceBuilder,
 };
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
@@ -48,17 +56,111 @@ pub async fn rest_server(
         }
     };
 
-    let cors_allowed_origin = match config.rest_cors_allowed_origin.parse::<HeaderValue>() {
-        Ok(url) => url,
+    let app = match router(&config).await {
+        Ok(app) => app,
         Err(e) => {
-            rest_error!(
-                "(rest_server) invalid cors_allowed_origin address: {:?}, exiting.",
-                e
-            );
+            rest_error!("(rest_server) {}, exiting.", e);
             return Err(());
         }
     };
 
+    // The router is built again when the configuration is reloaded, so new
+    // requests use the new limits and CORS allowed origins.
+    let (routers, latest_router) = watch::channel(Mutex::new(app));
+    let mut reloads = crate::reload::get_config_reloader().await.subscribe();
+    tokio::spawn(async move {
+        while reloads.changed().await.is_ok() {
+            let config = reloads.borrow_and_update().clone();
+            match router(&config).await {
+                Ok(app) => {
+                    rest_info!("(rest_server) applied the reloaded configuration.");
+                    routers.send_replace(Mutex::new(app));
+                }
+                Err(e) => rest_error!("(rest_server) reloaded configuration not applied: {}", e),
+            }
+        }
+    });
+    let make_service = hyper::service::make_service_fn(move |_| {
+        let app = LiveRouter::new(latest_router.clone());
+        async move { Ok::<_, Infallible>(app) }
+    });
+
+    //
+    // Bind to address
+    //
+    match axum::Server::bind(&full_rest_addr)
+        .serve(make_service)
+        .with_graceful_shutdown(shutdown_signal("rest", shutdown_rx))
+        .await
+    {
+        Ok(_) => {
+            rest_info!("(rest_server) hosted at: {}.", full_rest_addr);
+            Ok(())
+        }
+        Err(e) => {
+            rest_error!("(rest_server) could not start server: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Serves the requests of a connection with the router of the latest
+/// configuration.
+///
+/// Each connection has its own copy of the router, replaced by the first
+/// request after a reload, so requests neither wait on a lock nor clone the
+/// router. The latest router is kept in a [`Mutex`] only because a
+/// [`Router`] can't be shared between threads.
+#[derive(Clone)]
+struct LiveRouter {
+    router: Router,
+    latest: watch::Receiver<Mutex<Router>>,
+}
+
+impl LiveRouter {
+    fn new(mut latest: watch::Receiver<Mutex<Router>>) -> Self {
+        let router = latest
+            .borrow_and_update()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        Self { router, latest }
+    }
+}
+
+impl Service<Request<Body>> for LiveRouter {
+    type Response = Response;
+    type Error = Infallible;
+    type Future = RouteFuture<Body, Infallible>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // The sender is gone if the reload task stopped, the router stays
+        if self.latest.has_changed().unwrap_or(false) {
+            self.router = self
+                .latest
+                .borrow_and_update()
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone();
+        }
+        self.router.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        self.router.call(request)
+    }
+}
+
+/// Builds the REST API routes, limited and allowing CORS requests from the
+/// configured origins.
+pub async fn router(config: &Config) -> Result<Router, String> {
+    let cors_allowed_origins = config
+        .rest_cors_allowed_origin
+        .split(',')
+        .map(|origin| origin.trim().parse::<HeaderValue>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("invalid cors_allowed_origin address: {:?}", e))?;
+
     // Rate limiting
     let rate_limit = config.rest_request_limit_per_second as u64;
     let concurrency_limit = config.rest_concurrency_limit_per_service as usize;
@@ -66,6 +168,7 @@ pub async fn rest_server(
         .layer(TraceLayer::new_for_http())
         .layer(HandleErrorLayer::new(|e: BoxError| async move {
             rest_warn!("(server) too many requests: {}", e);
+            crate::metrics::get_metrics().await.record_rate_limited();
             (
                 StatusCode::TOO_MANY_REQUESTS,
                 "(server) too many requests.".to_string(),
@@ -81,11 +184,82 @@ pub async fn rest_server(
     //
     // Extensions
     //
-    // GRPC Clients
-    let grpc_clients = GrpcClients::default(config.clone());
+    // Asset storage
+    let store = crate::store::get_store().await.clone();
+    // Asset change notifications
+    let notifier = crate::events::get_notifier().await.clone();
+    // Vertipad occupancy
+    let occupancy = crate::occupancy::get_occupancy().await.clone();
+    // Vertipad reservations
+    let reservations = crate::reservations::get_reservations().await.clone();
+    // Removed assets
+    let deletions = crate::deletions::get_deletions().await.clone();
+    // Audit log of asset changes
+    let audit = crate::audit::get_audit_log().await.clone();
+    // Vehicle model catalog
+    let catalog = crate::catalog::get_catalog().await.clone();
+    // Registration mark validators per aviation authority
+    let registration = crate::registration::RegistrationValidators::default();
+    // Dependency health
+    let health = crate::health::get_health_monitor().await.clone();
+    // Stale reads and rejected writes while svc-storage is down
+    let degraded = crate::degraded::get_degraded_mode().await.clone();
+    // Request and storage call metrics
+    let metrics = crate::metrics::get_metrics().await.clone();
+    // Assets looked up by id
+    let cache = crate::cache::get_asset_cache().await.clone();
+    // Live aircraft state
+    let telemetry = crate::telemetry::get_telemetry().await.clone();
+    // Configuration reloads
+    let reloader = crate::reload::get_config_reloader().await.clone();
+
+    // TODO(R4): svc-storage can not clear the deleted_at of removed assets
+    let restore_routes = match config.storage_backend.as_str() {
+        SVC_STORAGE_BACKEND => Router::new(),
+        _ => Router::new()
+            .route(
+                "/assets/aircraft/:id/restore",
+                routing::post(api::deletions::restore_aircraft),
+            )
+            .route(
+                "/assets/vertiports/:id/restore",
+                routing::post(api::deletions::restore_vertiport),
+            )
+            .route(
+                "/assets/vertipads/:id/restore",
+                routing::post(api::deletions::restore_vertipad),
+            ),
+    };
 
     let app = Router::new()
-        .route("/health", routing::get(api::health_check))
+        .route("/health", routing::get(api::health::health_check))
+        .route("/health/live", routing::get(api::health::health_live))
+        .route("/health/ready", routing::get(api::health::health_ready))
+        .route("/metrics", routing::get(api::metrics::get_metrics))
+        .route(
+            "/assets/events/outbox",
+            routing::get(api::events::get_outbox_stats),
+        )
+        .route(
+            "/assets/deleted",
+            routing::get(api::deletions::get_deleted_assets),
+        )
+        .route("/assets/audit", routing::get(api::audit::get_audit_entries))
+        .route(
+            "/assets/models",
+            routing::get(api::catalog::get_vehicle_models)
+                .post(api::catalog::register_vehicle_model),
+        )
+        .route(
+            "/assets/models/:id",
+            routing::get(api::catalog::get_vehicle_model)
+                .put(api::catalog::update_vehicle_model)
+                .delete(api::catalog::remove_vehicle_model),
+        )
+        .route(
+            "/assets/capable-aircraft",
+            routing::get(api::matching::get_capable_aircraft),
+        )
         .route("/assets/operators/:id", routing::get(api::get_operator))
         .route("/assets/demo/aircraft", routing::get(api::get_all_aircraft))
         .route(
@@ -116,14 +290,51 @@ pub async fn rest_server(
             "/assets/aircraft/:id",
             routing::get(api::get_aircraft_by_id),
         )
+        .route(
+            "/assets/aircraft/:id/telemetry",
+            routing::get(api::telemetry::get_aircraft_telemetry),
+        )
+        .route(
+            "/assets/aircraft/:id/status-history",
+            routing::get(api::telemetry::get_aircraft_status_history),
+        )
+        .route(
+            "/assets/aircraft/:id/history",
+            routing::get(api::audit::get_aircraft_history),
+        )
         .route(
             "/assets/vertipads/:id",
             routing::get(api::get_vertipad_by_id),
         )
+        .route(
+            "/assets/vertipads/:id/occupancy",
+            routing::get(api::occupancy::get_vertipad_occupancy_history),
+        )
+        .route(
+            "/assets/vertipads/:id/history",
+            routing::get(api::audit::get_vertipad_history),
+        )
+        .route(
+            "/assets/vertipads/:id/reservations",
+            routing::get(api::reservations::get_reservations)
+                .post(api::reservations::create_reservation),
+        )
         .route(
             "/assets/vertiports/:id",
             routing::get(api::get_vertiport_by_id),
         )
+        .route(
+            "/assets/vertiports/:id/occupancy",
+            routing::get(api::occupancy::get_vertiport_occupancy),
+        )
+        .route(
+            "/assets/vertiports/:id/summary",
+            routing::get(api::summary::get_vertiport_summary),
+        )
+        .route(
+            "/assets/vertiports/:id/history",
+            routing::get(api::audit::get_vertiport_history),
+        )
         .route(
             "/assets/groups/:id",
             routing::get(api::get_asset_group_by_id),
@@ -133,6 +344,25 @@ pub async fn rest_server(
         .route("/assets/vertiports", routing::post(api::register_vertiport))
         .route("/assets/vertipads", routing::post(api::register_vertipad))
         .route("/assets/groups", routing::post(api::register_asset_group))
+        .route(
+            "/admin/config/reload",
+            routing::post(api::reload::reload_config),
+        )
+        .route(
+            "/assets/vertipads/:id/occupy",
+            routing::post(api::occupancy::occupy_vertipad),
+        )
+        .route(
+            "/assets/vertipads/:id/release",
+            routing::post(api::occupancy::release_vertipad),
+        )
+        .merge(restore_routes)
+        // PUT and DELETE endpoints of vertipad reservations
+        .route(
+            "/assets/vertipads/:id/reservations/:reservation_id",
+            routing::put(api::reservations::extend_reservation)
+                .delete(api::reservations::cancel_reservation),
+        )
         // PUT endpoints
         .route("/assets/aircraft", routing::put(api::update_aircraft))
         .route("/assets/vertiports", routing::put(api::update_vertiport))
@@ -155,30 +385,77 @@ pub async fn rest_server(
             "/assets/groups/:id",
             routing::delete(api::remove_asset_group),
         )
+        .layer(limit_middleware)
+        // Event streams are long-lived, they should not be rate limited
+        // or take up one of the concurrency limit slots
+        .route(
+            "/assets/events",
+            routing::get(api::events::get_asset_events),
+        )
+        .route_layer(middleware::from_fn(api::degraded::degraded_mode))
+        .route_layer(middleware::from_fn(api::metrics::track_requests))
+        .route_layer(middleware::from_fn(api::trace::trace_requests))
+        .layer(middleware::from_fn(api::request_id::assign_request_id))
         .layer(
             CorsLayer::new()
-                .allow_origin(cors_allowed_origin)
+                .allow_origin(cors_allowed_origins)
                 .allow_headers(Any)
                 .allow_methods(Any),
         )
-        .layer(limit_middleware)
-        .layer(Extension(grpc_clients)); // Extension layer must be last
+        .layer(Extension(notifier))
+        .layer(Extension(telemetry))
+        .layer(Extension(occupancy))
+        .layer(Extension(reservations))
+        .layer(Extension(deletions))
+        .layer(Extension(audit))
+        .layer(Extension(catalog))
+        .layer(Extension(registration))
+        .layer(Extension(metrics))
+        .layer(Extension(cache))
+        .layer(Extension(health))
+        .layer(Extension(degraded))
+        .layer(Extension(reloader))
+        .layer(Extension(store)); // Extension layer must be last
 
-    //
-    // Bind to address
-    //
-    match axum::Server::bind(&full_rest_addr)
-        .serve(app.into_make_service())
-        .with_graceful_shutdown(shutdown_signal("rest", shutdown_rx))
-        .await
-    {
-        Ok(_) => {
-            rest_info!("(rest_server) hosted at: {}.", full_rest_addr);
-            Ok(())
-        }
-        Err(e) => {
-            rest_error!("(rest_server) could not start server: {}", e);
-            Err(())
-        }
+    Ok(app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower::ServiceExt;
+
+    /// Returns the body of the response to a `GET /`.
+    async fn get(app: &mut LiveRouter) -> String {
+        let request = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let response = app.ready().await.unwrap().call(request).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_live_router() {
+        crate::get_log_handle().await;
+        ut_info!("(test_live_router) Start.");
+
+        let (routers, latest) = watch::channel(Mutex::new(
+            Router::new().route("/", routing::get(|| async { "first" })),
+        ));
+        let mut app = LiveRouter::new(latest);
+        assert_eq!(get(&mut app).await, "first");
+
+        // Connections switch to the latest router on their next request
+        routers.send_replace(Mutex::new(
+            Router::new().route("/", routing::get(|| async { "second" })),
+        ));
+        assert_eq!(get(&mut app).await, "second");
+        let mut other_app = app.clone();
+        assert_eq!(get(&mut other_app).await, "second");
+
+        // The router stays when reloads stop
+        drop(routers);
+        assert_eq!(get(&mut app).await, "second");
+
+        ut_info!("(test_live_router) Success.");
     }
 }
@@ -6,6 +6,7 @@
 //! Types here are different from the openapi types.
 #![allow(missing_docs)]
 
+use crate::catalog::VehicleModel;
 use chrono::{DateTime, Utc};
 use duplicate::duplicate_item;
 use lipsum::{lipsum, lipsum_title};
@@ -220,12 +221,15 @@ pub enum AssetStatus {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema, IntoParams)]
 pub struct Aircraft {
     pub basics: Basics,
+    /// The UUID of the aircraft's [`VehicleModel`].
+    pub vehicle_model_id: String,
     /// The aircraft's manufacturer.
     ///
-    /// TODO(R4): For now we can just say "Boeing", "Airbus", etc. Later, we
-    /// can a struct for this and store the manufacturer's name, logo,
-    /// etc.
+    /// Empty if the aircraft's vehicle model is not in the catalog.
     pub manufacturer: String,
+    /// The name of the aircraft's vehicle model.
+    ///
+    /// The `vehicle_model_id` if the vehicle model is not in the catalog.
     pub model: String,
     pub serial_number: String,
     /// The aircraft's registration number.
@@ -237,7 +241,11 @@ pub struct Aircraft {
     /// aircraft from national aviation authorities like the FAA.
     pub registration_number: String,
     pub description: Option<String>,
+    /// Maximum payload of the aircraft's vehicle model, 0 if the vehicle
+    /// model is not in the catalog.
     pub max_payload_kg: f64,
+    /// Maximum range of the aircraft's vehicle model, 0 if the vehicle
+    /// model is not in the catalog.
     pub max_range_km: f64,
     pub last_maintenance: Option<DateTime<Utc>>,
     pub next_maintenance: Option<DateTime<Utc>>,
@@ -261,6 +269,15 @@ impl Aircraft {
         }
     }
 
+    /// Use the manufacturer and performance data of the aircraft's
+    /// vehicle model.
+    pub fn apply_vehicle_model(&mut self, model: &VehicleModel) {
+        self.manufacturer = model.manufacturer.clone();
+        self.model = model.model_name.clone();
+        self.max_payload_kg = model.max_payload_kg;
+        self.max_range_km = model.max_range_km;
+    }
+
     /// Generate a random aircraft.
     pub fn random() -> Self {
         Self {
@@ -274,6 +291,7 @@ impl Aircraft {
                 whitelist: Vec::new(),
                 status: AssetStatus::Available,
             },
+            vehicle_model_id: Uuid::new_v4().to_string(),
             manufacturer: lipsum_title(),
             model: lipsum(12),
             serial_number: lipsum(12),
@@ -314,8 +332,9 @@ impl TryFrom<VehicleObject> for Aircraft {
                 whitelist: Vec::new(),
                 status: AssetStatus::Available,
             },
-            manufacturer: "Arrow".to_string(),
-            model: data.vehicle_model_id,
+            manufacturer: String::new(),
+            model: data.vehicle_model_id.clone(),
+            vehicle_model_id: data.vehicle_model_id,
             serial_number: data.serial_number,
             registration_number: data.registration_number,
             description: data.description,
@@ -475,6 +494,7 @@ mod tests {
         };
         let asset = Aircraft {
             basics: basics.clone(),
+            vehicle_model_id: Uuid::new_v4().to_string(),
             manufacturer: "Boeing".to_string(),
             model: "737-800".to_string(),
             serial_number: "12345".to_string(),
@@ -517,6 +537,7 @@ mod tests {
         };
         let aircraft = Aircraft {
             basics: basics.clone(),
+            vehicle_model_id: Uuid::new_v4().to_string(),
             manufacturer: "Boeing".to_string(),
             model: "737-800".to_string(),
             serial_number: aircraft.serial_number,
@@ -572,6 +593,38 @@ mod tests {
         assert_eq!(aircraft.status(), AssetStatus::Available);
     }
 
+    #[test]
+    fn test_aircraft_apply_vehicle_model() {
+        let vehicle_data: vehicle::Data = vehicle::mock::get_data_obj();
+        let vehicle_obj = VehicleObject {
+            id: Uuid::new_v4().to_string(),
+            data: Some(vehicle_data.clone()),
+        };
+        let mut aircraft: Aircraft = vehicle_obj.try_into().unwrap();
+        assert_eq!(aircraft.vehicle_model_id, vehicle_data.vehicle_model_id);
+        assert_eq!(aircraft.model, vehicle_data.vehicle_model_id);
+        assert_eq!(aircraft.max_payload_kg, 0.0);
+
+        let model = VehicleModel {
+            id: vehicle_data.vehicle_model_id.clone(),
+            manufacturer: "Arrow".to_string(),
+            model_name: "Cargo".to_string(),
+            max_payload_kg: 250.0,
+            max_range_km: 120.0,
+            cruise_speed_kph: 180.0,
+            charge_time_minutes: 45.0,
+            dimensions: Default::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        aircraft.apply_vehicle_model(&model);
+        assert_eq!(aircraft.vehicle_model_id, vehicle_data.vehicle_model_id);
+        assert_eq!(aircraft.manufacturer, "Arrow");
+        assert_eq!(aircraft.model, "Cargo");
+        assert_eq!(aircraft.max_payload_kg, 250.0);
+        assert_eq!(aircraft.max_range_km, 120.0);
+    }
+
     #[test]
     fn test_vertiport_basics_trait_methods() {
         let vertiport = vertiport::mock::get_data_obj();
@@ -731,6 +784,7 @@ mod tests {
         let aircraft = vehicle::mock::get_data_obj();
         let aircraft = Aircraft {
             basics: basics.clone(),
+            vehicle_model_id: Uuid::new_v4().to_string(),
             manufacturer: "Boeing".to_string(),
             model: "737-800".to_string(),
             serial_number: aircraft.serial_number,
@@ -0,0 +1,525 @@
+//! Storage of the assets in memory
+//!
+//! Assets are lost when the service shuts down. Searches and updates follow
+//! svc-storage: fields are matched and masked by their name in the stored
+//! data, and a field which isn't part of the data is never set, except for
+//! the `deleted_at` of soft deleted objects.
+
+use super::{AssetFilter, AssetStore, Condition, ResourceStore};
+use crate::rest::structs::AssetGroup;
+use chrono::{DateTime, Utc};
+use duplicate::duplicate_item;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, MutexGuard};
+use svc_storage_client_grpc::prelude::*;
+use tonic::Status;
+use uuid::Uuid;
+
+/// A svc-storage object built from its id and data.
+pub trait StoredObject<D> {
+    /// Create the object with the given id holding `data`.
+    fn from_data(id: String, data: D) -> Self;
+}
+
+#[duplicate_item(
+    object_type          data_type;
+    [vehicle::Object]    [vehicle::Data];
+    [vertiport::Object]  [vertiport::Data];
+    [vertipad::Object]   [vertipad::Data];
+)]
+impl StoredObject<data_type> for object_type {
+    fn from_data(id: String, data: data_type) -> Self {
+        Self {
+            id,
+            data: Some(data),
+        }
+    }
+}
+
+/// Returns the value of `field` in `data`, if it is set.
+fn field_value(data: &Value, field: &str) -> Option<String> {
+    match data.get(field) {
+        None | Some(Value::Null) => None,
+        Some(Value::String(value)) => Some(value.clone()),
+        Some(value) => Some(value.to_string()),
+    }
+}
+
+/// A stored object, with the time at which it has been soft deleted.
+#[derive(Clone, Debug)]
+struct Row<D> {
+    data: D,
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+/// Returns the stored fields of a `row`, including its `deleted_at`.
+fn row_value<D: Serialize>(row: &Row<D>) -> Result<Value, serde_json::Error> {
+    let mut value = serde_json::to_value(&row.data)?;
+    if let (Value::Object(fields), Some(deleted_at)) = (&mut value, row.deleted_at) {
+        fields.insert(
+            "deleted_at".to_string(),
+            Value::String(deleted_at.to_rfc3339()),
+        );
+    }
+    Ok(value)
+}
+
+/// Check if `data` meets the `condition`.
+fn meets(data: &Value, condition: &Condition) -> bool {
+    match condition {
+        Condition::Equals(field, value) => field_value(data, field).as_ref() == Some(value),
+        Condition::In(field, values) => {
+            field_value(data, field).is_some_and(|value| values.contains(&value))
+        }
+        Condition::IsNull(field) => field_value(data, field).is_none(),
+        Condition::IsNotNull(field) => field_value(data, field).is_some(),
+    }
+}
+
+fn to_status(e: serde_json::Error) -> Status {
+    Status::internal(format!("could not convert stored data: {}", e))
+}
+
+/// In memory storage of a single kind of asset.
+#[derive(Debug)]
+pub struct MemoryTable<O, D> {
+    resource: &'static str,
+    rows: Arc<Mutex<BTreeMap<String, Row<D>>>>,
+    /// Operations which fail, to test how failures are handled.
+    failing: Arc<Mutex<Vec<&'static str>>>,
+    object: PhantomData<fn() -> O>,
+}
+
+impl<O, D> Clone for MemoryTable<O, D> {
+    fn clone(&self) -> Self {
+        Self {
+            resource: self.resource,
+            rows: Arc::clone(&self.rows),
+            failing: Arc::clone(&self.failing),
+            object: PhantomData,
+        }
+    }
+}
+
+impl<O, D> MemoryTable<O, D> {
+    /// Create an empty table for the svc-storage `resource`.
+    pub fn new(resource: &'static str) -> Self {
+        Self {
+            resource,
+            rows: Arc::new(Mutex::new(BTreeMap::new())),
+            failing: Arc::new(Mutex::new(vec![])),
+            object: PhantomData,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, Row<D>>> {
+        match self.rows.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn not_found(&self, id: &str) -> Status {
+        Status::not_found(format!("{} {} not found", self.resource, id))
+    }
+
+    /// Make `operation`, like `restore`, fail from now on. Only operations
+    /// changing the stored objects can fail.
+    #[cfg(test)]
+    pub(crate) fn fail(&self, operation: &'static str) {
+        match self.failing.lock() {
+            Ok(mut failing) => failing.push(operation),
+            Err(poisoned) => poisoned.into_inner().push(operation),
+        }
+    }
+
+    /// Returns the error of `operation` if it has been made to fail.
+    fn failure(&self, operation: &'static str) -> Option<Status> {
+        let failing = match self.failing.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        failing
+            .contains(&operation)
+            .then(|| Status::unavailable(format!("{} {} failed", self.resource, operation)))
+    }
+}
+
+#[tonic::async_trait]
+impl<O, D> ResourceStore<O, D> for MemoryTable<O, D>
+where
+    O: StoredObject<D> + Send,
+    D: Clone + Serialize + DeserializeOwned + Send,
+{
+    async fn get_by_id(&self, id: &str) -> Result<O, Status> {
+        match self.lock().get(id) {
+            Some(row) => Ok(O::from_data(id.to_string(), row.data.clone())),
+            None => Err(self.not_found(id)),
+        }
+    }
+
+    async fn search(&self, filter: &AssetFilter) -> Result<Vec<O>, Status> {
+        let mut list = Vec::new();
+        for (id, row) in self.lock().iter() {
+            let value = row_value(row).map_err(to_status)?;
+            if filter
+                .conditions()
+                .iter()
+                .all(|condition| meets(&value, condition))
+            {
+                list.push(O::from_data(id.clone(), row.data.clone()));
+            }
+        }
+        Ok(list)
+    }
+
+    async fn insert(&self, data: D) -> Result<O, Status> {
+        if let Some(status) = self.failure("insert") {
+            return Err(status);
+        }
+        let id = Uuid::new_v4().to_string();
+        let row = Row {
+            data: data.clone(),
+            deleted_at: None,
+        };
+        self.lock().insert(id.clone(), row);
+        store_debug!("(insert) stored {} {}.", self.resource, id);
+        Ok(O::from_data(id, data))
+    }
+
+    async fn update(&self, id: &str, data: D, mask: Vec<String>) -> Result<(), Status> {
+        if let Some(status) = self.failure("update") {
+            return Err(status);
+        }
+        let mut rows = self.lock();
+        let Some(row) = rows.get_mut(id) else {
+            return Err(self.not_found(id));
+        };
+        let stored = &mut row.data;
+
+        // Like svc-storage, an empty mask updates all fields
+        if mask.is_empty() {
+            *stored = data;
+            return Ok(());
+        }
+
+        let mut current = serde_json::to_value(&*stored).map_err(to_status)?;
+        let update = serde_json::to_value(&data).map_err(to_status)?;
+        if let (Value::Object(current), Value::Object(update)) = (&mut current, &update) {
+            for path in &mask {
+                match update.get(path) {
+                    Some(value) => current.insert(path.clone(), value.clone()),
+                    None => {
+                        return Err(Status::invalid_argument(format!(
+                            "unknown {} field {}",
+                            self.resource, path
+                        )))
+                    }
+                };
+            }
+        }
+        *stored = serde_json::from_value(current).map_err(to_status)?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), Status> {
+        if let Some(status) = self.failure("delete") {
+            return Err(status);
+        }
+        match self.lock().remove(id) {
+            Some(_) => {
+                store_debug!("(delete) deleted {} {}.", self.resource, id);
+                Ok(())
+            }
+            None => Err(self.not_found(id)),
+        }
+    }
+
+    async fn soft_delete(&self, id: &str) -> Result<(), Status> {
+        if let Some(status) = self.failure("soft_delete") {
+            return Err(status);
+        }
+        match self.lock().get_mut(id) {
+            Some(row) => {
+                row.deleted_at.get_or_insert_with(Utc::now);
+                store_debug!("(soft_delete) deleted {} {}.", self.resource, id);
+                Ok(())
+            }
+            None => Err(self.not_found(id)),
+        }
+    }
+
+    async fn restore(&self, id: &str) -> Result<(), Status> {
+        if let Some(status) = self.failure("restore") {
+            return Err(status);
+        }
+        match self.lock().get_mut(id) {
+            Some(row) => {
+                row.deleted_at = None;
+                store_debug!("(restore) restored {} {}.", self.resource, id);
+                Ok(())
+            }
+            None => Err(self.not_found(id)),
+        }
+    }
+
+    async fn is_deleted(&self, id: &str) -> Result<bool, Status> {
+        Ok(self
+            .lock()
+            .get(id)
+            .is_some_and(|row| row.deleted_at.is_some()))
+    }
+
+    async fn is_ready(&self) -> Result<(), Status> {
+        Ok(())
+    }
+}
+
+/// Stores the assets in memory.
+///
+/// Clones share the same assets.
+#[derive(Clone, Debug)]
+pub struct MemoryStore {
+    vehicles: MemoryTable<vehicle::Object, vehicle::Data>,
+    vertiports: MemoryTable<vertiport::Object, vertiport::Data>,
+    vertipads: MemoryTable<vertipad::Object, vertipad::Data>,
+    groups: Arc<Mutex<HashMap<String, AssetGroup>>>,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self {
+            vehicles: MemoryTable::new("vehicle"),
+            vertiports: MemoryTable::new("vertiport"),
+            vertipads: MemoryTable::new("vertipad"),
+            groups: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl MemoryStore {
+    /// Make `operation` fail from now on for the vehicles, vertiports or
+    /// vertipads `resource`.
+    #[cfg(test)]
+    pub(crate) fn fail(&self, resource: &str, operation: &'static str) {
+        match resource {
+            "vehicle" => self.vehicles.fail(operation),
+            "vertiport" => self.vertiports.fail(operation),
+            _ => self.vertipads.fail(operation),
+        }
+    }
+
+    fn groups(&self) -> MutexGuard<'_, HashMap<String, AssetGroup>> {
+        match self.groups.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+fn group_not_found(id: &str) -> Status {
+    Status::not_found(format!("asset group {} not found", id))
+}
+
+#[tonic::async_trait]
+impl AssetStore for MemoryStore {
+    fn vehicles(&self) -> &dyn ResourceStore<vehicle::Object, vehicle::Data> {
+        &self.vehicles
+    }
+
+    fn vertiports(&self) -> &dyn ResourceStore<vertiport::Object, vertiport::Data> {
+        &self.vertiports
+    }
+
+    fn vertipads(&self) -> &dyn ResourceStore<vertipad::Object, vertipad::Data> {
+        &self.vertipads
+    }
+
+    fn stores_groups(&self) -> bool {
+        true
+    }
+
+    async fn get_group(&self, id: &str) -> Result<AssetGroup, Status> {
+        self.groups()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| group_not_found(id))
+    }
+
+    async fn insert_group(&self, group: AssetGroup) -> Result<(), Status> {
+        self.groups().insert(group.id.clone(), group);
+        Ok(())
+    }
+
+    async fn update_group(&self, group: AssetGroup) -> Result<(), Status> {
+        match self.groups().get_mut(&group.id) {
+            Some(stored) => {
+                *stored = group;
+                Ok(())
+            }
+            None => Err(group_not_found(&group.id)),
+        }
+    }
+
+    async fn delete_group(&self, id: &str) -> Result<(), Status> {
+        match self.groups().remove(id) {
+            Some(_) => Ok(()),
+            None => Err(group_not_found(id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    fn vertipad_data(name: &str, vertiport_id: &str) -> vertipad::Data {
+        vertipad::Data {
+            name: name.to_string(),
+            vertiport_id: vertiport_id.to_string(),
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_crud() {
+        crate::get_log_handle().await;
+        ut_info!("(test_crud) Start.");
+
+        let store = MemoryStore::default();
+        let vertipads = store.vertipads();
+        let object = vertipads.insert(vertipad_data("A", "port")).await.unwrap();
+        assert!(Uuid::parse_str(&object.id).is_ok());
+
+        let stored = vertipads.get_by_id(&object.id).await.unwrap();
+        assert_eq!(stored.data.unwrap().name, "A");
+
+        // Only the masked fields are updated
+        let update = vertipad::Data {
+            occupied: true,
+            ..vertipad_data("B", "other")
+        };
+        vertipads
+            .update(&object.id, update.clone(), vec![String::from("occupied")])
+            .await
+            .unwrap();
+        let data = vertipads.get_by_id(&object.id).await.unwrap().data.unwrap();
+        assert!(data.occupied);
+        assert_eq!(data.name, "A");
+        assert_eq!(data.vertiport_id, "port");
+
+        let unknown = vertipads
+            .update(&object.id, update.clone(), vec![String::from("color")])
+            .await
+            .unwrap_err();
+        assert_eq!(unknown.code(), Code::InvalidArgument);
+
+        vertipads.update(&object.id, update, vec![]).await.unwrap();
+        let data = vertipads.get_by_id(&object.id).await.unwrap().data.unwrap();
+        assert_eq!(data.name, "B");
+
+        // Clones share the assets
+        let clone = store.clone();
+        clone.vertipads().delete(&object.id).await.unwrap();
+        let missing = vertipads.get_by_id(&object.id).await.unwrap_err();
+        assert_eq!(missing.code(), Code::NotFound);
+        assert!(vertipads.delete(&object.id).await.is_err());
+        assert!(store.vehicles().get_by_id(&object.id).await.is_err());
+
+        ut_info!("(test_crud) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_search() {
+        crate::get_log_handle().await;
+        ut_info!("(test_search) Start.");
+
+        let store = MemoryStore::default();
+        let vertipads = store.vertipads();
+        for (name, vertiport_id) in [("A", "port1"), ("B", "port1"), ("C", "port2")] {
+            vertipads
+                .insert(vertipad_data(name, vertiport_id))
+                .await
+                .unwrap();
+        }
+        let names = |list: Vec<vertipad::Object>| {
+            let mut names: Vec<String> = list.into_iter().map(|o| o.data.unwrap().name).collect();
+            names.sort();
+            names
+        };
+
+        let filter = AssetFilter::equals("vertiport_id", "port1").and_is_null("deleted_at");
+        assert_eq!(names(vertipads.search(&filter).await.unwrap()), ["A", "B"]);
+
+        let filter = AssetFilter::is_in("name", vec![String::from("A"), String::from("C")]);
+        assert_eq!(names(vertipads.search(&filter).await.unwrap()), ["A", "C"]);
+
+        // Values other than strings are compared as JSON
+        let filter = AssetFilter::equals("enabled", "true");
+        assert_eq!(vertipads.search(&filter).await.unwrap().len(), 3);
+
+        // Fields which aren't part of the data are never set
+        let filter = AssetFilter::is_not_null("deleted_at");
+        assert!(vertipads.search(&filter).await.unwrap().is_empty());
+        let all = vertipads.search(&AssetFilter::default()).await.unwrap();
+        assert_eq!(all.len(), 3);
+
+        ut_info!("(test_search) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_soft_delete() {
+        crate::get_log_handle().await;
+        ut_info!("(test_soft_delete) Start.");
+
+        let store = MemoryStore::default();
+        let vertipads = store.vertipads();
+        let object = vertipads.insert(vertipad_data("A", "port")).await.unwrap();
+        let deleted = AssetFilter::is_not_null("deleted_at");
+        let active = AssetFilter::is_null("deleted_at");
+        assert!(!vertipads.is_deleted(&object.id).await.unwrap());
+
+        // Soft deleted objects are only found by searching deleted objects
+        vertipads.soft_delete(&object.id).await.unwrap();
+        assert!(vertipads.is_deleted(&object.id).await.unwrap());
+        assert!(vertipads.get_by_id(&object.id).await.is_ok());
+        assert!(vertipads.search(&active).await.unwrap().is_empty());
+        assert_eq!(vertipads.search(&deleted).await.unwrap().len(), 1);
+
+        vertipads.restore(&object.id).await.unwrap();
+        assert!(!vertipads.is_deleted(&object.id).await.unwrap());
+        assert_eq!(vertipads.search(&active).await.unwrap().len(), 1);
+
+        let missing = vertipads.soft_delete("missing").await.unwrap_err();
+        assert_eq!(missing.code(), Code::NotFound);
+        assert!(!vertipads.is_deleted("missing").await.unwrap());
+
+        ut_info!("(test_soft_delete) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_groups() {
+        crate::get_log_handle().await;
+        ut_info!("(test_groups) Start.");
+
+        let store = MemoryStore::default();
+        let mut group = AssetGroup::random();
+        assert!(store.get_group(&group.id).await.is_err());
+        assert!(store.update_group(group.clone()).await.is_err());
+
+        store.insert_group(group.clone()).await.unwrap();
+        group.name = Some(String::from("renamed"));
+        store.update_group(group.clone()).await.unwrap();
+        assert_eq!(store.get_group(&group.id).await.unwrap(), group);
+
+        store.delete_group(&group.id).await.unwrap();
+        assert!(store.delete_group(&group.id).await.is_err());
+
+        ut_info!("(test_groups) Success.");
+    }
+}
@@ -0,0 +1,185 @@
+//! Asset Store
+//! abstracts the storage of the assets managed by the service
+//!
+//! The REST API reads and writes assets through an [`AssetStore`]. By
+//! default, assets are stored in svc-storage ([`SvcStorage`]). With
+//! `STORAGE_BACKEND=memory`, they are kept in memory instead
+//! ([`MemoryStore`]), so the service can run standalone for local
+//! development and integration tests.
+//!
+//! Removed assets are soft deleted: their `deleted_at` is set in the store,
+//! which hides them from searches on `deleted_at` being null, until they
+//! are restored or permanently deleted.
+
+#[macro_use]
+pub mod macros;
+pub mod memory;
+pub mod svc_storage;
+
+pub use memory::MemoryStore;
+pub use svc_storage::SvcStorage;
+
+use crate::rest::structs::AssetGroup;
+use std::sync::Arc;
+use svc_storage_client_grpc::prelude::*;
+use tokio::sync::OnceCell;
+use tonic::Status;
+
+/// Backend selected with `STORAGE_BACKEND` to store the assets in memory.
+pub const MEMORY_BACKEND: &str = "memory";
+/// Backend selected with `STORAGE_BACKEND` to store the assets in
+/// svc-storage.
+pub const SVC_STORAGE_BACKEND: &str = "svc-storage";
+
+/// An [`AssetStore`] shared by all requests.
+pub type SharedStore = Arc<dyn AssetStore>;
+
+pub(crate) static STORE: OnceCell<SharedStore> = OnceCell::const_new();
+
+/// Returns STORE, the [`AssetStore`] selected by a Config object generated
+/// from environment variables.
+/// Initializes STORE if it hasn't been initialized yet.
+pub async fn get_store() -> &'static SharedStore {
+    STORE
+        .get_or_init(|| async move {
+            let config = crate::Config::try_from_env().unwrap_or_default();
+            match config.storage_backend.as_str() {
+                MEMORY_BACKEND => {
+                    store_warn!("(get_store) assets are kept in memory and lost on shutdown.");
+                    Arc::new(MemoryStore::default()) as SharedStore
+                }
+                backend => {
+                    if backend != SVC_STORAGE_BACKEND {
+                        store_error!(
+                            "(get_store) unknown storage backend [{}], using {}.",
+                            backend,
+                            SVC_STORAGE_BACKEND
+                        );
+                    }
+                    let grpc_clients = crate::grpc::client::get_clients().await;
+                    Arc::new(SvcStorage::new(grpc_clients.clone())) as SharedStore
+                }
+            }
+        })
+        .await
+}
+
+/// A condition on a stored field.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    /// The field equals the value.
+    Equals(String, String),
+    /// The field equals one of the values.
+    In(String, Vec<String>),
+    /// The field is not set.
+    IsNull(String),
+    /// The field is set.
+    IsNotNull(String),
+}
+
+/// Conditions the objects returned by a search must all meet.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AssetFilter {
+    conditions: Vec<Condition>,
+}
+
+impl AssetFilter {
+    /// Match objects of which `field` equals `value`.
+    pub fn equals(field: &str, value: &str) -> Self {
+        Self::default().and(Condition::Equals(field.to_string(), value.to_string()))
+    }
+
+    /// Match objects of which `field` equals one of the `values`.
+    pub fn is_in(field: &str, values: Vec<String>) -> Self {
+        Self::default().and(Condition::In(field.to_string(), values))
+    }
+
+    /// Match objects of which `field` is not set.
+    pub fn is_null(field: &str) -> Self {
+        Self::default().and_is_null(field)
+    }
+
+    /// Match objects of which `field` is set.
+    pub fn is_not_null(field: &str) -> Self {
+        Self::default().and(Condition::IsNotNull(field.to_string()))
+    }
+
+    /// Also require `field` not to be set.
+    pub fn and_is_null(self, field: &str) -> Self {
+        self.and(Condition::IsNull(field.to_string()))
+    }
+
+    /// Also require the `condition`.
+    pub fn and(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Returns the conditions of the filter.
+    pub fn conditions(&self) -> &[Condition] {
+        &self.conditions
+    }
+}
+
+/// Storage of a single kind of asset, as svc-storage objects of type `O`
+/// holding data of type `D`.
+#[tonic::async_trait]
+pub trait ResourceStore<O: Send, D: Send>: Send + Sync {
+    /// Get the object with the given id.
+    async fn get_by_id(&self, id: &str) -> Result<O, Status>;
+
+    /// Get all objects matching the `filter`.
+    async fn search(&self, filter: &AssetFilter) -> Result<Vec<O>, Status>;
+
+    /// Store a new object, returning it with its generated id.
+    async fn insert(&self, data: D) -> Result<O, Status>;
+
+    /// Update the fields of an object listed in the `mask`.
+    async fn update(&self, id: &str, data: D, mask: Vec<String>) -> Result<(), Status>;
+
+    /// Permanently delete an object.
+    async fn delete(&self, id: &str) -> Result<(), Status>;
+
+    /// Mark an object as deleted by setting its `deleted_at`.
+    ///
+    /// Marked objects are still returned by [`get_by_id`](Self::get_by_id).
+    async fn soft_delete(&self, id: &str) -> Result<(), Status>;
+
+    /// Clear the `deleted_at` of an object marked as deleted.
+    async fn restore(&self, id: &str) -> Result<(), Status>;
+
+    /// Check if an object is marked as deleted.
+    async fn is_deleted(&self, id: &str) -> Result<bool, Status>;
+
+    /// Check if the store can be used.
+    async fn is_ready(&self) -> Result<(), Status>;
+}
+
+/// Storage of the assets managed by the service.
+#[tonic::async_trait]
+pub trait AssetStore: std::fmt::Debug + Send + Sync {
+    /// Returns the storage of the vehicles of the aircraft.
+    fn vehicles(&self) -> &dyn ResourceStore<vehicle::Object, vehicle::Data>;
+
+    /// Returns the storage of the vertiports.
+    fn vertiports(&self) -> &dyn ResourceStore<vertiport::Object, vertiport::Data>;
+
+    /// Returns the storage of the vertipads.
+    fn vertipads(&self) -> &dyn ResourceStore<vertipad::Object, vertipad::Data>;
+
+    /// Check if asset groups are stored, so the stored asset group is the
+    /// one which was last registered or updated.
+    fn stores_groups(&self) -> bool;
+
+    /// Get the asset group with the given id.
+    async fn get_group(&self, id: &str) -> Result<AssetGroup, Status>;
+
+    /// Store a new asset group.
+    async fn insert_group(&self, group: AssetGroup) -> Result<(), Status>;
+
+    /// Replace a stored asset group.
+    async fn update_group(&self, group: AssetGroup) -> Result<(), Status>;
+
+    /// Permanently delete an asset group.
+    async fn delete_group(&self, id: &str) -> Result<(), Status>;
+}
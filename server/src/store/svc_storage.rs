@@ -0,0 +1,181 @@
+//! Storage of the assets in svc-storage
+
+use super::{AssetFilter, AssetStore, Condition, ResourceStore};
+use crate::grpc::client::{storage_call, storage_read, GrpcClients};
+use crate::rest::structs::AssetGroup;
+use duplicate::duplicate_item;
+use svc_storage_client_grpc::prelude::*;
+use tonic::Status;
+
+/// Convert a filter to a svc-storage search filter.
+fn search_filter(filter: &AssetFilter) -> AdvancedSearchFilter {
+    filter
+        .conditions()
+        .iter()
+        .fold(None, |search: Option<AdvancedSearchFilter>, condition| {
+            Some(match (search, condition.clone()) {
+                (None, Condition::Equals(field, value)) => {
+                    AdvancedSearchFilter::search_equals(field, value)
+                }
+                (None, Condition::In(field, values)) => {
+                    AdvancedSearchFilter::search_in(field, values)
+                }
+                (None, Condition::IsNull(field)) => AdvancedSearchFilter::search_is_null(field),
+                (None, Condition::IsNotNull(field)) => {
+                    AdvancedSearchFilter::search_is_not_null(field)
+                }
+                (Some(search), Condition::Equals(field, value)) => search.and_equals(field, value),
+                (Some(search), Condition::In(field, values)) => search.and_in(field, values),
+                (Some(search), Condition::IsNull(field)) => search.and_is_null(field),
+                (Some(search), Condition::IsNotNull(field)) => search.and_is_not_null(field),
+            })
+        })
+        .unwrap_or_default()
+}
+
+/// Stores the assets in svc-storage, using the svc-storage gRPC clients.
+///
+/// Calls are made with the timeouts, retries and circuit breakers of the
+/// [`CallPolicy`](crate::grpc::policy::CallPolicy).
+#[derive(Clone, Debug)]
+pub struct SvcStorage {
+    grpc_clients: GrpcClients,
+}
+
+impl SvcStorage {
+    /// Create a store using the provided svc-storage clients.
+    pub fn new(grpc_clients: GrpcClients) -> Self {
+        Self { grpc_clients }
+    }
+}
+
+#[duplicate_item(
+    resource      id_field        module       object_type          data_type;
+    ["vehicle"]   ["vehicle_id"]   [vehicle]    [vehicle::Object]    [vehicle::Data];
+    ["vertiport"] ["vertiport_id"] [vertiport]  [vertiport::Object]  [vertiport::Data];
+    ["vertipad"]  ["vertipad_id"]  [vertipad]   [vertipad::Object]   [vertipad::Data];
+)]
+#[tonic::async_trait]
+impl ResourceStore<object_type, data_type> for SvcStorage {
+    async fn get_by_id(&self, id: &str) -> Result<object_type, Status> {
+        let client = &self.grpc_clients.storage.module;
+        storage_read(resource, "get_by_id", || {
+            client.get_by_id(Id { id: id.to_string() })
+        })
+        .await
+        .map(|response| response.into_inner())
+    }
+
+    async fn search(&self, filter: &AssetFilter) -> Result<Vec<object_type>, Status> {
+        let client = &self.grpc_clients.storage.module;
+        let filter = search_filter(filter);
+        storage_read(resource, "search", || client.search(filter.clone()))
+            .await
+            .map(|response| response.into_inner().list)
+    }
+
+    async fn insert(&self, data: data_type) -> Result<object_type, Status> {
+        let client = &self.grpc_clients.storage.module;
+        let response = storage_call(resource, "insert", client.insert(data))
+            .await?
+            .into_inner();
+        response.object.ok_or_else(|| {
+            Status::invalid_argument(format!(
+                "svc-storage rejected the {}: {:?}",
+                resource, response.validation_result
+            ))
+        })
+    }
+
+    async fn update(&self, id: &str, data: data_type, mask: Vec<String>) -> Result<(), Status> {
+        let client = &self.grpc_clients.storage.module;
+        storage_call(
+            resource,
+            "update",
+            client.update(module::UpdateObject {
+                id: id.to_string(),
+                data: Some(data),
+                mask: Some(FieldMask { paths: mask }),
+            }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    // TODO(R4): svc-storage only sets the deleted_at of deleted resources,
+    // their rows are kept
+    async fn delete(&self, _id: &str) -> Result<(), Status> {
+        Err(Status::unimplemented(format!(
+            "svc-storage can not permanently delete a {}",
+            resource
+        )))
+    }
+
+    // svc-storage deletes resources by setting their deleted_at
+    async fn soft_delete(&self, id: &str) -> Result<(), Status> {
+        let client = &self.grpc_clients.storage.module;
+        storage_call(resource, "delete", client.delete(Id { id: id.to_string() }))
+            .await
+            .map(|_| ())
+    }
+
+    // TODO(R4): svc-storage can not clear the deleted_at of resources yet
+    async fn restore(&self, _id: &str) -> Result<(), Status> {
+        Err(Status::unimplemented(format!(
+            "svc-storage can not restore a deleted {}",
+            resource
+        )))
+    }
+
+    async fn is_deleted(&self, id: &str) -> Result<bool, Status> {
+        let client = &self.grpc_clients.storage.module;
+        let filter = AdvancedSearchFilter::search_equals(id_field.to_string(), id.to_string())
+            .and_is_not_null("deleted_at".to_string());
+        storage_read(resource, "search", || client.search(filter.clone()))
+            .await
+            .map(|response| !response.into_inner().list.is_empty())
+    }
+
+    async fn is_ready(&self) -> Result<(), Status> {
+        let client = &self.grpc_clients.storage.module;
+        storage_call(resource, "is_ready", client.is_ready(ReadyRequest {}))
+            .await
+            .map(|_| ())
+    }
+}
+
+#[tonic::async_trait]
+impl AssetStore for SvcStorage {
+    fn vehicles(&self) -> &dyn ResourceStore<vehicle::Object, vehicle::Data> {
+        self
+    }
+
+    fn vertiports(&self) -> &dyn ResourceStore<vertiport::Object, vertiport::Data> {
+        self
+    }
+
+    fn vertipads(&self) -> &dyn ResourceStore<vertipad::Object, vertipad::Data> {
+        self
+    }
+
+    // TODO(R4): svc-storage does not store asset groups yet
+    fn stores_groups(&self) -> bool {
+        false
+    }
+
+    async fn get_group(&self, _id: &str) -> Result<AssetGroup, Status> {
+        Ok(AssetGroup::random())
+    }
+
+    async fn insert_group(&self, _group: AssetGroup) -> Result<(), Status> {
+        Ok(())
+    }
+
+    async fn update_group(&self, _group: AssetGroup) -> Result<(), Status> {
+        Ok(())
+    }
+
+    async fn delete_group(&self, _id: &str) -> Result<(), Status> {
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+//! MAVLink UDP listener
+//!
+//! Receives MAVLink messages and applies the ones describing the state of
+//! an aircraft to the [`TelemetryStore`]:
+//!  - `HEARTBEAT`: armed state and time of the last heartbeat
+//!  - `SYS_STATUS`: battery and onboard sensor state
+//!  - `GLOBAL_POSITION_INT`: position and heading
+
+use super::{AircraftTelemetry, TelemetryPosition, TelemetryStore};
+use crate::Config;
+use chrono::Utc;
+use mavlink::common::{MavMessage, MavModeFlag};
+use mavlink::MavHeader;
+use std::thread::JoinHandle;
+
+/// Apply a received message to the state of the sending aircraft.
+///
+/// Returns the updated state, or `None` if the message was ignored.
+pub fn apply_message(
+    store: &TelemetryStore,
+    header: &MavHeader,
+    message: &MavMessage,
+) -> Option<AircraftTelemetry> {
+    match message {
+        MavMessage::HEARTBEAT(data) => store.update(header.system_id, |telemetry| {
+            telemetry.armed = Some(
+                data.base_mode
+                    .contains(MavModeFlag::MAV_MODE_FLAG_SAFETY_ARMED),
+            );
+            telemetry.last_heartbeat = Some(Utc::now());
+        }),
+        MavMessage::SYS_STATUS(data) => store.update(header.system_id, |telemetry| {
+            // -1 means the remaining capacity is not estimated
+            telemetry.battery_remaining_percent = u8::try_from(data.battery_remaining).ok();
+            telemetry.battery_voltage_v = match data.voltage_battery {
+                u16::MAX => None,
+                millivolts => Some(millivolts as f64 / 1000.0),
+            };
+            let failing =
+                data.onboard_control_sensors_enabled & !data.onboard_control_sensors_health;
+            telemetry.failing_sensors = match failing.is_empty() {
+                true => None,
+                false => Some(format!("{:?}", failing)),
+            };
+        }),
+        MavMessage::GLOBAL_POSITION_INT(data) => store.update(header.system_id, |telemetry| {
+            telemetry.position = Some(TelemetryPosition {
+                latitude: data.lat as f64 / 1e7,
+                longitude: data.lon as f64 / 1e7,
+                altitude_meters: data.alt as f64 / 1000.0,
+                heading_degrees: match data.hdg {
+                    u16::MAX => None,
+                    centidegrees => Some(centidegrees as f64 / 100.0),
+                },
+            });
+        }),
+        _ => None,
+    }
+}
+
+/// Listen for MAVLink messages on the given address, for example
+/// `udpin:0.0.0.0:14550`.
+///
+/// MAVLink connections are blocking, so messages are received on a
+/// dedicated thread which runs until the process is stopped.
+pub fn listen(address: &str, store: TelemetryStore) -> Result<JoinHandle<()>, std::io::Error> {
+    let connection = mavlink::connect::<MavMessage>(address)?;
+
+    std::thread::Builder::new()
+        .name("telemetry".to_string())
+        .spawn(move || loop {
+            match connection.recv() {
+                Ok((header, message)) => {
+                    if let Some(telemetry) = apply_message(&store, &header, &message) {
+                        telemetry_debug!("(listen) {:?}", telemetry);
+                    }
+                }
+                Err(e) => {
+                    telemetry_debug!("(listen) could not read message: {:?}", e);
+                }
+            }
+        })
+}
+
+/// Starts the MAVLink listener and status monitor for the global
+/// [`TelemetryStore`](super::get_telemetry), if telemetry has been enabled
+/// in the configuration.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: Listens on a fixed port, covered by the listener tests.
+pub async fn listener_task(config: Config) {
+    if !config.telemetry_enabled {
+        telemetry_info!("(listener_task) telemetry disabled, not starting listener.");
+        return;
+    }
+
+    let address = format!("udpin:0.0.0.0:{}", config.telemetry_port_udp);
+    let store = super::get_telemetry().await.clone();
+    match listen(&address, store.clone()) {
+        Ok(_) => telemetry_info!(
+            "(listener_task) listening for MAVLink messages on [{}].",
+            address
+        ),
+        Err(e) => {
+            telemetry_error!(
+                "(listener_task) could not listen for MAVLink messages on [{}]: {}",
+                address,
+                e
+            );
+            return;
+        }
+    }
+
+    // Mark aircraft which stopped sending heartbeats as unavailable
+    super::status::monitor(store).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rest::structs::AssetStatus;
+    use mavlink::common::{
+        MavSysStatusSensor, GLOBAL_POSITION_INT_DATA, HEARTBEAT_DATA, SYS_STATUS_DATA,
+    };
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    const AIRCRAFT_ID: &str = "1b8d07d0-bd57-4a8b-8aea-c2d7e5e7c2a1";
+
+    fn store() -> TelemetryStore {
+        TelemetryStore::with_system_ids(HashMap::from([(1, AIRCRAFT_ID.to_string())]))
+    }
+
+    fn header(system_id: u8) -> MavHeader {
+        MavHeader {
+            system_id,
+            component_id: 1,
+            sequence: 0,
+        }
+    }
+
+    fn messages() -> Vec<MavMessage> {
+        vec![
+            MavMessage::HEARTBEAT(HEARTBEAT_DATA {
+                base_mode: MavModeFlag::MAV_MODE_FLAG_SAFETY_ARMED,
+                ..Default::default()
+            }),
+            MavMessage::SYS_STATUS(SYS_STATUS_DATA {
+                voltage_battery: 22_500,
+                battery_remaining: 75,
+                ..Default::default()
+            }),
+            MavMessage::GLOBAL_POSITION_INT(GLOBAL_POSITION_INT_DATA {
+                lat: 523_456_789,
+                lon: 45_678_901,
+                alt: 120_500,
+                hdg: 9_000,
+                ..Default::default()
+            }),
+        ]
+    }
+
+    fn assert_telemetry(telemetry: &AircraftTelemetry) {
+        assert_eq!(telemetry.aircraft_id, AIRCRAFT_ID);
+        assert_eq!(telemetry.armed, Some(true));
+        assert_eq!(telemetry.battery_remaining_percent, Some(75));
+        assert_eq!(telemetry.battery_voltage_v, Some(22.5));
+        assert_eq!(
+            telemetry.position,
+            Some(TelemetryPosition {
+                latitude: 52.3456789,
+                longitude: 4.5678901,
+                altitude_meters: 120.5,
+                heading_degrees: Some(90.0),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_message() {
+        crate::get_log_handle().await;
+        ut_info!("(test_apply_message) Start.");
+
+        let store = store();
+        for message in messages() {
+            assert!(apply_message(&store, &header(1), &message).is_some());
+            assert!(apply_message(&store, &header(2), &message).is_none());
+        }
+        assert_telemetry(&store.get(AIRCRAFT_ID).unwrap());
+
+        // Unknown battery values should not be reported
+        apply_message(
+            &store,
+            &header(1),
+            &MavMessage::SYS_STATUS(SYS_STATUS_DATA {
+                voltage_battery: u16::MAX,
+                battery_remaining: -1,
+                ..Default::default()
+            }),
+        );
+        let telemetry = store.get(AIRCRAFT_ID).unwrap();
+        assert_eq!(telemetry.battery_remaining_percent, None);
+        assert_eq!(telemetry.battery_voltage_v, None);
+        assert_eq!(telemetry.failing_sensors, None);
+        assert_eq!(telemetry.status, AssetStatus::Available);
+
+        // Unhealthy sensors ground the aircraft
+        apply_message(
+            &store,
+            &header(1),
+            &MavMessage::SYS_STATUS(SYS_STATUS_DATA {
+                onboard_control_sensors_present: MavSysStatusSensor::MAV_SYS_STATUS_SENSOR_GPS,
+                onboard_control_sensors_enabled: MavSysStatusSensor::MAV_SYS_STATUS_SENSOR_GPS,
+                battery_remaining: 75,
+                ..Default::default()
+            }),
+        );
+        let telemetry = store.get(AIRCRAFT_ID).unwrap();
+        assert!(telemetry.failing_sensors.is_some());
+        assert_eq!(telemetry.status, AssetStatus::Unavailable);
+
+        ut_info!("(test_apply_message) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_listen() {
+        crate::get_log_handle().await;
+        ut_info!("(test_listen) Start.");
+
+        // Find a free port to listen on
+        let port = std::net::UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let store = store();
+        listen(&format!("udpin:127.0.0.1:{}", port), store.clone()).unwrap();
+
+        let sender = mavlink::connect::<MavMessage>(&format!("udpout:127.0.0.1:{}", port)).unwrap();
+        for message in messages() {
+            sender.send(&header(1), &message).unwrap();
+        }
+
+        // Messages are received on a separate thread
+        let mut telemetry = None;
+        for _ in 0..50 {
+            telemetry = store.get(AIRCRAFT_ID).filter(|t| t.position.is_some());
+            if telemetry.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_telemetry(&telemetry.unwrap());
+
+        ut_info!("(test_listen) Success.");
+    }
+}
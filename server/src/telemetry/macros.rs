@@ -0,0 +1,4 @@
+//! log macro's for aircraft telemetry logging
+
+use lib_common::log_macros;
+log_macros!("telemetry");
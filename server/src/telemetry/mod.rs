@@ -0,0 +1,399 @@
+//! Aircraft Telemetry
+//! provides the live state of aircraft, derived from received MAVLink messages
+
+#[macro_use]
+pub mod macros;
+pub mod listener;
+pub mod status;
+
+use crate::events::{AssetAction, AssetKind, ChangeNotifier};
+use crate::rest::structs::AssetStatus;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::OnceCell;
+use utoipa::ToSchema;
+
+use status::{StatusChange, StatusReason, StatusRules};
+
+/// Number of status changes kept for each aircraft.
+const STATUS_HISTORY_SIZE: usize = 100;
+
+pub(crate) static TELEMETRY: OnceCell<TelemetryStore> = OnceCell::const_new();
+
+/// Returns TELEMETRY, a [`TelemetryStore`] configured using a Config object
+/// generated from environment variables.
+/// Initializes TELEMETRY if it hasn't been initialized yet.
+pub async fn get_telemetry() -> &'static TelemetryStore {
+    TELEMETRY
+        .get_or_init(|| async move {
+            let config = crate::Config::try_from_env().unwrap_or_default();
+            TelemetryStore::new(&config).with_notifier(crate::events::get_notifier().await.clone())
+        })
+        .await
+}
+
+/// Last reported position of an aircraft.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TelemetryPosition {
+    /// Latitude in degrees.
+    pub latitude: f64,
+    /// Longitude in degrees.
+    pub longitude: f64,
+    /// Altitude above mean sea level in meters.
+    pub altitude_meters: f64,
+    /// Heading in degrees, if known.
+    pub heading_degrees: Option<f64>,
+}
+
+/// Live state of an aircraft.
+///
+/// Fields are `None` until the message providing them has been received.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AircraftTelemetry {
+    /// The id of the [`Aircraft`](crate::rest::structs::Aircraft).
+    pub aircraft_id: String,
+    /// The MAVLink system id of the aircraft.
+    pub system_id: u8,
+    /// Whether the aircraft's motors are armed.
+    pub armed: Option<bool>,
+    /// Remaining battery capacity in percent.
+    pub battery_remaining_percent: Option<u8>,
+    /// Battery voltage in volts.
+    pub battery_voltage_v: Option<f64>,
+    /// Description of the enabled onboard sensors which are reported
+    /// unhealthy, `None` if all of them are healthy.
+    pub failing_sensors: Option<String>,
+    /// Last reported position.
+    pub position: Option<TelemetryPosition>,
+    /// Time at which the last heartbeat of the aircraft has been received.
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    /// Time at which the last message of the aircraft has been received.
+    pub last_seen: DateTime<Utc>,
+    /// Status of the aircraft derived from its telemetry.
+    pub status: AssetStatus,
+    /// The reasons for the aircraft to be unavailable, empty if available.
+    pub status_reasons: Vec<StatusReason>,
+}
+
+impl AircraftTelemetry {
+    fn new(aircraft_id: &str, system_id: u8) -> Self {
+        Self {
+            aircraft_id: aircraft_id.to_string(),
+            system_id,
+            armed: None,
+            battery_remaining_percent: None,
+            battery_voltage_v: None,
+            failing_sensors: None,
+            position: None,
+            last_heartbeat: None,
+            last_seen: Utc::now(),
+            status: AssetStatus::Available,
+            status_reasons: vec![],
+        }
+    }
+}
+
+/// Parse a comma separated list of `system_id=aircraft_id` pairs.
+///
+/// Invalid entries are logged and skipped.
+pub fn parse_system_ids(value: &str) -> HashMap<u8, String> {
+    let mut system_ids = HashMap::new();
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let Some((system_id, aircraft_id)) = entry.split_once('=') else {
+            telemetry_warn!("(parse_system_ids) ignoring invalid entry [{}].", entry);
+            continue;
+        };
+
+        let aircraft_id = aircraft_id.trim();
+        match system_id.trim().parse::<u8>() {
+            Ok(system_id) if uuid::Uuid::try_parse(aircraft_id).is_ok() => {
+                system_ids.insert(system_id, aircraft_id.to_string());
+            }
+            _ => {
+                telemetry_warn!("(parse_system_ids) ignoring invalid entry [{}].", entry);
+            }
+        }
+    }
+
+    system_ids
+}
+
+#[derive(Debug, Default)]
+struct TelemetryState {
+    aircraft: HashMap<String, AircraftTelemetry>,
+    history: HashMap<String, VecDeque<StatusChange>>,
+}
+
+/// Keeps the live state of all aircraft telemetry has been received for.
+///
+/// MAVLink system ids are correlated to aircraft using a fixed mapping,
+/// messages from unknown systems are ignored.
+///
+/// The status of an aircraft is re-evaluated using the [`StatusRules`]
+/// on every update. Status changes are recorded in the status history
+/// and emitted as [`AssetAction::Updated`] events.
+#[derive(Clone, Debug)]
+pub struct TelemetryStore {
+    system_ids: Arc<HashMap<u8, String>>,
+    rules: StatusRules,
+    state: Arc<Mutex<TelemetryState>>,
+    notifier: Option<ChangeNotifier>,
+}
+
+impl TelemetryStore {
+    /// Create a new [`TelemetryStore`] using the provided configuration.
+    pub fn new(config: &crate::Config) -> Self {
+        Self::with_system_ids(parse_system_ids(&config.telemetry_system_ids))
+            .with_rules(StatusRules::new(config))
+    }
+
+    /// Create a new [`TelemetryStore`] using the provided mapping of
+    /// MAVLink system ids to aircraft ids.
+    pub fn with_system_ids(system_ids: HashMap<u8, String>) -> Self {
+        Self {
+            system_ids: Arc::new(system_ids),
+            rules: StatusRules::default(),
+            state: Arc::new(Mutex::new(TelemetryState::default())),
+            notifier: None,
+        }
+    }
+
+    /// Set the rules used to decide whether an aircraft is available.
+    pub fn with_rules(mut self, rules: StatusRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Emit status changes through the provided [`ChangeNotifier`].
+    pub fn with_notifier(mut self, notifier: ChangeNotifier) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TelemetryState> {
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Returns the id of the aircraft with the given MAVLink system id.
+    pub fn aircraft_id(&self, system_id: u8) -> Option<&str> {
+        self.system_ids.get(&system_id).map(String::as_str)
+    }
+
+    /// Update the state of the aircraft with the given MAVLink system id.
+    ///
+    /// Returns the updated state, or `None` if the system id is unknown.
+    pub fn update<F>(&self, system_id: u8, update: F) -> Option<AircraftTelemetry>
+    where
+        F: FnOnce(&mut AircraftTelemetry),
+    {
+        let Some(aircraft_id) = self.aircraft_id(system_id) else {
+            telemetry_debug!(
+                "(update) ignoring message of unknown system [{}].",
+                system_id
+            );
+            return None;
+        };
+
+        let now = Utc::now();
+        let mut state = self.lock();
+        let telemetry = state
+            .aircraft
+            .entry(aircraft_id.to_string())
+            .or_insert_with(|| AircraftTelemetry::new(aircraft_id, system_id));
+        update(telemetry);
+        telemetry.last_seen = now;
+
+        self.apply_rules(&mut state, aircraft_id, now);
+        state.aircraft.get(aircraft_id).cloned()
+    }
+
+    /// Re-evaluate the status of all aircraft, returning the changes.
+    pub fn check_all(&self, now: DateTime<Utc>) -> Vec<StatusChange> {
+        let mut state = self.lock();
+        let aircraft_ids: Vec<String> = state.aircraft.keys().cloned().collect();
+        aircraft_ids
+            .iter()
+            .filter_map(|aircraft_id| self.apply_rules(&mut state, aircraft_id, now))
+            .collect()
+    }
+
+    fn apply_rules(
+        &self,
+        state: &mut TelemetryState,
+        aircraft_id: &str,
+        now: DateTime<Utc>,
+    ) -> Option<StatusChange> {
+        let telemetry = state.aircraft.get_mut(aircraft_id)?;
+        let reasons = self.rules.evaluate(telemetry, now);
+        if reasons == telemetry.status_reasons {
+            return None;
+        }
+
+        let status = match reasons.is_empty() {
+            true => AssetStatus::Available,
+            false => AssetStatus::Unavailable,
+        };
+        telemetry.status = status;
+        telemetry.status_reasons = reasons.clone();
+
+        let change = StatusChange {
+            aircraft_id: aircraft_id.to_string(),
+            status,
+            reasons,
+            timestamp: now,
+        };
+        telemetry_info!("(apply_rules) status changed: {:?}", change);
+
+        let history = state.history.entry(aircraft_id.to_string()).or_default();
+        if history.len() >= STATUS_HISTORY_SIZE {
+            history.pop_front();
+        }
+        history.push_back(change.clone());
+
+        if let Some(notifier) = &self.notifier {
+            notifier.emit(
+                AssetKind::Aircraft,
+                AssetAction::Updated,
+                aircraft_id,
+                serde_json::to_value(&change).ok(),
+            );
+        }
+
+        Some(change)
+    }
+
+    /// Returns the live state of the aircraft with the given id, if any
+    /// telemetry has been received for it.
+    pub fn get(&self, aircraft_id: &str) -> Option<AircraftTelemetry> {
+        self.lock().aircraft.get(aircraft_id).cloned()
+    }
+
+    /// Returns the status of the aircraft with the given id derived from
+    /// its telemetry, if any telemetry has been received for it.
+    pub fn status(&self, aircraft_id: &str) -> Option<AssetStatus> {
+        self.lock()
+            .aircraft
+            .get(aircraft_id)
+            .map(|telemetry| telemetry.status)
+    }
+
+    /// Returns the recorded status changes of the aircraft with the given
+    /// id, oldest first.
+    pub fn status_history(&self, aircraft_id: &str) -> Vec<StatusChange> {
+        self.lock()
+            .history
+            .get(aircraft_id)
+            .map(|history| history.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AIRCRAFT_ID: &str = "1b8d07d0-bd57-4a8b-8aea-c2d7e5e7c2a1";
+
+    #[test]
+    fn test_parse_system_ids() {
+        let system_ids = parse_system_ids(&format!(
+            "1={id}, 2 = {id},invalid,300={id},4=not-a-uuid,",
+            id = AIRCRAFT_ID
+        ));
+        assert_eq!(system_ids.len(), 2);
+        assert_eq!(system_ids.get(&1).unwrap(), AIRCRAFT_ID);
+        assert_eq!(system_ids.get(&2).unwrap(), AIRCRAFT_ID);
+
+        assert!(parse_system_ids("").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_telemetry_store_update() {
+        crate::get_log_handle().await;
+        ut_info!("(test_telemetry_store_update) Start.");
+
+        let store = TelemetryStore::with_system_ids(HashMap::from([(1, AIRCRAFT_ID.to_string())]));
+        assert!(store.get(AIRCRAFT_ID).is_none());
+
+        // Messages of unknown systems are ignored
+        assert!(store.update(2, |t| t.armed = Some(true)).is_none());
+        assert!(store.get(AIRCRAFT_ID).is_none());
+
+        let telemetry = store.update(1, |t| t.armed = Some(true)).unwrap();
+        assert_eq!(telemetry.aircraft_id, AIRCRAFT_ID);
+        assert_eq!(telemetry.system_id, 1);
+        assert_eq!(telemetry.armed, Some(true));
+
+        store.update(1, |t| t.battery_remaining_percent = Some(80));
+        let telemetry = store.get(AIRCRAFT_ID).unwrap();
+        assert_eq!(telemetry.armed, Some(true));
+        assert_eq!(telemetry.battery_remaining_percent, Some(80));
+        assert_eq!(telemetry.status, AssetStatus::Available);
+        assert!(store.status_history(AIRCRAFT_ID).is_empty());
+
+        ut_info!("(test_telemetry_store_update) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_telemetry_store_status() {
+        crate::get_log_handle().await;
+        ut_info!("(test_telemetry_store_status) Start.");
+
+        let notifier = ChangeNotifier::with_outbox(None);
+        let store = TelemetryStore::with_system_ids(HashMap::from([(1, AIRCRAFT_ID.to_string())]))
+            .with_notifier(notifier.clone());
+        let mut events = Box::pin(notifier.subscribe(crate::events::AssetEventFilter::default()));
+
+        store.update(1, |t| t.last_heartbeat = Some(Utc::now()));
+        assert_eq!(store.status(AIRCRAFT_ID), Some(AssetStatus::Available));
+
+        // Low battery grounds the aircraft
+        store.update(1, |t| t.battery_remaining_percent = Some(10));
+        assert_eq!(store.status(AIRCRAFT_ID), Some(AssetStatus::Unavailable));
+
+        // Heartbeat lost while the battery is still low, adds a reason
+        let changes = store.check_all(Utc::now() + chrono::Duration::seconds(60));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].reasons,
+            vec![StatusReason::HeartbeatLost, StatusReason::LowBattery]
+        );
+
+        // Back to healthy
+        store.update(1, |t| {
+            t.last_heartbeat = Some(Utc::now());
+            t.battery_remaining_percent = Some(90);
+        });
+        assert_eq!(store.status(AIRCRAFT_ID), Some(AssetStatus::Available));
+        assert!(store.check_all(Utc::now()).is_empty());
+
+        let history = store.status_history(AIRCRAFT_ID);
+        let statuses: Vec<AssetStatus> = history.iter().map(|change| change.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                AssetStatus::Unavailable,
+                AssetStatus::Unavailable,
+                AssetStatus::Available
+            ]
+        );
+        assert_eq!(history[0].reasons, vec![StatusReason::LowBattery]);
+        assert!(history[2].reasons.is_empty());
+
+        // Every change is emitted as an event
+        use futures::StreamExt;
+        for change in history {
+            let event = events.next().await.unwrap().unwrap();
+            assert_eq!(event.asset_id, AIRCRAFT_ID);
+            assert_eq!(event.action, AssetAction::Updated);
+            assert_eq!(event.data, serde_json::to_value(&change).ok());
+        }
+
+        ut_info!("(test_telemetry_store_status) Success.");
+    }
+}
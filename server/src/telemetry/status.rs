@@ -0,0 +1,147 @@
+//! Aircraft status rules
+//!
+//! Aircraft are marked [`AssetStatus::Unavailable`] while their telemetry
+//! reports a problem, and [`AssetStatus::Available`] again once all
+//! problems have been resolved. Every change is recorded in the status
+//! history of the aircraft, together with the reasons for the change.
+
+use super::{AircraftTelemetry, TelemetryStore};
+use crate::rest::structs::AssetStatus;
+use crate::Config;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Interval at which the heartbeats of all aircraft are checked.
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Reason for an aircraft to be unavailable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum StatusReason {
+    /// No heartbeat has been received within the configured timeout.
+    HeartbeatLost,
+    /// One or more enabled onboard sensors are reported unhealthy.
+    SensorFailure,
+    /// The remaining battery capacity is below the configured minimum.
+    LowBattery,
+}
+
+/// A change of the status of an aircraft.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct StatusChange {
+    /// The id of the [`Aircraft`](crate::rest::structs::Aircraft).
+    pub aircraft_id: String,
+    /// The new status of the aircraft.
+    pub status: AssetStatus,
+    /// The reasons for the aircraft to be unavailable, empty if available.
+    pub reasons: Vec<StatusReason>,
+    /// Time at which the change was detected.
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Rules deciding whether an aircraft is available based on its telemetry.
+#[derive(Clone, Debug)]
+pub struct StatusRules {
+    /// Time after the last heartbeat at which the aircraft is considered lost.
+    pub heartbeat_timeout: Duration,
+    /// Minimum remaining battery capacity in percent.
+    pub min_battery_percent: u8,
+}
+
+impl Default for StatusRules {
+    fn default() -> Self {
+        Self {
+            heartbeat_timeout: Duration::seconds(10),
+            min_battery_percent: 20,
+        }
+    }
+}
+
+impl StatusRules {
+    /// Create new [`StatusRules`] using the provided configuration.
+    pub fn new(config: &Config) -> Self {
+        Self {
+            heartbeat_timeout: Duration::seconds(config.telemetry_heartbeat_timeout_seconds as i64),
+            min_battery_percent: config.telemetry_min_battery_percent,
+        }
+    }
+
+    /// Returns the reasons for the aircraft to be unavailable at `now`.
+    ///
+    /// Values which have not been reported yet are not evaluated.
+    pub fn evaluate(&self, telemetry: &AircraftTelemetry, now: DateTime<Utc>) -> Vec<StatusReason> {
+        let mut reasons = vec![];
+
+        if let Some(last_heartbeat) = telemetry.last_heartbeat {
+            if now - last_heartbeat > self.heartbeat_timeout {
+                reasons.push(StatusReason::HeartbeatLost);
+            }
+        }
+
+        if telemetry.failing_sensors.is_some() {
+            reasons.push(StatusReason::SensorFailure);
+        }
+
+        if let Some(battery) = telemetry.battery_remaining_percent {
+            if battery < self.min_battery_percent {
+                reasons.push(StatusReason::LowBattery);
+            }
+        }
+
+        reasons
+    }
+}
+
+/// Periodically checks the status of all aircraft, so aircraft which
+/// stopped sending messages are marked unavailable.
+///
+/// Runs until the process is stopped.
+pub async fn monitor(store: TelemetryStore) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        for change in store.check_all(Utc::now()) {
+            telemetry_info!(
+                "(monitor) aircraft [{}] is now {:?}: {:?}",
+                change.aircraft_id,
+                change.status,
+                change.reasons
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn telemetry() -> AircraftTelemetry {
+        AircraftTelemetry::new("aircraft", 1)
+    }
+
+    #[test]
+    fn test_status_rules_evaluate() {
+        let rules = StatusRules::default();
+        let now = Utc::now();
+
+        // Nothing reported yet
+        let mut telemetry = telemetry();
+        assert!(rules.evaluate(&telemetry, now).is_empty());
+
+        telemetry.last_heartbeat = Some(now - Duration::seconds(5));
+        telemetry.battery_remaining_percent = Some(20);
+        assert!(rules.evaluate(&telemetry, now).is_empty());
+
+        telemetry.last_heartbeat = Some(now - Duration::seconds(11));
+        telemetry.battery_remaining_percent = Some(19);
+        telemetry.failing_sensors = Some("MAV_SYS_STATUS_SENSOR_GPS".to_string());
+        assert_eq!(
+            rules.evaluate(&telemetry, now),
+            vec![
+                StatusReason::HeartbeatLost,
+                StatusReason::SensorFailure,
+                StatusReason::LowBattery
+            ]
+        );
+    }
+}
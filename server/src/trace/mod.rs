@@ -0,0 +1,223 @@
+//! Distributed Tracing
+//! records spans of requests and svc-storage calls
+//!
+//! Spans are recorded with the `tracing` crate. Incoming REST and gRPC
+//! requests continue the trace of their W3C `traceparent` header, or start
+//! a new trace, and the spans of the svc-storage calls made while handling
+//! a request become children of the request's span. If `OTLP_ENDPOINT` is
+//! configured, spans are exported to an OpenTelemetry collector through
+//! `tracing-opentelemetry` and `opentelemetry-otlp`.
+
+#[macro_use]
+pub mod macros;
+
+use crate::Config;
+use axum::http::HeaderMap;
+use opentelemetry::propagation::{Extractor, TextMapPropagator};
+use opentelemetry::trace::TraceError;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::{runtime, trace as sdktrace, Resource};
+use tonic::metadata::{KeyRef, MetadataMap};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+
+/// Name of the W3C trace context header.
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Name of the service reported to the collector.
+const SERVICE_NAME: &str = "svc-assets";
+
+/// Start exporting spans to the collector at `OTLP_ENDPOINT`, such as
+/// `http://otel-collector:4318`, over OTLP/HTTP.
+///
+/// Spans are not recorded if no endpoint is configured. Must be called
+/// once, from within a tokio runtime.
+pub fn init_tracing(config: &Config) -> Result<(), TraceError> {
+    if config.otlp_endpoint.is_empty() {
+        trace_info!("(init_tracing) no OTLP endpoint configured, spans are not exported.");
+        return Ok(());
+    }
+
+    let exporter = opentelemetry_otlp::new_exporter()
+        .http()
+        .with_endpoint(config.otlp_endpoint.trim_end_matches('/'));
+    let tracer =
+        opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(exporter)
+            .with_trace_config(sdktrace::config().with_resource(Resource::new(vec![
+                KeyValue::new("service.name", SERVICE_NAME),
+            ])))
+            .install_batch(runtime::Tokio)?;
+
+    // Events of the dependencies below info level would be added to the spans
+    let subscriber = tracing_subscriber::registry()
+        .with(LevelFilter::INFO)
+        .with(tracing_opentelemetry::layer().with_tracer(tracer));
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|e| TraceError::Other(Box::new(e)))?;
+
+    trace_info!(
+        "(init_tracing) exporting spans to [{}].",
+        config.otlp_endpoint
+    );
+    Ok(())
+}
+
+/// Export the spans which are still waiting, before shutting down.
+pub fn shutdown_tracing() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+/// Reads the trace context from the headers of a REST request.
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// Reads the trace context from the metadata of a gRPC request.
+struct MetadataExtractor<'a>(&'a MetadataMap);
+
+impl Extractor for MetadataExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0
+            .keys()
+            .filter_map(|key| match key {
+                KeyRef::Ascii(key) => Some(key.as_str()),
+                KeyRef::Binary(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// Make `span` continue the trace of the `traceparent` header of a REST
+/// request, if valid.
+///
+/// Must be called before the span is entered.
+pub fn continue_from_headers(span: &tracing::Span, headers: &HeaderMap) {
+    span.set_parent(TraceContextPropagator::new().extract(&HeaderExtractor(headers)));
+}
+
+/// Make `span` continue the trace of the `traceparent` metadata of a gRPC
+/// request, if valid.
+///
+/// Must be called before the span is entered.
+pub fn continue_from_metadata(span: &tracing::Span, metadata: &MetadataMap) {
+    span.set_parent(TraceContextPropagator::new().extract(&MetadataExtractor(metadata)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::client::storage_call;
+    use opentelemetry::trace::{SpanKind, Status, TraceId, TracerProvider as _};
+    use opentelemetry_sdk::testing::trace::InMemorySpanExporter;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use tracing::Instrument;
+
+    /// Record a request with a `traceparent` header making a svc-storage
+    /// call, returning the exported spans.
+    async fn record_request(traceparent: &str) -> Vec<opentelemetry_sdk::export::trace::SpanData> {
+        let exporter = InMemorySpanExporter::default();
+        let provider = TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(TRACEPARENT_HEADER, traceparent.parse().unwrap());
+        let span = tracing::info_span!(
+            "request",
+            otel.name = "GET /assets/aircraft/:id",
+            otel.kind = "server"
+        );
+        continue_from_headers(&span, &headers);
+        let result: Result<(), tonic::Status> = storage_call("test_trace", "get_by_id", async {
+            Err(tonic::Status::unavailable("connection refused"))
+        })
+        .instrument(span)
+        .await;
+        assert!(result.is_err());
+
+        // Spans are exported in the background
+        provider.force_flush();
+        exporter.get_finished_spans().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_spans() {
+        crate::get_log_handle().await;
+        ut_info!("(test_spans) Start.");
+
+        let spans = record_request("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").await;
+        assert_eq!(spans.len(), 2);
+        let (client, server) = (&spans[0], &spans[1]);
+        let trace_id = TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap();
+
+        assert_eq!(server.name, "GET /assets/aircraft/:id");
+        assert_eq!(server.span_kind, SpanKind::Server);
+        assert_eq!(server.span_context.trace_id(), trace_id);
+        assert_eq!(format!("{}", server.parent_span_id), "00f067aa0ba902b7");
+
+        assert_eq!(client.name, "svc-storage test_trace/get_by_id");
+        assert_eq!(client.span_kind, SpanKind::Client);
+        assert_eq!(client.span_context.trace_id(), trace_id);
+        assert_eq!(client.parent_span_id, server.span_context.span_id());
+        assert!(matches!(client.status, Status::Error { .. }));
+
+        // Spans of unsampled traces are not exported
+        let spans = record_request("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00").await;
+        assert!(spans.is_empty());
+
+        ut_info!("(test_spans) Success.");
+    }
+
+    #[tokio::test]
+    async fn test_continue_from_metadata() {
+        crate::get_log_handle().await;
+        ut_info!("(test_continue_from_metadata) Start.");
+
+        let mut metadata = MetadataMap::new();
+        metadata.insert(
+            TRACEPARENT_HEADER,
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+                .parse()
+                .unwrap(),
+        );
+        let context = TraceContextPropagator::new().extract(&MetadataExtractor(&metadata));
+        let span_context = opentelemetry::trace::TraceContextExt::span(&context)
+            .span_context()
+            .clone();
+        assert!(span_context.is_remote());
+        assert!(span_context.is_sampled());
+        assert_eq!(
+            span_context.trace_id(),
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap()
+        );
+
+        // Invalid trace contexts are ignored
+        metadata.insert(TRACEPARENT_HEADER, "00-invalid-01".parse().unwrap());
+        let context = TraceContextPropagator::new().extract(&MetadataExtractor(&metadata));
+        assert!(!opentelemetry::trace::TraceContextExt::has_active_span(
+            &context
+        ));
+
+        ut_info!("(test_continue_from_metadata) Success.");
+    }
+}
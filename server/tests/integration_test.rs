@@ -23,6 +23,11 @@ async fn test_server_requests_and_logs() {
 
     //test_is_ready_request_logs
     {
+        // Ready once the (stubbed) dependencies have been checked
+        svc_assets::health::get_health_monitor()
+            .await
+            .refresh()
+            .await;
         let imp = ServerImpl::default();
         let result = imp.is_ready(tonic::Request::new(ReadyRequest {})).await;
         assert!(result.is_ok());
@@ -0,0 +1,94 @@
+//! REST API Tests
+//! running the handlers against the in-memory asset store
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use lib_common::log_macros;
+use svc_storage_client_grpc::prelude::*;
+use tower::ServiceExt;
+
+log_macros!("it", "test");
+
+/// Send a request to the REST API, returning the status and body.
+async fn send(
+    app: &axum::Router,
+    method: Method,
+    uri: &str,
+    body: Option<serde_json::Value>,
+) -> (StatusCode, String) {
+    let request = Request::builder().method(method).uri(uri);
+    let request = match body {
+        Some(body) => request
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+/// Returns the ids of the assets listed by `uri`.
+async fn list_ids(app: &axum::Router, uri: &str) -> Vec<String> {
+    let (status, body) = send(app, Method::GET, uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let assets: Vec<serde_json::Value> = serde_json::from_str(&body).unwrap();
+    assets
+        .iter()
+        .filter_map(|asset| asset["id"].as_str().map(String::from))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_vertiport_lifecycle() {
+    it_info!("(test_vertiport_lifecycle) Start.");
+
+    // The store is selected from the environment on first use
+    std::env::set_var("STORAGE_BACKEND", "memory");
+    let config = svc_assets::Config {
+        storage_backend: String::from("memory"),
+        ..Default::default()
+    };
+    let app = svc_assets::rest::server::router(&config).await.unwrap();
+
+    // Register
+    let data = serde_json::to_value(vertiport::mock::get_data_obj()).unwrap();
+    let (status, id) = send(&app, Method::POST, "/assets/vertiports", Some(data)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let uri = format!("/assets/vertiports/{}", id);
+    let (status, body) = send(&app, Method::GET, &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let vertiport: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(vertiport["id"], id.as_str());
+    assert!(list_ids(&app, "/assets/demo/vertiports")
+        .await
+        .contains(&id));
+
+    // Removed vertiports are hidden
+    let (status, body) = send(&app, Method::DELETE, &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, id);
+    let (status, _) = send(&app, Method::GET, &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, Method::DELETE, &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(!list_ids(&app, "/assets/demo/vertiports")
+        .await
+        .contains(&id));
+
+    // Until they are restored
+    let restore_uri = format!("{}/restore", uri);
+    let (status, body) = send(&app, Method::POST, &restore_uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, id);
+    let (status, _) = send(&app, Method::GET, &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(list_ids(&app, "/assets/demo/vertiports")
+        .await
+        .contains(&id));
+
+    it_info!("(test_vertiport_lifecycle) Success.");
+}